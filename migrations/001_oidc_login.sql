@@ -0,0 +1,31 @@
+-- 第三方身份表（OpenID Connect）
+CREATE TABLE
+  user_identities (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    UNIQUE (issuer, subject)
+  );
+
+-- OpenID Connect 登录中间状态
+CREATE TABLE
+  oidc_login_states (
+    state TEXT PRIMARY KEY,
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    link_user_id INTEGER,
+    invite_code TEXT,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (link_user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
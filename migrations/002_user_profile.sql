@@ -0,0 +1,8 @@
+-- 用户资料：显示名称、头像、时区与语言
+ALTER TABLE users ADD COLUMN display_name TEXT;
+
+ALTER TABLE users ADD COLUMN avatar_path TEXT;
+
+ALTER TABLE users ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
+
+ALTER TABLE users ADD COLUMN locale TEXT NOT NULL DEFAULT 'zh-CN';
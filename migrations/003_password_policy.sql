@@ -0,0 +1,12 @@
+-- 密码策略设置
+ALTER TABLE settings ADD COLUMN password_min_length INTEGER NOT NULL DEFAULT 6;
+
+ALTER TABLE settings ADD COLUMN password_require_uppercase BOOLEAN NOT NULL DEFAULT 0;
+
+ALTER TABLE settings ADD COLUMN password_require_lowercase BOOLEAN NOT NULL DEFAULT 0;
+
+ALTER TABLE settings ADD COLUMN password_require_digit BOOLEAN NOT NULL DEFAULT 0;
+
+ALTER TABLE settings ADD COLUMN password_require_symbol BOOLEAN NOT NULL DEFAULT 0;
+
+ALTER TABLE settings ADD COLUMN password_check_breached BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- 书籍文件大小，已有书籍在升级后按文件实际大小补充
+ALTER TABLE books ADD COLUMN file_size INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- 用户状态、上传配额与最近活动时间
+ALTER TABLE users ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+
+ALTER TABLE users ADD COLUMN storage_quota INTEGER;
+
+ALTER TABLE users ADD COLUMN book_quota INTEGER;
+
+ALTER TABLE users ADD COLUMN last_active_at TEXT;
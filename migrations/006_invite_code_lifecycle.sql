@@ -0,0 +1,19 @@
+-- 邀请码过期时间、撤销状态与使用记录
+ALTER TABLE invite_codes ADD COLUMN expires_at TEXT;
+
+ALTER TABLE invite_codes ADD COLUMN revoked BOOLEAN NOT NULL DEFAULT 0;
+
+-- 邀请码使用记录
+CREATE TABLE
+  invite_code_uses (
+    id INTEGER PRIMARY KEY,
+    invite_code_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    used_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (invite_code_id) REFERENCES invite_codes (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
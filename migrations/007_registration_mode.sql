@@ -0,0 +1,14 @@
+-- 是否需要邀请码改为注册模式
+ALTER TABLE settings ADD COLUMN registration_mode TEXT NOT NULL DEFAULT 'open' CHECK (
+  registration_mode IN ('open', 'invite', 'closed', 'approval')
+);
+
+UPDATE settings
+SET
+  registration_mode = CASE
+    WHEN invite_code_required THEN 'invite'
+    ELSE 'open'
+  END;
+
+ALTER TABLE settings
+DROP COLUMN invite_code_required;
@@ -0,0 +1,9 @@
+-- 每日阅读时长统计
+CREATE TABLE
+  reading_daily_stats (
+    user_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    reading_time INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, date)
+  );
@@ -0,0 +1,34 @@
+-- 审计日志表，只允许追加
+CREATE TABLE
+  audit_log (
+    id INTEGER PRIMARY KEY,
+    actor_type TEXT NOT NULL,
+    actor_id INTEGER,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id INTEGER,
+    detail TEXT,
+    ip TEXT,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    )
+  );
+
+CREATE INDEX idx_audit_log_action ON audit_log (action);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log (created_at);
+
+CREATE TRIGGER audit_log_no_update BEFORE
+UPDATE ON audit_log BEGIN
+SELECT
+  RAISE (ABORT, 'audit_log is append-only');
+
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log BEGIN
+SELECT
+  RAISE (ABORT, 'audit_log is append-only');
+
+END;
@@ -0,0 +1,67 @@
+-- 用户角色：原管理员表中的管理员并入用户表
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'uploader' CHECK (
+  role IN ('admin', 'moderator', 'uploader', 'reader')
+);
+
+-- 优先使用 admin，被占用时依次尝试 admin_legacy、admin_legacy_2……，直到找到未被使用的用户名
+WITH RECURSIVE
+  candidates (n, username) AS (
+    SELECT
+      0,
+      'admin'
+    UNION ALL
+    SELECT
+      n + 1,
+      CASE
+        WHEN n = 0 THEN 'admin_legacy'
+        ELSE 'admin_legacy_' || (n + 1)
+      END
+    FROM
+      candidates
+    WHERE
+      username IN (
+        SELECT
+          username
+        FROM
+          users
+      )
+  )
+INSERT INTO
+  users (username, password_hash, role, created_at)
+SELECT
+  (
+    SELECT
+      username
+    FROM
+      candidates
+    ORDER BY
+      n DESC
+    LIMIT
+      1
+  ),
+  password_hash,
+  'admin',
+  created_at
+FROM
+  admin
+ORDER BY
+  id
+LIMIT
+  1;
+
+INSERT INTO
+  reading_settings (user_id)
+SELECT
+  id
+FROM
+  users
+WHERE
+  role = 'admin'
+  AND id NOT IN (
+    SELECT
+      user_id
+    FROM
+      reading_settings
+  );
+
+DROP TABLE admin;
@@ -0,0 +1,31 @@
+-- 维护模式与站点公告
+ALTER TABLE settings ADD COLUMN maintenance_mode BOOLEAN NOT NULL DEFAULT 0;
+
+ALTER TABLE settings ADD COLUMN maintenance_message TEXT NOT NULL DEFAULT '';
+
+-- 站点公告表
+CREATE TABLE
+  announcements (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    starts_at TEXT,
+    ends_at TEXT,
+    created_by INTEGER,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+  );
+
+-- 用户已关闭的公告
+CREATE TABLE
+  announcement_dismissals (
+    user_id INTEGER NOT NULL,
+    announcement_id INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (announcement_id) REFERENCES announcements (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, announcement_id)
+  );
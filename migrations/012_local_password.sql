@@ -0,0 +1,3 @@
+-- 是否设置了本地密码，反向代理与 OIDC 自动创建的用户没有本地密码
+-- 升级前创建的用户无法区分，一律视为有密码，修改密码与注销账号时必须提供密码
+ALTER TABLE users ADD COLUMN has_password BOOLEAN NOT NULL DEFAULT 1;
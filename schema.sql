@@ -1,9 +1,25 @@
 -- 用户表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   users (
     id INTEGER PRIMARY KEY,
     username TEXT NOT NULL UNIQUE,
     password_hash TEXT NOT NULL,
+    -- 是否设置了本地密码，反向代理与 OIDC 自动创建的用户为 0
+    has_password BOOLEAN NOT NULL DEFAULT 1,
+    display_name TEXT,
+    avatar_path TEXT,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    locale TEXT NOT NULL DEFAULT 'zh-CN',
+    -- 角色: admin 管理员 / moderator 版主 / uploader 可上传书籍 / reader 只读
+    role TEXT NOT NULL DEFAULT 'uploader' CHECK (
+      role IN ('admin', 'moderator', 'uploader', 'reader')
+    ),
+    -- 账号状态: active / disabled / pending（等待审核）
+    status TEXT NOT NULL DEFAULT 'active',
+    -- 存储空间配额（字节）与书籍数量配额，NULL 表示不限制
+    storage_quota INTEGER,
+    book_quota INTEGER,
+    last_active_at TEXT,
     created_at TEXT NOT NULL DEFAULT (
       strftime (
         '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
@@ -12,33 +28,97 @@ CREATE TABLE
     total_reading_time INTEGER NOT NULL DEFAULT 0
   );
 
--- 管理员表
-CREATE TABLE
-  admin (
+-- 第三方身份表（OpenID Connect）
+CREATE TABLE IF NOT EXISTS
+  user_identities (
     id INTEGER PRIMARY KEY,
-    password_hash TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
     created_at TEXT NOT NULL DEFAULT (
       strftime (
         '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
       )
-    )
+    ),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    UNIQUE (issuer, subject)
+  );
+
+-- OpenID Connect 登录中间状态
+CREATE TABLE IF NOT EXISTS
+  oidc_login_states (
+    state TEXT PRIMARY KEY,
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    link_user_id INTEGER,
+    invite_code TEXT,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (link_user_id) REFERENCES users (id) ON DELETE CASCADE
   );
 
 -- 系统设置表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   settings (
     id INTEGER PRIMARY KEY,
-    invite_code_required BOOLEAN NOT NULL DEFAULT 0
+    -- 注册模式: open 开放注册 / invite 仅限邀请码 / closed 关闭注册 / approval 需管理员审核
+    registration_mode TEXT NOT NULL DEFAULT 'open' CHECK (
+      registration_mode IN ('open', 'invite', 'closed', 'approval')
+    ),
+    password_min_length INTEGER NOT NULL DEFAULT 6,
+    password_require_uppercase BOOLEAN NOT NULL DEFAULT 0,
+    password_require_lowercase BOOLEAN NOT NULL DEFAULT 0,
+    password_require_digit BOOLEAN NOT NULL DEFAULT 0,
+    password_require_symbol BOOLEAN NOT NULL DEFAULT 0,
+    password_check_breached BOOLEAN NOT NULL DEFAULT 0,
+    -- 维护模式，开启后非管理员请求返回 503
+    maintenance_mode BOOLEAN NOT NULL DEFAULT 0,
+    -- 维护提示，为空时使用默认提示
+    maintenance_message TEXT NOT NULL DEFAULT ''
+  );
+
+-- 站点公告表
+CREATE TABLE IF NOT EXISTS
+  announcements (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    -- 展示时间范围，NULL 表示不限制
+    starts_at TEXT,
+    ends_at TEXT,
+    created_by INTEGER,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+  );
+
+-- 用户已关闭的公告
+CREATE TABLE IF NOT EXISTS
+  announcement_dismissals (
+    user_id INTEGER NOT NULL,
+    announcement_id INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (announcement_id) REFERENCES announcements (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, announcement_id)
   );
 
 -- 邀请码表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   invite_codes (
     id INTEGER PRIMARY KEY,
     code TEXT NOT NULL UNIQUE,
     limit_times INTEGER NOT NULL DEFAULT 1,
     used_times INTEGER NOT NULL DEFAULT 0,
     description TEXT,
+    -- 过期时间，NULL 表示永不过期
+    expires_at TEXT,
+    revoked BOOLEAN NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL DEFAULT (
       strftime (
         '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
@@ -46,14 +126,30 @@ CREATE TABLE
     )
   );
 
+-- 邀请码使用记录
+CREATE TABLE IF NOT EXISTS
+  invite_code_uses (
+    id INTEGER PRIMARY KEY,
+    invite_code_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    used_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    ),
+    FOREIGN KEY (invite_code_id) REFERENCES invite_codes (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+  );
+
 -- 书籍表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   books (
     id INTEGER PRIMARY KEY,
     user_id INTEGER NOT NULL,
     title TEXT NOT NULL,
     author TEXT,
     file_path TEXT NOT NULL,
+    file_size INTEGER NOT NULL DEFAULT 0,
     is_public BOOLEAN NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL DEFAULT (
       strftime (
@@ -64,7 +160,7 @@ CREATE TABLE
   );
 
 -- 章节表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   chapters (
     id INTEGER PRIMARY KEY,
     book_id INTEGER NOT NULL,
@@ -74,7 +170,7 @@ CREATE TABLE
   );
 
 -- 阅读进度表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   reading_progress (
     id INTEGER PRIMARY KEY,
     user_id INTEGER NOT NULL,
@@ -88,8 +184,18 @@ CREATE TABLE
     UNIQUE (user_id, book_id)
   );
 
+-- 每日阅读时长统计（用户所在时区的日期），由阅读心跳累加
+CREATE TABLE IF NOT EXISTS
+  reading_daily_stats (
+    user_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    reading_time INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, date)
+  );
+
 -- 阅读设置表
-CREATE TABLE
+CREATE TABLE IF NOT EXISTS
   reading_settings (
     id INTEGER PRIMARY KEY,
     user_id INTEGER NOT NULL UNIQUE,
@@ -106,4 +212,41 @@ CREATE TABLE
   );
 
 -- 为 user_id 创建索引
-CREATE INDEX idx_reading_settings_user_id ON reading_settings (user_id);
\ No newline at end of file
+CREATE INDEX IF NOT EXISTS idx_reading_settings_user_id ON reading_settings (user_id);
+-- 审计日志表，只允许追加
+-- 操作者与目标不设外键，删除用户或书籍后日志仍然保留
+CREATE TABLE IF NOT EXISTS
+  audit_log (
+    id INTEGER PRIMARY KEY,
+    -- 操作者类型: user / admin / moderator / anonymous / system（命令行）
+    actor_type TEXT NOT NULL,
+    actor_id INTEGER,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id INTEGER,
+    -- 附加信息（JSON）
+    detail TEXT,
+    ip TEXT,
+    created_at TEXT NOT NULL DEFAULT (
+      strftime (
+        '%Y-%m-%dT%H:%M:%S.' || substr (strftime ('%f'), 4, 6) || 'Z'
+      )
+    )
+  );
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log (action);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE
+UPDATE ON audit_log BEGIN
+SELECT
+  RAISE (ABORT, 'audit_log is append-only');
+
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log BEGIN
+SELECT
+  RAISE (ABORT, 'audit_log is append-only');
+
+END;
@@ -1,15 +1,35 @@
+use std::sync::Arc;
+
 use anyhow::Result;
-use axum::{extract::FromRef, Router};
+use axum::{
+    extract::{FromRef, Request, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
 use sqlx::{Pool, Sqlite};
 use tower::ServiceBuilder;
 use tower_http::{
     cors::{Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     trace::TraceLayer,
 };
 
 use crate::{
+    auth::peek_role,
+    cache::{ActivityTracker, ContentCache, SettingsCache},
     config::Config,
-    routes::{admin, auth, books, reading},
+    error::AppError,
+    i18n::{self, Message},
+    logging,
+    metrics::{self, Metrics},
+    models::Role,
+    openapi,
+    routes::{
+        admin, announcements, auth, books, health,
+        oidc::{self, OidcClient},
+        reading,
+    },
 };
 
 // 应用状态
@@ -17,6 +37,11 @@ use crate::{
 pub struct AppState {
     pub db: Pool<Sqlite>,
     pub config: Config,
+    pub metrics: Arc<Metrics>,
+    pub content_cache: Arc<ContentCache>,
+    pub settings: SettingsCache,
+    pub activity: ActivityTracker,
+    pub oidc: OidcClient,
 }
 
 // 为状态实现FromRef trait，允许从状态中提取数据库连接和配置
@@ -32,10 +57,50 @@ impl FromRef<AppState> for Config {
     }
 }
 
+impl FromRef<AppState> for Arc<Metrics> {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<ContentCache> {
+    fn from_ref(state: &AppState) -> Self {
+        state.content_cache.clone()
+    }
+}
+
+impl FromRef<AppState> for SettingsCache {
+    fn from_ref(state: &AppState) -> Self {
+        state.settings.clone()
+    }
+}
+
+impl FromRef<AppState> for ActivityTracker {
+    fn from_ref(state: &AppState) -> Self {
+        state.activity.clone()
+    }
+}
+
+impl FromRef<AppState> for OidcClient {
+    fn from_ref(state: &AppState) -> Self {
+        state.oidc.clone()
+    }
+}
+
 // 创建应用实例
 pub async fn create_app(db: Pool<Sqlite>, config: Config) -> Result<Router> {
     // 创建共享状态
-    let state = AppState { db, config };
+    let content_cache = ContentCache::new(config.storage.content_cache_mb as usize * 1024 * 1024);
+    let settings = SettingsCache::load(&db).await?;
+    let state = AppState {
+        db,
+        config,
+        metrics: Arc::new(Metrics::default()),
+        content_cache: Arc::new(content_cache),
+        settings,
+        activity: ActivityTracker::default(),
+        oidc: OidcClient::new()?,
+    };
 
     // 创建CORS中间件
     let cors = CorsLayer::new()
@@ -47,19 +112,81 @@ pub async fn create_app(db: Pool<Sqlite>, config: Config) -> Result<Router> {
     let app = Router::new()
         // 认证路由
         .nest("/api/auth", auth::routes())
+        // OpenID Connect 登录路由
+        .nest("/api/auth/oidc", oidc::routes())
         // 书籍路由
         .nest("/api/books", books::routes())
         // 阅读路由
         .nest("/api/reading", reading::routes())
+        // 公告路由
+        .nest("/api/announcements", announcements::routes())
         // 管理员路由
         .nest("/api/admin", admin::routes())
+        // 维护模式
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            maintenance_guard,
+        ))
+        // 健康检查与监控
+        .merge(health::routes())
+        // 接口文档
+        .merge(openapi::routes());
+
+    // 内嵌前端，处理其余未匹配的请求
+    #[cfg(feature = "embed-frontend")]
+    let app = app.merge(crate::frontend::routes());
+
+    let app = app
+        // 请求指标
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            metrics::track_requests,
+        ))
         // 中间件
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                // 生成请求ID并写入响应头
+                .layer(SetRequestIdLayer::new(
+                    logging::REQUEST_ID_HEADER.clone(),
+                    MakeRequestUuid,
+                ))
+                .layer(TraceLayer::new_for_http().make_span_with(logging::make_request_span))
+                .layer(PropagateRequestIdLayer::new(
+                    logging::REQUEST_ID_HEADER.clone(),
+                ))
+                .layer(middleware::from_fn(logging::scope_request_id))
+                .layer(middleware::from_fn(i18n::negotiate_locale))
                 .layer(cors),
         )
         .with_state(state);
 
     Ok(app)
 }
+
+// 维护模式下仍然可以访问的路径，保证管理员能够登录并关闭维护模式
+const MAINTENANCE_EXEMPT_PATHS: &[&str] = &["/api/auth/login", "/api/auth/admin/setup"];
+
+// 维护模式中间件：开启后除管理员外的请求均返回 503
+// 设置从缓存读取，只查询请求者当前的角色，不执行完整的认证流程
+async fn maintenance_guard(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let settings = state.settings.get(&state.db).await;
+    if !settings.maintenance_mode || MAINTENANCE_EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    if peek_role(&parts, &state.config, &state.db).await == Some(Role::Admin) {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let message = if settings.maintenance_message.is_empty() {
+        Message::Maintenance.localized().to_string()
+    } else {
+        settings.maintenance_message.clone()
+    };
+    AppError::Maintenance(message).into_response()
+}
@@ -0,0 +1,122 @@
+use std::net::IpAddr;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    auth::{self, AuthModerator},
+    config::Config,
+    models::Role,
+};
+
+// 审计日志中的操作者
+#[derive(Debug, Clone, Copy)]
+pub enum Actor {
+    User(i64),
+    Admin(i64),
+    Moderator(i64),
+    // 未登录的请求，例如登录失败
+    Anonymous,
+    // 命令行管理操作
+    System,
+}
+
+impl Actor {
+    fn parts(self) -> (&'static str, Option<i64>) {
+        match self {
+            Actor::User(id) => ("user", Some(id)),
+            Actor::Admin(id) => ("admin", Some(id)),
+            Actor::Moderator(id) => ("moderator", Some(id)),
+            Actor::Anonymous => ("anonymous", None),
+            Actor::System => ("system", None),
+        }
+    }
+}
+
+// 版主接口由管理员调用时按管理员记录
+impl From<&AuthModerator> for Actor {
+    fn from(moderator: &AuthModerator) -> Self {
+        match moderator.role {
+            Role::Admin => Actor::Admin(moderator.user_id),
+            _ => Actor::Moderator(moderator.user_id),
+        }
+    }
+}
+
+// 客户端IP，来自受信任代理的请求从右向左查找 X-Forwarded-For，
+// 取第一个不属于受信任代理的地址，左侧的条目可能由客户端伪造
+#[derive(Debug, Clone, Default)]
+pub struct ClientIp(pub Option<String>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    Config: FromRef<S>,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Config::from_ref(state);
+        let mut client_ip = auth::peer_ip(parts);
+        if auth::from_trusted_proxy(parts, &config.proxy_auth) {
+            // 多个代理可能分别追加请求头，按出现顺序拼接
+            let forwarded_for: Vec<&str> = parts
+                .headers
+                .get_all("X-Forwarded-For")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .collect();
+            for entry in forwarded_for.iter().rev() {
+                // 无法解析的条目不可信，停在最后一个可信的地址
+                let Ok(ip) = entry.parse::<IpAddr>() else {
+                    break;
+                };
+                client_ip = Some(ip);
+                if !config.proxy_auth.is_trusted(ip) {
+                    break;
+                }
+            }
+        }
+
+        Ok(ClientIp(client_ip.map(|ip| ip.to_string())))
+    }
+}
+
+// 追加一条审计日志
+// 写入失败只记录错误，不影响正在进行的操作
+pub async fn record(
+    pool: &Pool<Sqlite>,
+    actor: Actor,
+    action: &str,
+    target: Option<(&str, i64)>,
+    detail: Option<serde_json::Value>,
+    ip: &ClientIp,
+) {
+    let (actor_type, actor_id) = actor.parts();
+    let (target_type, target_id) = target.unzip();
+    let detail = detail.map(|detail| detail.to_string());
+
+    let result = sqlx::query!(
+        "INSERT INTO audit_log (actor_type, actor_id, action, target_type, target_id, detail, ip)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        actor_type,
+        actor_id,
+        action,
+        target_type,
+        target_id,
+        detail,
+        ip.0
+    )
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("写入审计日志失败 ({}): {}", action, e);
+    }
+}
@@ -1,9 +1,11 @@
+use std::net::{IpAddr, SocketAddr};
+
 use axum::{
-    extract::{FromRef, FromRequestParts},
+    extract::{ConnectInfo, FromRef, FromRequestParts},
     http::request::Parts,
 };
 use axum_extra::{
-    headers::{authorization::Bearer, Authorization},
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
     TypedHeader,
 };
 use chrono::{DateTime, Duration, Utc};
@@ -11,7 +13,17 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation}
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Sqlite};
 
-use crate::{config::Config, error::AppError, models::User};
+use crate::{
+    cache::ActivityTracker,
+    config::{Config, ProxyAuthConfig},
+    db,
+    error::{AppError, ErrorCode},
+    i18n,
+    models::{Role, User},
+    routes::auth::check_registration,
+    server::UnixSocketConnection,
+    utils::{generate_uuid, hash_password, validate_username},
+};
 
 // JWT 声明结构
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +31,7 @@ pub struct Claims {
     pub sub: String,  // 用户ID
     pub exp: usize,   // 过期时间
     pub iat: usize,   // 颁发时间
-    pub role: String, // 角色: "user" 或 "admin"
+    pub role: String, // 令牌类型，固定为 "user"，用户角色保存在数据库中
 }
 
 // 为Claims实现方法
@@ -35,18 +47,6 @@ impl Claims {
             role: "user".to_string(),
         }
     }
-
-    // 创建管理员JWT声明
-    pub fn new_admin(admin_id: i64, config: &Config) -> Self {
-        let now = Utc::now();
-        let expiry = now + Duration::seconds(config.jwt.admin_expiration as i64);
-        Self {
-            sub: admin_id.to_string(),
-            iat: now.timestamp() as usize,
-            exp: expiry.timestamp() as usize,
-            role: "admin".to_string(),
-        }
-    }
 }
 
 // 创建JWT令牌
@@ -65,10 +65,17 @@ pub fn verify_token(token: &str, config: &Config) -> Result<Claims, AppError> {
         .map_err(AppError::Jwt)
 }
 
+// 无需输入密码即可确认身份的时间窗口，单位为秒
+// 只适用于没有本地密码的用户（反向代理或 OIDC 创建），刚登录后可以凭令牌注销账号或设置密码
+const REAUTH_WINDOW: i64 = 5 * 60;
+
 // 提取用户的认证中间件
 pub struct AuthUser {
     pub user_id: i64,
+    pub role: Role,
     pub created_at: DateTime<Utc>,
+    // 本次请求由反向代理认证，或令牌在 REAUTH_WINDOW 内签发
+    pub recently_authenticated: bool,
 }
 
 impl<S> FromRequestParts<S> for AuthUser
@@ -76,6 +83,7 @@ where
     S: Send + Sync,
     Config: FromRef<S>,
     Pool<Sqlite>: FromRef<S>,
+    ActivityTracker: FromRef<S>,
 {
     type Rejection = AppError;
 
@@ -83,83 +91,302 @@ where
         // 从状态获取配置
         let config = Config::from_ref(state);
 
-        // 从请求头获取Authorization
-        let TypedHeader(Authorization(bearer)) =
-            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
-                .await
-                .map_err(|_| AppError::Auth("未提供授权令牌".to_string()))?;
-
-        // 验证令牌
-        let claims = verify_token(bearer.token(), &config)?;
-
-        // 验证角色
-        if claims.role != "user" {
-            return Err(AppError::Auth("令牌角色无效".to_string()));
-        }
-
-        // 获取用户ID
-        let user_id: i64 = claims
-            .sub
-            .parse()
-            .map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
-
         // 获取数据库连接
         let pool = Pool::<Sqlite>::from_ref(state);
 
+        // 优先使用反向代理传递的用户名，代理在每个请求上都完成了认证
+        let (user_id, recently_authenticated) =
+            match proxy_auth_user_id(parts, &config, &pool).await? {
+                Some(user_id) => (user_id, true),
+                None => {
+                    let claims = bearer_claims(parts, state, &config).await?;
+                    let user_id = claims
+                        .sub
+                        .parse()
+                        .map_err(|_| AppError::Auth("无效的用户ID".to_string()))?;
+                    let issued_at = claims.iat as i64;
+                    (user_id, Utc::now().timestamp() - issued_at <= REAUTH_WINDOW)
+                }
+            };
+
         // 验证用户是否存在
         let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
             .bind(user_id)
             .fetch_optional(&pool)
             .await
             .map_err(AppError::Database)?
-            .ok_or_else(|| AppError::NotFound("用户不存在".to_string()))?;
+            .ok_or_else(|| AppError::NotFound(ErrorCode::UserNotFound, "用户不存在".to_string()))?;
+        ensure_active(&user.status)?;
+        i18n::set_user_locale(&user.locale);
+
+        // 在请求日志中记录用户
+        tracing::Span::current().record("user_id", user_id);
+
+        // 记录最近活跃时间，同一用户五分钟内只更新一次
+        if ActivityTracker::from_ref(state).should_record(user_id) {
+            sqlx::query(
+                "UPDATE users SET last_active_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+                 WHERE id = ?",
+            )
+            .bind(user_id)
+            .execute(&pool)
+            .await?;
+        }
 
         Ok(AuthUser {
             user_id,
+            role: user.role,
             created_at: user
                 .created_at
                 .parse::<DateTime<Utc>>()
                 .unwrap_or_else(|_| panic!("无法将其 parse 为 UTC 时间: {}", user.created_at)),
+            recently_authenticated,
         })
     }
 }
 
+// 检查账号状态是否允许登录和访问
+pub fn ensure_active(status: &str) -> Result<(), AppError> {
+    match status {
+        "disabled" => Err(AppError::Forbidden(
+            ErrorCode::AccountDisabled,
+            "账号已被禁用".to_string(),
+        )),
+        "pending" => Err(AppError::Forbidden(
+            ErrorCode::AccountPending,
+            "账号尚未通过审核".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// 从 Bearer 令牌中解析声明
+async fn bearer_claims<S>(parts: &mut Parts, state: &S, config: &Config) -> Result<Claims, AppError>
+where
+    S: Send + Sync,
+{
+    // 从请求头获取Authorization
+    let TypedHeader(Authorization(bearer)) =
+        TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AppError::Auth("未提供授权令牌".to_string()))?;
+
+    // 验证令牌
+    let claims = verify_token(bearer.token(), config)?;
+
+    // 验证角色
+    if claims.role != "user" {
+        return Err(AppError::Auth("令牌角色无效".to_string()));
+    }
+
+    Ok(claims)
+}
+
+// 读取受信任的反向代理传递的用户名
+// 未启用、请求不来自受信任代理或未携带请求头时返回 None
+fn proxy_username<'a>(parts: &'a Parts, config: &Config) -> Option<&'a str> {
+    let proxy_auth = &config.proxy_auth;
+    if !proxy_auth.enabled {
+        return None;
+    }
+
+    let username = parts
+        .headers
+        .get(proxy_auth.header.as_str())
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+
+    // 只信任来自白名单代理的请求头，防止客户端伪造
+    if !from_trusted_proxy(parts, proxy_auth) {
+        tracing::warn!(
+            "忽略来自非受信任地址 {:?} 的 {} 请求头",
+            peer_ip(parts),
+            proxy_auth.header
+        );
+        return None;
+    }
+
+    Some(username)
+}
+
+// 连接的对端地址，Unix 域套接字连接没有对端地址
+pub(crate) fn peer_ip(parts: &Parts) -> Option<IpAddr> {
+    parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+// 判断请求是否直接来自受信任的代理
+// TCP 连接按对端地址判断，Unix 域套接字连接只在显式开启 trust_unix_socket 时信任
+pub(crate) fn from_trusted_proxy(parts: &Parts, proxy_auth: &ProxyAuthConfig) -> bool {
+    match peer_ip(parts) {
+        Some(ip) => proxy_auth.is_trusted(ip),
+        None => {
+            proxy_auth.trust_unix_socket && parts.extensions.get::<UnixSocketConnection>().is_some()
+        }
+    }
+}
+
+// 不产生副作用地判断请求者当前的角色，用于维护模式检查
+// 角色与状态以数据库为准，降级或禁用立即生效；反向代理认证只查询已有用户，不会自动创建
+pub async fn peek_role(parts: &Parts, config: &Config, pool: &Pool<Sqlite>) -> Option<Role> {
+    if let Some(username) = proxy_username(parts, config) {
+        return sqlx::query_scalar::<_, Role>(
+            "SELECT role FROM users WHERE username = ? AND status = 'active'",
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten();
+    }
+
+    let bearer = parts.headers.typed_get::<Authorization<Bearer>>()?;
+    let claims = verify_token(bearer.token(), config).ok()?;
+    let user_id = claims.sub.parse::<i64>().ok()?;
+    sqlx::query_scalar::<_, Role>("SELECT role FROM users WHERE id = ? AND status = 'active'")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+// 从受信任的反向代理请求头中解析用户ID
+// 未启用、请求不来自受信任代理或未携带请求头时返回 None
+async fn proxy_auth_user_id(
+    parts: &Parts,
+    config: &Config,
+    pool: &Pool<Sqlite>,
+) -> Result<Option<i64>, AppError> {
+    let Some(username) = proxy_username(parts, config) else {
+        return Ok(None);
+    };
+
+    // 查找已有用户
+    let user_id = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+    if let Some(user_id) = user_id {
+        return Ok(Some(user_id));
+    }
+
+    if !config.proxy_auth.auto_create {
+        return Err(AppError::Auth("用户不存在".to_string()));
+    }
+
+    // 与注册接口执行相同的检查，代理无法提供邀请码
+    validate_username(username)?;
+    let status = check_registration(pool, None).await?;
+
+    // 首次出现的用户自动创建，密码为随机值，只能通过代理登录
+    let password_hash = hash_password(&generate_uuid())?;
+    let mut tx = pool.begin().await?;
+    match db::create_passwordless_user(&mut tx, username, &password_hash, status).await {
+        Ok(user_id) => {
+            tx.commit().await?;
+            tracing::info!("通过反向代理自动创建用户: {} (id={})", username, user_id);
+            Ok(Some(user_id))
+        }
+        // 并发请求可能已经创建了该用户
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            let user_id = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_one(pool)
+                .await?;
+            Ok(Some(user_id))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    // 系统设置、用户、邀请码与审计日志
+    ManageSystem,
+    // 管理所有用户的书籍
+    ModerateBooks,
+    // 上传书籍
+    UploadBooks,
+}
+
+impl Role {
+    // 角色是否拥有指定权限
+    pub fn allows(self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Moderator => permission != Permission::ManageSystem,
+            Role::Uploader => permission == Permission::UploadBooks,
+            Role::Reader => false,
+        }
+    }
+}
+
+impl AuthUser {
+    // 检查当前用户是否拥有指定权限
+    pub fn require(&self, permission: Permission, message: &str) -> Result<(), AppError> {
+        if self.role.allows(permission) {
+            Ok(())
+        } else {
+            let code = match permission {
+                Permission::ManageSystem => ErrorCode::AdminRequired,
+                _ => ErrorCode::RoleForbidden,
+            };
+            Err(AppError::Forbidden(code, message.to_string()))
+        }
+    }
+}
+
 // 提取管理员的认证中间件
 pub struct AuthAdmin {
-    pub admin_id: i64,
+    pub user_id: i64,
 }
 
 impl<S> FromRequestParts<S> for AuthAdmin
 where
     S: Send + Sync,
     Config: FromRef<S>,
+    Pool<Sqlite>: FromRef<S>,
+    ActivityTracker: FromRef<S>,
 {
     type Rejection = AppError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        // 从状态获取配置
-        let config = Config::from_ref(state);
+        let auth = AuthUser::from_request_parts(parts, state).await?;
+        auth.require(Permission::ManageSystem, "需要管理员权限")?;
 
-        // 从请求头获取Authorization
-        let TypedHeader(Authorization(bearer)) =
-            TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
-                .await
-                .map_err(|_| AppError::Auth("未提供管理员授权令牌".to_string()))?;
+        Ok(AuthAdmin {
+            user_id: auth.user_id,
+        })
+    }
+}
 
-        // 验证令牌
-        let claims = verify_token(bearer.token(), &config)?;
+// 提取版主（或管理员）的认证中间件
+pub struct AuthModerator {
+    pub user_id: i64,
+    pub role: Role,
+}
 
-        // 验证角色
-        if claims.role != "admin" {
-            return Err(AppError::Forbidden("需要管理员权限".to_string()));
-        }
+impl<S> FromRequestParts<S> for AuthModerator
+where
+    S: Send + Sync,
+    Config: FromRef<S>,
+    Pool<Sqlite>: FromRef<S>,
+    ActivityTracker: FromRef<S>,
+{
+    type Rejection = AppError;
 
-        // 获取管理员ID
-        let admin_id: i64 = claims
-            .sub
-            .parse()
-            .map_err(|_| AppError::Auth("无效的管理员ID".to_string()))?;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = AuthUser::from_request_parts(parts, state).await?;
+        auth.require(Permission::ModerateBooks, "需要版主或管理员角色")?;
 
-        Ok(AuthAdmin { admin_id })
+        Ok(AuthModerator {
+            user_id: auth.user_id,
+            role: auth.role,
+        })
     }
 }
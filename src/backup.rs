@@ -0,0 +1,446 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use sqlx::{sqlite::SqliteConnectOptions, Pool, Sqlite, SqlitePool};
+use tokio::fs;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    config::Config,
+    db,
+    error::{AppError, ErrorCode},
+    models::BackupInfo,
+    utils::generate_uuid,
+};
+
+// 备份文件名前缀，用于识别与清理旧备份
+const BACKUP_PREFIX: &str = "reader-backup-";
+// 备份包中的数据库文件名
+const DB_ENTRY: &str = "reader.db";
+const BOOKS_DIR_ENTRY: &str = "books/";
+const AVATARS_DIR_ENTRY: &str = "avatars/";
+
+// 创建一份备份：数据库快照与书籍、头像文件打包为 ZIP
+// 数据库使用 VACUUM INTO 生成一致的快照，不影响正在进行的读写
+pub async fn create_backup(pool: &Pool<Sqlite>, config: &Config) -> Result<BackupInfo, AppError> {
+    let backup_dir = &config.backup.dir;
+    fs::create_dir_all(backup_dir).await.map_err(AppError::Io)?;
+
+    let name = format!(
+        "{}{}.zip",
+        BACKUP_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%3fZ")
+    );
+    let snapshot_path = backup_dir.join(format!(".{}.db", generate_uuid()));
+    let partial_path = backup_dir.join(format!("{}.partial", name));
+    let archive_path = backup_dir.join(&name);
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(snapshot_path.to_string_lossy())
+        .execute(pool)
+        .await?;
+
+    // 快照之后才打包文件，期间新上传的书籍只会成为孤立文件，可通过一致性检查清理
+    let book_dir = config.storage.book_dir.clone();
+    let avatar_dir = config.storage.avatar_dir.clone();
+    let snapshot = snapshot_path.clone();
+    let partial = partial_path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        write_archive(&partial, &snapshot, &book_dir, &avatar_dir)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("备份任务失败: {}", e)))?;
+
+    let _ = fs::remove_file(&snapshot_path).await;
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial_path).await;
+        return Err(AppError::Internal(format!("生成备份文件失败: {}", e)));
+    }
+    fs::rename(&partial_path, &archive_path)
+        .await
+        .map_err(AppError::Io)?;
+
+    tracing::info!("已创建备份 {}", archive_path.display());
+    prune_backups(backup_dir, config.backup.keep).await?;
+
+    backup_info(&archive_path).await
+}
+
+fn write_archive(
+    archive_path: &Path,
+    snapshot_path: &Path,
+    book_dir: &Path,
+    avatar_dir: &Path,
+) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(DB_ENTRY, options)?;
+    io::copy(&mut File::open(snapshot_path)?, &mut zip)?;
+
+    for (dir, prefix) in [(book_dir, BOOKS_DIR_ENTRY), (avatar_dir, AVATARS_DIR_ENTRY)] {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name();
+            zip.start_file(
+                format!("{}{}", prefix, file_name.to_string_lossy()),
+                options,
+            )?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+// 列出已有的备份，按时间从新到旧排序
+pub async fn list_backups(backup_dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    let mut backups = Vec::new();
+    for path in backup_paths(backup_dir).await? {
+        backups.push(backup_info(&path).await?);
+    }
+    backups.reverse();
+
+    Ok(backups)
+}
+
+// 根据名称查找备份文件，名称不合法或文件不存在时返回 None
+pub fn backup_path(backup_dir: &Path, name: &str) -> Option<PathBuf> {
+    let valid = name.starts_with(BACKUP_PREFIX)
+        && name.ends_with(".zip")
+        && !name.contains(['/', '\\'])
+        && !name.contains("..");
+    let path = backup_dir.join(name);
+    (valid && path.is_file()).then_some(path)
+}
+
+// 按时间从旧到新排列的备份文件
+async fn backup_paths(backup_dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut paths = Vec::new();
+    if !backup_dir.exists() {
+        return Ok(paths);
+    }
+
+    let mut entries = fs::read_dir(backup_dir).await.map_err(AppError::Io)?;
+    while let Some(entry) = entries.next_entry().await.map_err(AppError::Io)? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(BACKUP_PREFIX) && name.ends_with(".zip") {
+            paths.push(entry.path());
+        }
+    }
+    // 文件名中的时间戳保证按名称排序即按时间排序
+    paths.sort();
+
+    Ok(paths)
+}
+
+async fn backup_info(path: &Path) -> Result<BackupInfo, AppError> {
+    let metadata = fs::metadata(path).await.map_err(AppError::Io)?;
+    let created_at: DateTime<Utc> = metadata.modified().map_err(AppError::Io)?.into();
+
+    Ok(BackupInfo {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: metadata.len() as i64,
+        created_at: created_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    })
+}
+
+// 只保留最新的 keep 份备份，keep 为 0 时不清理
+async fn prune_backups(backup_dir: &Path, keep: usize) -> Result<(), AppError> {
+    if keep == 0 {
+        return Ok(());
+    }
+
+    let paths = backup_paths(backup_dir).await?;
+    let excess = paths.len().saturating_sub(keep);
+    for path in &paths[..excess] {
+        fs::remove_file(path).await.map_err(AppError::Io)?;
+        tracing::info!("已删除旧备份 {}", path.display());
+    }
+
+    Ok(())
+}
+
+// 按配置的间隔定时创建备份
+pub fn spawn_scheduler(pool: Pool<Sqlite>, config: Config) {
+    let Some(interval_hours) = config.backup.interval_hours.filter(|hours| *hours > 0) else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(interval_hours * 60 * 60);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = create_backup(&pool, &config).await {
+                tracing::error!("定时备份失败: {}", e);
+            }
+        }
+    });
+}
+
+// 从备份恢复数据库与书籍、头像文件，必须在服务停止时执行
+// 先在临时目录中解压并校验备份，全部通过后才替换现有数据
+// 被替换的数据库与目录保留为 *.before-restore，替换中途失败时恢复原有数据
+pub async fn restore(config: &Config, archive_path: &Path) -> Result<(), AppError> {
+    let db_path = db::connect_options(config)?.get_filename().to_path_buf();
+    let staging_dir = db_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(format!(".restore-{}", generate_uuid()));
+
+    let result = stage_restore(config, archive_path, &staging_dir).await;
+    let result = match result {
+        Ok(()) => replace_data(config, &db_path, &staging_dir).await,
+        Err(e) => Err(e),
+    };
+    let _ = fs::remove_dir_all(&staging_dir).await;
+    result?;
+
+    tracing::info!("已从 {} 恢复数据", archive_path.display());
+    Ok(())
+}
+
+// 先把现有数据全部移到 *.before-restore，再放入解压出的数据
+// 任何一步失败都撤销已完成的步骤
+async fn replace_data(config: &Config, db_path: &Path, staging_dir: &Path) -> Result<(), AppError> {
+    // 旧数据库的 WAL 文件不能与新数据库一起使用，随数据库一起移走
+    let wal_path = PathBuf::from(format!("{}-wal", db_path.display()));
+    let shm_path = PathBuf::from(format!("{}-shm", db_path.display()));
+    let existing = [
+        db_path,
+        &wal_path,
+        &shm_path,
+        &config.storage.book_dir,
+        &config.storage.avatar_dir,
+    ];
+    let staged = [
+        (staging_dir.join(DB_ENTRY), db_path),
+        (staging_dir.join("books"), &config.storage.book_dir),
+        (staging_dir.join("avatars"), &config.storage.avatar_dir),
+    ];
+
+    let mut moved = Vec::new();
+    let mut installed = Vec::new();
+    let result = async {
+        for target in existing {
+            let previous = before_restore_path(target);
+            if move_aside(target, &previous).await? {
+                moved.push((target, previous));
+            }
+        }
+        for (source, target) in &staged {
+            fs::rename(source, target).await.map_err(AppError::Io)?;
+            installed.push(*target);
+        }
+        Ok::<_, AppError>(())
+    }
+    .await;
+
+    let Err(e) = result else {
+        return Ok(());
+    };
+    tracing::error!("替换数据失败，正在恢复原有数据: {}", e);
+    for target in installed {
+        if let Err(e) = remove_path(target).await {
+            tracing::error!("无法移除 {}: {}", target.display(), e);
+        }
+    }
+    for (target, previous) in moved {
+        if let Err(e) = fs::rename(&previous, target).await {
+            tracing::error!(
+                "无法将 {} 恢复为 {}: {}",
+                previous.display(),
+                target.display(),
+                e
+            );
+        }
+    }
+    Err(e)
+}
+
+fn before_restore_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.before-restore", path.display()))
+}
+
+// 将现有文件或目录移到 previous，不存在时返回 false
+async fn move_aside(target: &Path, previous: &Path) -> Result<bool, AppError> {
+    if !target.exists() {
+        return Ok(false);
+    }
+    remove_path(previous).await?;
+    fs::rename(target, previous).await.map_err(AppError::Io)?;
+    Ok(true)
+}
+
+// 解压备份到临时目录并校验
+async fn stage_restore(
+    config: &Config,
+    archive_path: &Path,
+    staging_dir: &Path,
+) -> Result<(), AppError> {
+    fs::create_dir_all(staging_dir.join("books"))
+        .await
+        .map_err(AppError::Io)?;
+    fs::create_dir_all(staging_dir.join("avatars"))
+        .await
+        .map_err(AppError::Io)?;
+
+    let archive = archive_path.to_path_buf();
+    let staging = staging_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || extract_archive(&archive, &staging))
+        .await
+        .map_err(|e| AppError::Internal(format!("解压任务失败: {}", e)))??;
+
+    // 校验数据库，并将文件路径改写为当前配置的目录
+    let options = SqliteConnectOptions::new().filename(staging_dir.join(DB_ENTRY));
+    let pool = SqlitePool::connect_with(options).await?;
+    let result = verify_and_relocate(&pool, config, staging_dir).await;
+    pool.close().await;
+
+    result
+}
+
+fn extract_archive(archive_path: &Path, staging_dir: &Path) -> Result<(), AppError> {
+    let invalid = |message: &str| {
+        AppError::Validation(ErrorCode::BadRequest, format!("备份文件无效: {}", message))
+    };
+
+    let file = File::open(archive_path).map_err(AppError::Io)?;
+    let mut archive = ZipArchive::new(file).map_err(|e| invalid(&e.to_string()))?;
+
+    let mut has_db = false;
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| invalid(&e.to_string()))?;
+        let name = entry.name().to_string();
+        let relative = entry
+            .enclosed_name()
+            .ok_or_else(|| invalid(&format!("非法的路径 {}", name)))?;
+
+        // 只接受数据库文件与书籍、头像目录下的文件
+        let allowed = name == DB_ENTRY
+            || ((name.starts_with(BOOKS_DIR_ENTRY) || name.starts_with(AVATARS_DIR_ENTRY))
+                && relative.components().count() == 2);
+        if !allowed {
+            return Err(invalid(&format!("未知的文件 {}", name)));
+        }
+        has_db |= name == DB_ENTRY;
+
+        let mut output = File::create(staging_dir.join(relative)).map_err(AppError::Io)?;
+        io::copy(&mut entry, &mut output).map_err(AppError::Io)?;
+    }
+
+    if !has_db {
+        return Err(invalid("缺少数据库文件"));
+    }
+
+    Ok(())
+}
+
+async fn verify_and_relocate(
+    pool: &Pool<Sqlite>,
+    config: &Config,
+    staging_dir: &Path,
+) -> Result<(), AppError> {
+    let invalid = |message: String| {
+        AppError::Validation(ErrorCode::BadRequest, format!("备份文件无效: {}", message))
+    };
+
+    let integrity = sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| invalid(e.to_string()))?;
+    if integrity != "ok" {
+        return Err(invalid(format!("数据库校验失败: {}", integrity)));
+    }
+
+    // 拒绝较新版本程序生成的备份，较旧的备份先升级到当前结构
+    let version = db::schema_version(&mut *pool.acquire().await?).await?;
+    if version > db::SCHEMA_VERSION {
+        return Err(invalid(format!(
+            "数据库结构版本 {} 高于程序支持的版本 {}，请升级程序",
+            version,
+            db::SCHEMA_VERSION
+        )));
+    }
+    db::run_migrations(pool)
+        .await
+        .map_err(|e| invalid(format!("{:#}", e)))?;
+
+    let books =
+        sqlx::query_as::<_, (i64, String, i64)>("SELECT id, file_path, file_size FROM books")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| invalid(e.to_string()))?;
+    for (book_id, file_path, file_size) in books {
+        let file_name = Path::new(&file_path)
+            .file_name()
+            .ok_or_else(|| invalid(format!("书籍 {} 的文件路径无效", book_id)))?;
+        let staged_path = staging_dir.join("books").join(file_name);
+        if !staged_path.is_file() {
+            return Err(invalid(format!("缺少书籍 {} 的文件", book_id)));
+        }
+        // 升级时书籍文件尚未就位，未记录的文件大小按备份中的文件补充
+        let file_size = match file_size {
+            0 => fs::metadata(&staged_path)
+                .await
+                .map_err(AppError::Io)?
+                .len() as i64,
+            size => size,
+        };
+        let new_path = config.storage.book_dir.join(file_name);
+        sqlx::query("UPDATE books SET file_path = ?, file_size = ? WHERE id = ?")
+            .bind(new_path.to_string_lossy())
+            .bind(file_size)
+            .bind(book_id)
+            .execute(pool)
+            .await?;
+    }
+
+    let avatars = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, avatar_path FROM users WHERE avatar_path IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| invalid(e.to_string()))?;
+    for (user_id, avatar_path) in avatars {
+        // 头像缺失不影响使用，只清空记录
+        let new_path = Path::new(&avatar_path)
+            .file_name()
+            .filter(|file_name| staging_dir.join("avatars").join(file_name).is_file())
+            .map(|file_name| config.storage.avatar_dir.join(file_name));
+        sqlx::query("UPDATE users SET avatar_path = ? WHERE id = ?")
+            .bind(new_path.map(|path| path.to_string_lossy().into_owned()))
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn remove_path(path: &Path) -> Result<(), AppError> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).await.map_err(AppError::Io)?;
+    } else if path.exists() {
+        fs::remove_file(path).await.map_err(AppError::Io)?;
+    }
+
+    Ok(())
+}
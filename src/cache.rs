@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use sqlx::{Pool, Sqlite};
+use tokio::fs;
+
+use crate::{db, error::AppError, models::Settings};
+
+// 书籍正文缓存，避免每次翻页都重新读取并解码整个文件
+// 以文件路径为键：书籍文件写入后不再修改，删除书籍后旧条目只会被自然淘汰
+// 超出容量时淘汰最久未读的书籍
+pub struct ContentCache {
+    // 容量（字节），为 0 时不缓存
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+// 书籍正文及其字符数，字符数用于按字符位置分页
+pub struct BookText {
+    pub text: String,
+    pub char_count: usize,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, CacheEntry>,
+    // 当前缓存的正文总字节数
+    size: usize,
+    // 每次访问递增，用于判断最久未使用的条目
+    tick: u64,
+}
+
+struct CacheEntry {
+    content: Arc<BookText>,
+    last_used: u64,
+}
+
+impl ContentCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // 读取书籍正文，优先使用缓存
+    pub async fn get(&self, file_path: &str) -> Result<Arc<BookText>, AppError> {
+        if let Some(content) = self.lookup(file_path) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(content);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let text = fs::read_to_string(file_path).await.map_err(AppError::Io)?;
+        let content = Arc::new(BookText {
+            char_count: text.chars().count(),
+            text,
+        });
+        self.insert(file_path, content.clone());
+
+        Ok(content)
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    // 当前缓存的正文总字节数
+    pub fn size(&self) -> usize {
+        self.lock().size
+    }
+
+    fn lookup(&self, file_path: &str) -> Option<Arc<BookText>> {
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let entry = inner.entries.get_mut(file_path)?;
+        entry.last_used = tick;
+        Some(entry.content.clone())
+    }
+
+    fn insert(&self, file_path: &str, content: Arc<BookText>) {
+        let len = content.text.len();
+        if len > self.capacity {
+            return;
+        }
+
+        let mut inner = self.lock();
+        // 并发读取同一本书时只保留一份
+        if inner.entries.contains_key(file_path) {
+            return;
+        }
+        while inner.size + len > self.capacity {
+            let Some(oldest) = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = inner.entries.remove(&oldest) {
+                inner.size -= evicted.content.text.len();
+            }
+        }
+
+        inner.tick += 1;
+        let last_used = inner.tick;
+        inner.size += len;
+        inner
+            .entries
+            .insert(file_path.to_string(), CacheEntry { content, last_used });
+    }
+
+    // 缓存中只有可重建的数据，锁中毒时继续使用
+    fn lock(&self) -> MutexGuard<'_, CacheInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// 系统设置缓存，供维护模式检查等每个请求都要读取设置的地方使用
+// 通过接口修改设置后调用 refresh 立即生效；命令行或恢复备份等绕过接口的修改在缓存过期后生效
+#[derive(Clone)]
+pub struct SettingsCache {
+    inner: Arc<RwLock<CachedSettings>>,
+    ttl: Duration,
+}
+
+struct CachedSettings {
+    settings: Arc<Settings>,
+    loaded_at: Instant,
+}
+
+impl SettingsCache {
+    // 默认缓存有效期
+    const TTL: Duration = Duration::from_secs(10);
+
+    pub async fn load(pool: &Pool<Sqlite>) -> Result<Self, sqlx::Error> {
+        Self::load_with_ttl(pool, Self::TTL).await
+    }
+
+    pub async fn load_with_ttl(pool: &Pool<Sqlite>, ttl: Duration) -> Result<Self, sqlx::Error> {
+        let settings = db::load_settings(pool).await?;
+        Ok(SettingsCache {
+            inner: Arc::new(RwLock::new(CachedSettings {
+                settings: Arc::new(settings),
+                loaded_at: Instant::now(),
+            })),
+            ttl,
+        })
+    }
+
+    // 读取设置，缓存过期时从数据库重新加载，加载失败则继续使用旧值
+    pub async fn get(&self, pool: &Pool<Sqlite>) -> Arc<Settings> {
+        {
+            let cached = self.read();
+            if cached.loaded_at.elapsed() < self.ttl {
+                return cached.settings.clone();
+            }
+        }
+        if let Err(e) = self.refresh(pool).await {
+            tracing::warn!("重新加载系统设置失败: {}", e);
+        }
+        self.read().settings.clone()
+    }
+
+    // 从数据库重新加载设置
+    pub async fn refresh(&self, pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+        let settings = db::load_settings(pool).await?;
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = CachedSettings {
+            settings: Arc::new(settings),
+            loaded_at: Instant::now(),
+        };
+        Ok(())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, CachedSettings> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// 记录每个用户最近一次写入 last_active_at 的时间
+// 更新语句即使不修改任何行也要获取写锁，先在内存中判断，避免每个请求都与写操作竞争
+#[derive(Clone, Default)]
+pub struct ActivityTracker(Arc<Mutex<HashMap<i64, Instant>>>);
+
+impl ActivityTracker {
+    // 同一用户在该间隔内只写入一次
+    const INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+    // 距离上次写入超过间隔时返回 true，并记为已写入
+    pub fn should_record(&self, user_id: i64) -> bool {
+        let now = Instant::now();
+        let mut recorded = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match recorded.get(&user_id) {
+            Some(last) if now.duration_since(*last) < Self::INTERVAL => false,
+            _ => {
+                recorded.insert(user_id, now);
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::{config::ConfigOverrides, models::Role};
+
+/// 命令行参数
+#[derive(Debug, Parser)]
+#[command(version, about = "reader-server-rs 阅读服务器")]
+pub struct Cli {
+    /// 配置文件路径，支持 .json 与 .toml，默认依次查找 config.toml 与 config.json
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// 监听地址
+    #[arg(long, global = true)]
+    pub host: Option<String>,
+
+    /// 监听端口
+    #[arg(short, long, global = true)]
+    pub port: Option<u16>,
+
+    /// 数据库文件路径
+    #[arg(long, global = true)]
+    pub db_url: Option<String>,
+
+    /// 书籍目录
+    #[arg(long, global = true)]
+    pub book_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 启动服务器，未指定子命令时的默认行为
+    Serve,
+    /// 创建或更新数据库表结构
+    Migrate,
+    /// 管理员账号维护
+    #[command(subcommand)]
+    Admin(AdminCommand),
+    /// 用户管理
+    #[command(subcommand)]
+    User(UserCommand),
+    /// 邀请码管理
+    #[command(subcommand)]
+    Invite(InviteCommand),
+    /// 将目录中的 TXT 书籍批量导入到指定用户名下
+    Import {
+        /// 书籍所在目录
+        dir: PathBuf,
+        /// 导入到该用户名下
+        #[arg(short, long)]
+        user: String,
+        /// 导入后公开
+        #[arg(long)]
+        public: bool,
+    },
+    /// 重新识别书籍章节
+    Reindex {
+        /// 只处理指定的书籍，默认处理全部书籍
+        #[arg(long)]
+        book_id: Option<i64>,
+    },
+    /// 立即创建一次备份
+    Backup,
+    /// 从备份恢复数据，需要先停止服务
+    Restore {
+        /// 备份文件路径
+        archive: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// 重置管理员密码
+    ResetPassword {
+        /// 管理员用户名，默认为最早创建的管理员
+        #[arg(short, long)]
+        username: Option<String>,
+        /// 新密码，未指定时从标准输入读取
+        #[arg(long)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// 创建用户
+    Create {
+        /// 用户名
+        username: String,
+        /// 密码，未指定时从标准输入读取
+        #[arg(long)]
+        password: Option<String>,
+        /// 角色
+        #[arg(long, value_enum, default_value_t = Role::Uploader)]
+        role: Role,
+    },
+    /// 列出所有用户
+    List,
+    /// 禁用用户，已签发的令牌随之失效
+    Disable {
+        /// 用户名
+        username: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InviteCommand {
+    /// 生成邀请码
+    Create {
+        /// 生成数量
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+        /// 每个邀请码可使用的次数，负数表示不限次数
+        #[arg(long, default_value_t = 1, allow_negative_numbers = true)]
+        limit_times: i64,
+        /// 自定义邀请码，仅在生成一个时可用
+        #[arg(long, conflicts_with = "count")]
+        code: Option<String>,
+        /// 备注
+        #[arg(long)]
+        description: Option<String>,
+        /// 过期时间（RFC 3339）
+        #[arg(long)]
+        expires_at: Option<String>,
+    },
+}
+
+impl Cli {
+    pub fn config_overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            config_path: self.config.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            db_url: self.db_url.clone(),
+            book_dir: self.book_dir.clone(),
+        }
+    }
+}
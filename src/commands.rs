@@ -0,0 +1,393 @@
+use std::{io::BufRead, path::Path};
+
+use anyhow::{bail, Context, Result};
+use sqlx::{Pool, Sqlite};
+use tokio::fs;
+
+use crate::{
+    audit::{self, Actor, ClientIp},
+    backup,
+    cli::{AdminCommand, Command, InviteCommand, UserCommand},
+    config::Config,
+    db, invite,
+    models::Role,
+    password_policy, storage,
+    utils::{extract_chapters, hash_password, validate_username},
+};
+
+// 执行需要数据库的管理子命令，结果直接输出到标准输出
+pub async fn run(pool: &Pool<Sqlite>, config: &Config, command: Command) -> Result<()> {
+    match command {
+        // 迁移已在打开数据库时完成，这里只输出结果
+        Command::Migrate => {
+            let version = db::schema_version(&mut *pool.acquire().await?).await?;
+            println!("数据库结构已是最新版本（版本 {}）", version);
+        }
+        Command::Admin(AdminCommand::ResetPassword { username, password }) => {
+            reset_admin_password(pool, config, username, password).await?
+        }
+        Command::User(UserCommand::Create {
+            username,
+            password,
+            role,
+        }) => create_user(pool, config, &username, password, role).await?,
+        Command::User(UserCommand::List) => list_users(pool).await?,
+        Command::User(UserCommand::Disable { username }) => disable_user(pool, &username).await?,
+        Command::Invite(InviteCommand::Create {
+            count,
+            limit_times,
+            code,
+            description,
+            expires_at,
+        }) => {
+            create_invite_codes(
+                pool,
+                count,
+                limit_times,
+                code.as_deref(),
+                description.as_deref(),
+                expires_at.as_deref(),
+            )
+            .await?
+        }
+        Command::Import { dir, user, public } => {
+            import_books(pool, config, &dir, &user, public).await?
+        }
+        Command::Reindex { book_id } => reindex_books(pool, book_id).await?,
+        Command::Backup => {
+            let backup = backup::create_backup(pool, config).await?;
+            println!("已创建备份 {}（{} 字节）", backup.name, backup.size);
+        }
+        Command::Serve | Command::Restore { .. } => unreachable!("由 main 直接处理"),
+    }
+
+    Ok(())
+}
+
+// 重置管理员密码，用于忘记密码后恢复管理权限
+async fn reset_admin_password(
+    pool: &Pool<Sqlite>,
+    config: &Config,
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<()> {
+    let admin = match &username {
+        Some(username) => sqlx::query_as::<_, (i64, String, Role)>(
+            "SELECT id, username, role FROM users WHERE username = ?",
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await?
+        .with_context(|| format!("用户 {} 不存在", username))?,
+        None => sqlx::query_as::<_, (i64, String, Role)>(
+            "SELECT id, username, role FROM users WHERE role = 'admin' ORDER BY id LIMIT 1",
+        )
+        .fetch_optional(pool)
+        .await?
+        .context("尚未设置管理员")?,
+    };
+    let (admin_id, admin_username, role) = admin;
+    if role != Role::Admin {
+        bail!("用户 {} 不是管理员", admin_username);
+    }
+
+    let password = read_password(password)?;
+    password_policy::enforce(pool, config, &password).await?;
+    let password_hash = hash_password(&password)?;
+
+    sqlx::query!(
+        "UPDATE users SET password_hash = ? WHERE id = ?",
+        password_hash,
+        admin_id
+    )
+    .execute(pool)
+    .await?;
+
+    audit::record(
+        pool,
+        Actor::System,
+        "user.password_reset",
+        Some(("user", admin_id)),
+        None,
+        &ClientIp::default(),
+    )
+    .await;
+
+    println!("已重置管理员 {} 的密码", admin_username);
+    Ok(())
+}
+
+// 创建已激活的用户，不受注册模式限制
+async fn create_user(
+    pool: &Pool<Sqlite>,
+    config: &Config,
+    username: &str,
+    password: Option<String>,
+    role: Role,
+) -> Result<()> {
+    validate_username(username)?;
+
+    let username_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE username = ?)")
+            .bind(username)
+            .fetch_one(pool)
+            .await?;
+    if username_exists {
+        bail!("用户名 {} 已存在", username);
+    }
+
+    let password = read_password(password)?;
+    password_policy::enforce(pool, config, &password).await?;
+    let password_hash = hash_password(&password)?;
+
+    let mut tx = pool.begin().await?;
+    let user_id = db::create_user(&mut tx, username, &password_hash, "active").await?;
+    sqlx::query!("UPDATE users SET role = ? WHERE id = ?", role, user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    audit::record(
+        pool,
+        Actor::System,
+        "user.create",
+        Some(("user", user_id)),
+        Some(serde_json::json!({ "username": username, "role": role })),
+        &ClientIp::default(),
+    )
+    .await;
+
+    println!("已创建用户 {}（ID {}）", username, user_id);
+    Ok(())
+}
+
+// 以表格形式列出所有用户
+async fn list_users(pool: &Pool<Sqlite>) -> Result<()> {
+    let users = sqlx::query_as::<_, (i64, String, String, String, String)>(
+        "SELECT id, username, role, status, created_at FROM users ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    println!(
+        "{:<6} {:<20} {:<10} {:<9} 创建时间",
+        "ID", "用户名", "角色", "状态"
+    );
+    for (user_id, username, role, status, created_at) in users {
+        println!(
+            "{:<6} {:<20} {:<10} {:<9} {}",
+            user_id, username, role, status, created_at
+        );
+    }
+
+    Ok(())
+}
+
+// 禁用用户，不允许禁用唯一的管理员
+async fn disable_user(pool: &Pool<Sqlite>, username: &str) -> Result<()> {
+    let user_id = find_user_id(pool, username).await?;
+    if db::is_last_admin(pool, user_id).await? {
+        bail!("不能禁用唯一的管理员");
+    }
+
+    sqlx::query!("UPDATE users SET status = 'disabled' WHERE id = ?", user_id)
+        .execute(pool)
+        .await?;
+
+    audit::record(
+        pool,
+        Actor::System,
+        "user.disable",
+        Some(("user", user_id)),
+        None,
+        &ClientIp::default(),
+    )
+    .await;
+
+    println!("已禁用用户 {}", username);
+    Ok(())
+}
+
+// 生成邀请码，全部生成成功才提交
+async fn create_invite_codes(
+    pool: &Pool<Sqlite>,
+    count: u32,
+    limit_times: i64,
+    code: Option<&str>,
+    description: Option<&str>,
+    expires_at: Option<&str>,
+) -> Result<()> {
+    if !(1..=100).contains(&count) {
+        bail!("生成数量必须在 1-100 之间");
+    }
+    if let Some(code) = code {
+        invite::validate_code(code)?;
+    }
+    let expires_at = invite::parse_expires_at(expires_at)?;
+
+    let mut tx = pool.begin().await?;
+    let mut invite_codes = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        invite_codes.push(
+            invite::insert(
+                &mut tx,
+                code,
+                limit_times,
+                description,
+                expires_at.as_deref(),
+            )
+            .await?,
+        );
+    }
+    tx.commit().await?;
+
+    audit::record(
+        pool,
+        Actor::System,
+        "invite_code.create",
+        None,
+        Some(serde_json::json!({ "invite_codes": invite_codes, "limit_times": limit_times, "expires_at": expires_at })),
+        &ClientIp::default(),
+    )
+    .await;
+
+    for invite_code in invite_codes {
+        println!("{}", invite_code);
+    }
+    Ok(())
+}
+
+// 导入目录中的全部 TXT 文件，书名取文件名
+// 由运维人员执行，不受上传大小与配额的限制；单个文件失败不影响其他文件
+async fn import_books(
+    pool: &Pool<Sqlite>,
+    config: &Config,
+    dir: &Path,
+    username: &str,
+    is_public: bool,
+) -> Result<()> {
+    let user_id = find_user_id(pool, username).await?;
+
+    let mut paths = Vec::new();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("无法读取目录 {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_txt = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+        if is_txt && entry.file_type().await?.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut imported = 0;
+    let mut failed = 0;
+    for path in &paths {
+        match import_book(pool, config, user_id, path, is_public).await {
+            Ok(chapter_count) => {
+                imported += 1;
+                println!("已导入 {}（{} 章）", path.display(), chapter_count);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("导入 {} 失败: {}", path.display(), e);
+            }
+        }
+    }
+
+    println!("导入完成：成功 {} 本，失败 {} 本", imported, failed);
+    if failed > 0 {
+        bail!("有 {} 本书籍导入失败", failed);
+    }
+    Ok(())
+}
+
+// 导入单本书籍，返回识别出的章节数
+async fn import_book(
+    pool: &Pool<Sqlite>,
+    config: &Config,
+    user_id: i64,
+    path: &Path,
+    is_public: bool,
+) -> Result<usize> {
+    let content = String::from_utf8(fs::read(path).await?).context("文件编码不是有效的UTF-8")?;
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let chapters = extract_chapters(&content);
+    let chapter_count = chapters.len();
+
+    // 保存失败时 book_file 被丢弃，已写入的文件随之删除
+    let book_file = storage::write_book_file(&config.storage.book_dir, &content).await?;
+    let new_book = storage::NewBook {
+        user_id,
+        title: &title,
+        author: None,
+        file_path: book_file.path(),
+        file_size: content.len() as i64,
+        is_public,
+        enforce_quota: false,
+    };
+    storage::save_book(pool, &new_book, chapters).await?;
+    book_file.keep();
+
+    Ok(chapter_count)
+}
+
+// 重新识别章节，单本书失败不影响其他书籍
+async fn reindex_books(pool: &Pool<Sqlite>, book_id: Option<i64>) -> Result<()> {
+    let books = sqlx::query_as::<_, (i64, String, String)>(
+        "SELECT id, title, file_path FROM books WHERE ? IS NULL OR id = ? ORDER BY id",
+    )
+    .bind(book_id)
+    .bind(book_id)
+    .fetch_all(pool)
+    .await?;
+    if let (Some(book_id), true) = (book_id, books.is_empty()) {
+        bail!("书籍 {} 不存在", book_id);
+    }
+
+    let mut failed = 0;
+    for (book_id, title, file_path) in &books {
+        match storage::reindex_book(pool, *book_id, file_path).await {
+            Ok(chapter_count) => println!("{} {}：{} 章", book_id, title, chapter_count),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{} {} 重新识别失败: {}", book_id, title, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("有 {} 本书籍重新识别失败", failed);
+    }
+    Ok(())
+}
+
+async fn find_user_id(pool: &Pool<Sqlite>, username: &str) -> Result<i64> {
+    sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await?
+        .with_context(|| format!("用户 {} 不存在", username))
+}
+
+// 未通过参数指定密码时从标准输入读取一行，避免密码留在命令历史中
+fn read_password(password: Option<String>) -> Result<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+
+    eprint!("请输入密码: ");
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        bail!("密码不能为空");
+    }
+    Ok(password)
+}
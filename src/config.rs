@@ -1,6 +1,12 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 
@@ -10,12 +16,49 @@ pub struct Config {
     pub db: DbConfig,
     pub storage: StorageConfig,
     pub jwt: JwtConfig,
+    #[serde(default)]
+    pub proxy_auth: ProxyAuthConfig,
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+    #[serde(default)]
+    pub password: PasswordConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub log: LogConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
+    // 监听地址，支持 IPv4（如 0.0.0.0）、IPv6（如 ::）与主机名
     pub host: String,
     pub port: u16,
+    // 额外监听的 Unix 域套接字路径，供同一主机上的反向代理使用
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
+    // Unix 域套接字文件的权限，八进制字符串，默认只允许属主与同组用户连接
+    #[serde(default = "default_unix_socket_mode")]
+    pub unix_socket_mode: String,
+    // 内置 TLS，证书与私钥为 PEM 格式，收到 SIGHUP 时重新加载
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    // 收到 SIGINT/SIGTERM 后等待进行中请求完成的最长时间（秒），超时后强制中止
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_unix_socket_mode() -> String {
+    "660".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,13 +70,166 @@ pub struct DbConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub book_dir: PathBuf,
+    #[serde(default = "default_avatar_dir")]
+    pub avatar_dir: PathBuf,
+    // 启动时检查书籍文件与数据库是否一致
+    #[serde(default)]
+    pub startup_check: StartupCheck,
+    // 书籍正文缓存的容量（MB），0 表示不缓存
+    #[serde(default = "default_content_cache_mb")]
+    pub content_cache_mb: u64,
+}
+
+fn default_content_cache_mb() -> u64 {
+    64
+}
+
+// 启动时的一致性检查方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupCheck {
+    // 不检查
+    #[default]
+    Off,
+    // 只在日志中报告问题
+    Report,
+    // 报告并修复问题
+    Repair,
+}
+
+fn default_avatar_dir() -> PathBuf {
+    PathBuf::from("avatars")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtConfig {
     pub secret: String,
     pub expiration: u64, // 过期时间，单位为秒
-    pub admin_expiration: u64,
+}
+
+// 密码策略相关配置，策略本身在系统设置中由管理员调整
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordConfig {
+    // 额外的泄露密码列表文件，每行一个密码，与内置列表一起使用
+    pub breached_list: Option<PathBuf>,
+    // 启动时从 breached_list 加载的密码，已转为小写
+    #[serde(skip)]
+    pub breached_passwords: Arc<HashSet<String>>,
+}
+
+// 备份配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    // 备份文件目录
+    pub dir: PathBuf,
+    // 定时备份间隔（小时），为空或 0 表示不定时备份
+    pub interval_hours: Option<u64>,
+    // 保留的备份数量，0 表示全部保留
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig {
+            dir: PathBuf::from("backups"),
+            interval_hours: None,
+            keep: 7,
+        }
+    }
+}
+
+// 日志配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    // 日志过滤规则，语法同 RUST_LOG；设置了 RUST_LOG 环境变量时以环境变量为准
+    pub filter: Option<String>,
+}
+
+// 日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    // 便于阅读的文本
+    #[default]
+    Text,
+    // 每行一个 JSON 对象，便于日志系统采集
+    Json,
+}
+
+// 反向代理头认证配置（Authelia / oauth2-proxy 等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyAuthConfig {
+    pub enabled: bool,
+    // 携带用户名的请求头
+    pub header: String,
+    // 受信任的代理地址，支持单个 IP 或 CIDR
+    pub trusted_proxies: Vec<String>,
+    // 是否信任经 Unix 域套接字连接的代理，该连接没有对端地址，不受 trusted_proxies 影响
+    pub trust_unix_socket: bool,
+    // 首次出现的用户是否自动创建，与注册接口一样受注册模式限制
+    pub auto_create: bool,
+}
+
+impl Default for ProxyAuthConfig {
+    fn default() -> Self {
+        ProxyAuthConfig {
+            enabled: false,
+            header: "Remote-User".to_string(),
+            trusted_proxies: vec!["127.0.0.1".to_string(), "::1".to_string()],
+            trust_unix_socket: false,
+            auto_create: true,
+        }
+    }
+}
+
+impl ProxyAuthConfig {
+    // 判断对端地址是否为受信任的代理
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|proxy| {
+            if let Ok(net) = proxy.parse::<IpNet>() {
+                net.contains(&ip)
+            } else if let Ok(addr) = proxy.parse::<IpAddr>() {
+                addr == ip
+            } else {
+                tracing::warn!("无法解析受信任代理地址: {}", proxy);
+                false
+            }
+        })
+    }
+}
+
+// OpenID Connect 登录配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    // 签发者地址，用于拼接 /.well-known/openid-configuration
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    // 登录完成后身份提供方回调的前端地址
+    pub redirect_uri: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    // 新建用户时作为用户名的声明
+    #[serde(default = "default_oidc_username_claim")]
+    pub username_claim: String,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "profile".to_string(),
+        "email".to_string(),
+    ]
+}
+
+fn default_oidc_username_claim() -> String {
+    "preferred_username".to_string()
 }
 
 impl Default for Config {
@@ -42,6 +238,10 @@ impl Default for Config {
             server: ServerConfig {
                 host: "127.0.0.1".to_string(),
                 port: 3000,
+                unix_socket: None,
+                unix_socket_mode: default_unix_socket_mode(),
+                tls: None,
+                shutdown_timeout: default_shutdown_timeout(),
             },
             db: DbConfig {
                 url: std::env::var("DATABASE_URL").unwrap_or("sqlite:reader.db".to_string()),
@@ -49,30 +249,182 @@ impl Default for Config {
             },
             storage: StorageConfig {
                 book_dir: PathBuf::from("books"),
+                avatar_dir: default_avatar_dir(),
+                startup_check: StartupCheck::default(),
+                content_cache_mb: default_content_cache_mb(),
             },
             jwt: JwtConfig {
-                secret: "super_secret_key_change_me_in_production".to_string(),
-                expiration: 60 * 60 * 24 * 30,      // 30天
-                admin_expiration: 60 * 60 * 24 * 7, // 7天
+                secret: PLACEHOLDER_JWT_SECRET.to_string(),
+                expiration: 60 * 60 * 24 * 30, // 30天
             },
+            proxy_auth: ProxyAuthConfig::default(),
+            oidc: None,
+            password: PasswordConfig::default(),
+            backup: BackupConfig::default(),
+            log: LogConfig::default(),
         }
     }
 }
 
-pub async fn load_config() -> Result<Config> {
-    // 配置文件路径
-    let config_path = PathBuf::from("config.json");
+// 占位用的旧默认密钥，仍在使用时给出警告
+const PLACEHOLDER_JWT_SECRET: &str = "super_secret_key_change_me_in_production";
+
+// 环境变量前缀，如 READER_JWT_SECRET 对应 jwt.secret
+const ENV_PREFIX: &str = "READER";
 
-    // 如果配置文件存在，从文件加载配置
-    if config_path.exists() {
-        let config_str = fs::read_to_string(config_path).await?;
-        let config: Config = serde_json::from_str(&config_str)?;
-        Ok(config)
+// 命令行中可以直接覆盖的配置
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    // 配置文件路径，支持 .json 与 .toml
+    pub config_path: Option<PathBuf>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub db_url: Option<String>,
+    pub book_dir: Option<PathBuf>,
+}
+
+// 按优先级从低到高加载配置：默认值、配置文件、环境变量、命令行参数
+pub async fn load_config(overrides: &ConfigOverrides) -> Result<Config> {
+    load_config_with_env(overrides, std::env::vars()).await
+}
+
+pub async fn load_config_with_env(
+    overrides: &ConfigOverrides,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Config> {
+    // 未指定路径时依次查找 config.toml 与 config.json
+    let config_path = overrides.config_path.clone().unwrap_or_else(|| {
+        [PathBuf::from("config.toml"), PathBuf::from("config.json")]
+            .into_iter()
+            .find(|path| path.exists())
+            .unwrap_or_else(|| PathBuf::from("config.json"))
+    });
+
+    let config = if config_path.exists() {
+        // 从文件加载配置
+        let config_str = fs::read_to_string(&config_path).await?;
+        parse_config(&config_path, &config_str)
+            .with_context(|| format!("无法解析配置文件 {}", config_path.display()))?
     } else {
-        // 否则使用默认配置，并写入配置文件
-        let config = Config::default();
-        let config_str = serde_json::to_string_pretty(&config)?;
-        fs::write(config_path, config_str).await?;
-        Ok(config)
+        // 否则使用默认配置并生成随机的 JWT 密钥，写入配置文件
+        let mut config = Config::default();
+        config.jwt.secret = generate_jwt_secret();
+        let config_str = if is_toml(&config_path) {
+            toml::to_string_pretty(&config)?
+        } else {
+            serde_json::to_string_pretty(&config)?
+        };
+        fs::write(&config_path, config_str)
+            .await
+            .with_context(|| format!("无法写入配置文件 {}", config_path.display()))?;
+        tracing::info!("已生成默认配置文件 {}", config_path.display());
+        config
+    };
+
+    let mut config = apply_env(config, vars)?;
+
+    // 命令行参数优先级最高
+    if let Some(host) = &overrides.host {
+        config.server.host = host.clone();
+    }
+    if let Some(port) = overrides.port {
+        config.server.port = port;
+    }
+    if let Some(db_url) = &overrides.db_url {
+        config.db.url = db_url.clone();
+    }
+    if let Some(book_dir) = &overrides.book_dir {
+        config.storage.book_dir = book_dir.clone();
+    }
+
+    // 额外的泄露密码列表只在启动时读取一次，文件不存在时拒绝启动
+    if let Some(path) = &config.password.breached_list {
+        let list = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("无法读取泄露密码列表 {}", path.display()))?;
+        config.password.breached_passwords = Arc::new(crate::password_policy::parse_list(&list));
+    }
+
+    if config.jwt.secret == PLACEHOLDER_JWT_SECRET {
+        tracing::warn!("正在使用默认的 JWT 密钥，请修改 jwt.secret 或设置 READER_JWT_SECRET");
     }
+
+    Ok(config)
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+fn parse_config(path: &Path, content: &str) -> Result<Config> {
+    if is_toml(path) {
+        Ok(toml::from_str(content)?)
+    } else {
+        Ok(serde_json::from_str(content)?)
+    }
+}
+
+// 生成 32 字节的随机密钥
+fn generate_jwt_secret() -> String {
+    let bytes: [u8; 32] = rand::random();
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// 用环境变量覆盖配置中的任意字段
+// 变量名由前缀与字段路径组成，如 READER_SERVER_PORT、READER_PROXY_AUTH_TRUSTED_PROXIES
+// 列表可以使用 JSON 数组或逗号分隔；未设置的可选配置段（如 oidc、server.tls）以 JSON 对象整体设置
+fn apply_env(config: Config, vars: impl IntoIterator<Item = (String, String)>) -> Result<Config> {
+    let vars: HashMap<String, String> = vars
+        .into_iter()
+        .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+        .collect();
+    if vars.is_empty() {
+        return Ok(config);
+    }
+
+    let mut value = serde_json::to_value(&config)?;
+    let mut applied = Vec::new();
+    overlay_env(&mut value, ENV_PREFIX, &vars, &mut applied)?;
+    if applied.is_empty() {
+        return Ok(config);
+    }
+
+    serde_json::from_value(value)
+        .with_context(|| format!("环境变量 {} 中的配置无效", applied.join(", ")))
+}
+
+fn overlay_env(
+    value: &mut serde_json::Value,
+    name: &str,
+    vars: &HashMap<String, String>,
+    applied: &mut Vec<String>,
+) -> Result<()> {
+    if let serde_json::Value::Object(fields) = value {
+        for (field, child) in fields.iter_mut() {
+            let child_name = format!("{}_{}", name, field.to_uppercase());
+            overlay_env(child, &child_name, vars, applied)?;
+        }
+        return Ok(());
+    }
+
+    let Some(raw) = vars.get(name) else {
+        return Ok(());
+    };
+    *value = match value {
+        serde_json::Value::String(_) => serde_json::Value::String(raw.clone()),
+        serde_json::Value::Array(_) => serde_json::from_str(raw).unwrap_or_else(|_| {
+            raw.split(',')
+                .map(|item| serde_json::Value::String(item.trim().to_string()))
+                .filter(|item| item.as_str() != Some(""))
+                .collect()
+        }),
+        serde_json::Value::Null => {
+            serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.clone()))
+        }
+        _ => serde_json::from_str(raw)
+            .with_context(|| format!("环境变量 {} 的值无效: {}", name, raw))?,
+    };
+    applied.push(name.to_string());
+
+    Ok(())
 }
@@ -1,22 +1,26 @@
-use std::path::Path;
+use std::{path::Path, str::FromStr};
 
-use anyhow::Result;
-use sqlx::{sqlite::SqliteConnectOptions, Executor, Pool, Sqlite, SqlitePool};
+use anyhow::{bail, Context, Result};
+use sqlx::{
+    sqlite::SqliteConnectOptions, Connection, Executor, Pool, Sqlite, SqliteConnection, SqlitePool,
+};
 use tokio::fs;
 
-use crate::config::Config;
+use crate::{config::Config, models::Settings};
+
+// 当前时间，与数据库中时间字段的格式一致
+pub const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%S.' || substr(strftime('%f'), 4, 6) || 'Z')";
 
 pub async fn init_db_pool(config: &Config) -> Result<Pool<Sqlite>> {
+    let options = connect_options(config)?.create_if_missing(true);
+
     // 确保数据库文件的目录存在
-    if let Some(db_path) = Path::new(&config.db.url).parent() {
+    if let Some(db_path) = options.get_filename().parent() {
         if !db_path.exists() {
             fs::create_dir_all(db_path).await?;
         }
     }
-
-    let options = SqliteConnectOptions::new()
-        .filename(&config.db.url)
-        .create_if_missing(true);
+    adopt_legacy_db_file(&config.db.url, options.get_filename()).await?;
 
     // 创建连接池
     let pool = SqlitePool::connect_with(options).await?;
@@ -25,32 +29,253 @@ pub async fn init_db_pool(config: &Config) -> Result<Pool<Sqlite>> {
     Ok(pool)
 }
 
+// 解析数据库地址，支持 sqlite:reader.db、sqlite://data/reader.db 与不带前缀的文件路径
+pub fn connect_options(config: &Config) -> Result<SqliteConnectOptions, sqlx::Error> {
+    SqliteConnectOptions::from_str(&config.db.url)
+}
+
+// 旧版本把数据库地址原样当作文件名，默认的 sqlite:reader.db 会创建名为 sqlite:reader.db 的文件
+// 解析后的数据库文件不存在而旧文件存在时，将旧文件（连同 WAL 文件）移动到新位置继续使用
+async fn adopt_legacy_db_file(url: &str, db_path: &Path) -> Result<()> {
+    let legacy_path = Path::new(url);
+    if legacy_path == db_path || db_path.exists() || !legacy_path.is_file() {
+        return Ok(());
+    }
+
+    for suffix in ["", "-wal", "-shm"] {
+        let from = format!("{}{}", legacy_path.display(), suffix);
+        if Path::new(&from).exists() {
+            let to = format!("{}{}", db_path.display(), suffix);
+            fs::rename(&from, &to)
+                .await
+                .with_context(|| format!("无法将旧数据库文件 {} 移动到 {}", from, to))?;
+        }
+    }
+    tracing::warn!(
+        "已将旧数据库文件 {} 移动到 {}",
+        legacy_path.display(),
+        db_path.display()
+    );
+
+    Ok(())
+}
+
+// 已有数据库的升级步骤，第 N 项把结构版本（PRAGMA user_version）从 N 升级到 N+1，只能在末尾追加
+// schema.sql 始终描述最新的结构，新数据库直接按它创建并记为最新版本
+const MIGRATIONS: &[&str] = &[
+    include_str!("../migrations/001_oidc_login.sql"),
+    include_str!("../migrations/002_user_profile.sql"),
+    include_str!("../migrations/003_password_policy.sql"),
+    include_str!("../migrations/004_book_file_size.sql"),
+    include_str!("../migrations/005_user_status_and_quotas.sql"),
+    include_str!("../migrations/006_invite_code_lifecycle.sql"),
+    include_str!("../migrations/007_registration_mode.sql"),
+    include_str!("../migrations/008_reading_daily_stats.sql"),
+    include_str!("../migrations/009_audit_log.sql"),
+    include_str!("../migrations/010_user_roles.sql"),
+    include_str!("../migrations/011_announcements_and_maintenance.sql"),
+    include_str!("../migrations/012_local_password.sql"),
+];
+
+// 当前程序使用的数据库结构版本
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+// 从该版本起记录书籍文件大小
+const FILE_SIZE_VERSION: i64 = 4;
+
+// 该版本将原管理员表并入用户表
+const USER_ROLES_VERSION: i64 = 10;
+
+// 创建或升级数据库结构，每个版本的升级在单独的事务中完成
 pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<()> {
-    // 使用 schema 模块中定义的 SQL 语句
-    let sql = include_str!("../schema.sql");
-    let statements = sql.split(';').filter(|s| !s.trim().is_empty());
+    let mut conn = pool.acquire().await?;
+    let version = schema_version(&mut conn).await?;
+    if version > SCHEMA_VERSION {
+        bail!(
+            "数据库结构版本 {} 高于程序支持的版本 {}，请升级程序",
+            version,
+            SCHEMA_VERSION
+        );
+    }
 
-    for statement in statements {
-        let query = format!("{};", statement);
-        sqlx::query(&query).execute(pool).await?;
+    let has_tables = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'users')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !has_tables {
+        // 使用 schema.sql 创建新数据库，整体执行以支持包含分号的触发器
+        let mut tx = conn.begin().await?;
+        sqlx::raw_sql(include_str!("../schema.sql"))
+            .execute(&mut *tx)
+            .await?;
+        set_schema_version(&mut tx, SCHEMA_VERSION).await?;
+        tx.commit().await?;
+        return Ok(());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let target = index as i64 + 1;
+        let mut tx = conn.begin().await?;
+        sqlx::raw_sql(migration)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("数据库结构升级到版本 {} 失败", target))?;
+        set_schema_version(&mut tx, target).await?;
+        tx.commit().await?;
+        tracing::info!("数据库结构已升级到版本 {}", target);
+
+        // 升级前的书籍没有记录文件大小
+        if target == FILE_SIZE_VERSION {
+            backfill_file_sizes(&mut conn).await?;
+        }
+
+        // 原管理员的用户名可能因重名而改变，提示管理员使用新的用户名登录
+        if target == USER_ROLES_VERSION {
+            let admin = sqlx::query_scalar::<_, String>(
+                "SELECT username FROM users WHERE role = 'admin' ORDER BY id LIMIT 1",
+            )
+            .fetch_optional(&mut *conn)
+            .await?;
+            if let Some(username) = admin {
+                tracing::warn!("原管理员账号已迁移为用户 {}，请使用该用户名登录", username);
+            }
+        }
     }
 
     Ok(())
 }
 
+// 读取数据库结构版本
+pub async fn schema_version(conn: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await
+}
+
+async fn set_schema_version(conn: &mut SqliteConnection, version: i64) -> Result<(), sqlx::Error> {
+    // PRAGMA 不支持参数绑定，版本号为整数，直接拼接
+    sqlx::query(&format!("PRAGMA user_version = {}", version))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+// 按文件实际大小补充书籍的 file_size，文件缺失时保持为 0
+async fn backfill_file_sizes(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let books =
+        sqlx::query_as::<_, (i64, String)>("SELECT id, file_path FROM books WHERE file_size = 0")
+            .fetch_all(&mut *conn)
+            .await?;
+    for (book_id, file_path) in books {
+        if let Ok(metadata) = fs::metadata(&file_path).await {
+            sqlx::query("UPDATE books SET file_size = ? WHERE id = ?")
+                .bind(metadata.len() as i64)
+                .bind(book_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// 创建用户，并为其初始化默认阅读设置
+pub async fn create_user(
+    conn: &mut SqliteConnection,
+    username: &str,
+    password_hash: &str,
+    status: &str,
+) -> Result<i64, sqlx::Error> {
+    let user_id = sqlx::query!(
+        "INSERT INTO users (username, password_hash, status) VALUES (?, ?, ?)",
+        username,
+        password_hash,
+        status
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    sqlx::query!("INSERT INTO reading_settings (user_id) VALUES (?)", user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(user_id)
+}
+
+// 创建没有本地密码的用户（反向代理或 OIDC 首次登录），password_hash 为随机值
+// 这类用户修改密码或注销账号时以刚完成的登录确认身份
+pub async fn create_passwordless_user(
+    conn: &mut SqliteConnection,
+    username: &str,
+    password_hash: &str,
+    status: &str,
+) -> Result<i64, sqlx::Error> {
+    let user_id = create_user(conn, username, password_hash, status).await?;
+    sqlx::query!("UPDATE users SET has_password = 0 WHERE id = ?", user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(user_id)
+}
+
+// 在没有管理员时创建第一个管理员，判断与插入在同一条语句中完成，并发设置时只有一个成功
+// 已有管理员时返回 None
+pub async fn create_first_admin(
+    conn: &mut SqliteConnection,
+    username: &str,
+    password_hash: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO users (username, password_hash, status, role)
+         SELECT ?, ?, 'active', 'admin'
+         WHERE NOT EXISTS (SELECT 1 FROM users WHERE role = 'admin')",
+        username,
+        password_hash
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let user_id = result.last_insert_rowid();
+    sqlx::query!("INSERT INTO reading_settings (user_id) VALUES (?)", user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Some(user_id))
+}
+
+// 检查用户是否为唯一的管理员，用于防止移除最后一个管理员
+pub async fn is_last_admin(pool: &Pool<Sqlite>, user_id: i64) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE id = ? AND role = 'admin')
+            AND NOT EXISTS(SELECT 1 FROM users WHERE id != ? AND role = 'admin' AND status = 'active')",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+// 读取系统设置，尚未初始化时返回默认设置
+pub async fn load_settings(pool: &Pool<Sqlite>) -> Result<Settings, sqlx::Error> {
+    let settings = sqlx::query_as::<_, Settings>("SELECT * FROM settings WHERE id = 1")
+        .fetch_optional(pool)
+        .await?
+        .unwrap_or_default();
+
+    Ok(settings)
+}
+
 // 为测试创建内存数据库连接池
 #[cfg(test)]
 pub async fn create_test_pool() -> Result<Pool<Sqlite>> {
     let pool = SqlitePool::connect("sqlite::memory:").await?;
 
     // 使用 schema 模块中定义的 SQL 语句创建表
-    let sql = include_str!("../schema.sql");
-    let statements = sql.split(';').filter(|s| !s.trim().is_empty());
-
-    for statement in statements {
-        let query = format!("{};", statement);
-        sqlx::query(&query).execute(&pool).await?;
-    }
+    run_migrations(&pool).await?;
 
     Ok(pool)
 }
@@ -5,6 +5,139 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::i18n::{self, Locale, Message};
+
+// 响应中的错误码，数值一经发布不再修改
+// 文档中的错误码表由 ErrorCode::markdown_table 生成
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    AuthFailed = 1001,
+    InvalidInviteCode = 1002,
+    UsernameTaken = 1003,
+    Unauthenticated = 1004,
+    AdminRequired = 1005,
+    AdminAlreadySetup = 1006,
+    WrongPassword = 1007,
+    PasswordPolicy = 1008,
+    AccountDisabled = 1009,
+    AccountPending = 1010,
+    RegistrationClosed = 1011,
+    RoleForbidden = 1012,
+    BookNotFound = 2001,
+    BookForbidden = 2002,
+    UnsupportedFileType = 2003,
+    FileTooLarge = 2004,
+    QuotaExceeded = 2005,
+    UserNotFound = 3001,
+    BadRequest = 400,
+    Forbidden = 403,
+    NotFound = 404,
+    Maintenance = 9001,
+    Unavailable = 9002,
+    Internal = 9999,
+}
+
+impl ErrorCode {
+    // 全部错误码，按文档中的顺序排列
+    pub const ALL: [ErrorCode; 24] = [
+        ErrorCode::AuthFailed,
+        ErrorCode::InvalidInviteCode,
+        ErrorCode::UsernameTaken,
+        ErrorCode::Unauthenticated,
+        ErrorCode::AdminRequired,
+        ErrorCode::AdminAlreadySetup,
+        ErrorCode::WrongPassword,
+        ErrorCode::PasswordPolicy,
+        ErrorCode::AccountDisabled,
+        ErrorCode::AccountPending,
+        ErrorCode::RegistrationClosed,
+        ErrorCode::RoleForbidden,
+        ErrorCode::BookNotFound,
+        ErrorCode::BookForbidden,
+        ErrorCode::UnsupportedFileType,
+        ErrorCode::FileTooLarge,
+        ErrorCode::QuotaExceeded,
+        ErrorCode::UserNotFound,
+        ErrorCode::BadRequest,
+        ErrorCode::Forbidden,
+        ErrorCode::NotFound,
+        ErrorCode::Maintenance,
+        ErrorCode::Unavailable,
+        ErrorCode::Internal,
+    ];
+
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::AuthFailed | ErrorCode::Unauthenticated => StatusCode::UNAUTHORIZED,
+            ErrorCode::AdminRequired
+            | ErrorCode::AccountDisabled
+            | ErrorCode::AccountPending
+            | ErrorCode::RegistrationClosed
+            | ErrorCode::RoleForbidden
+            | ErrorCode::BookForbidden
+            | ErrorCode::QuotaExceeded
+            | ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::BookNotFound | ErrorCode::UserNotFound | ErrorCode::NotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ErrorCode::Maintenance | ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::AuthFailed => "用户名或密码错误",
+            ErrorCode::InvalidInviteCode => "邀请码无效、已用完或已失效",
+            ErrorCode::UsernameTaken => "用户名已存在",
+            ErrorCode::Unauthenticated => "未登录或登录已过期",
+            ErrorCode::AdminRequired => "需要管理员权限",
+            ErrorCode::AdminAlreadySetup => "管理员已设置，无法重复设置",
+            ErrorCode::WrongPassword => "旧密码不正确",
+            ErrorCode::PasswordPolicy => "密码不符合安全策略",
+            ErrorCode::AccountDisabled => "账号已被禁用",
+            ErrorCode::AccountPending => "账号尚未通过审核",
+            ErrorCode::RegistrationClosed => "系统已关闭注册",
+            ErrorCode::RoleForbidden => "当前角色没有此操作的权限",
+            ErrorCode::BookNotFound => "书籍不存在",
+            ErrorCode::BookForbidden => "用户无权限操作此书籍",
+            ErrorCode::UnsupportedFileType => "书籍文件格式不支持",
+            ErrorCode::FileTooLarge => "文件过大",
+            ErrorCode::QuotaExceeded => "超出上传配额",
+            ErrorCode::UserNotFound => "用户不存在",
+            ErrorCode::BadRequest => "请求参数无效",
+            ErrorCode::Forbidden => "没有权限",
+            ErrorCode::NotFound => "资源不存在",
+            ErrorCode::Maintenance => "系统维护中",
+            ErrorCode::Unavailable => "服务未就绪",
+            ErrorCode::Internal => "服务器内部错误",
+        }
+    }
+
+    // 生成文档中的错误码表
+    pub fn markdown_table() -> String {
+        let mut table = String::from(
+            "| 错误码 | HTTP 状态码 | 描述 | 英文提示 |\n| --- | --- | --- | --- |\n| 0 | 200 | 成功 | OK |\n",
+        );
+        for code in Self::ALL {
+            table.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                code.code(),
+                code.status().as_u16(),
+                code.description(),
+                i18n::error_message(code, Locale::En)
+            ));
+        }
+        table
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -17,14 +150,17 @@ pub enum AppError {
     #[error("JWT错误: {0}")]
     Jwt(#[from] jsonwebtoken::errors::Error),
 
-    #[error("参数验证失败: {0}")]
-    Validation(String),
+    #[error("参数验证失败: {1}")]
+    Validation(ErrorCode, String),
+
+    #[error("密码不符合安全策略: {0}")]
+    PasswordPolicy(String),
 
-    #[error("资源不存在: {0}")]
-    NotFound(String),
+    #[error("资源不存在: {1}")]
+    NotFound(ErrorCode, String),
 
-    #[error("权限不足: {0}")]
-    Forbidden(String),
+    #[error("权限不足: {1}")]
+    Forbidden(ErrorCode, String),
 
     #[error("IO错误: {0}")]
     Io(#[from] std::io::Error),
@@ -35,75 +171,115 @@ pub enum AppError {
     #[error("请求处理错误: {0}")]
     BadRequest(String),
 
+    #[error("{0}")]
+    Maintenance(String),
+
+    #[error("服务未就绪: {0}")]
+    Unavailable(String),
+
     #[error("内容解析错误: {0}")]
     ParseError(String),
+
+    // 附带机器可读的详细信息，如校验失败的字段
+    #[error("{0}")]
+    WithDetails(Box<AppError>, serde_json::Value),
+}
+
+impl AppError {
+    // 参数校验失败，details 中注明出错的字段
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation(ErrorCode::BadRequest, message.into()).for_field(field)
+    }
+
+    // 在 details 中注明出错的字段
+    pub fn for_field(self, field: &str) -> Self {
+        self.with_details(serde_json::json!({ "field": field }))
+    }
+
+    // 已有详细信息时替换为新的
+    pub fn with_details(self, details: serde_json::Value) -> Self {
+        match self {
+            AppError::WithDetails(error, _) => AppError::WithDetails(error, details),
+            error => AppError::WithDetails(Box::new(error), details),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Auth(_) => ErrorCode::AuthFailed,
+            AppError::Jwt(_) => ErrorCode::Unauthenticated,
+            AppError::Validation(code, _)
+            | AppError::NotFound(code, _)
+            | AppError::Forbidden(code, _) => *code,
+            AppError::PasswordPolicy(_) => ErrorCode::PasswordPolicy,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Maintenance(_) => ErrorCode::Maintenance,
+            AppError::Unavailable(_) => ErrorCode::Unavailable,
+            AppError::WithDetails(error, _) => error.code(),
+            AppError::Database(_)
+            | AppError::Io(_)
+            | AppError::Internal(_)
+            | AppError::ParseError(_) => ErrorCode::Internal,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub code: i32,
     pub message: String,
+    // 机器可读的详细信息，如 {"field": "username"}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    // 内部错误时附带请求ID，便于用户反馈问题时定位日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_code, message) = match &self {
-            AppError::Auth(_) => (StatusCode::UNAUTHORIZED, 1001, self.to_string()),
-            AppError::Validation(msg) if msg.contains("邀请码") => {
-                (StatusCode::BAD_REQUEST, 1002, self.to_string())
-            }
-            AppError::Validation(msg) if msg.contains("用户名已存在") => {
-                (StatusCode::BAD_REQUEST, 1003, self.to_string())
-            }
-            AppError::Jwt(_) => (
-                StatusCode::UNAUTHORIZED,
-                1004,
-                "未登录或登录已过期".to_string(),
-            ),
-            AppError::Forbidden(msg) if msg.contains("管理员") => {
-                (StatusCode::FORBIDDEN, 1005, self.to_string())
-            }
-            AppError::Validation(msg) if msg.contains("管理员已设置") => {
-                (StatusCode::BAD_REQUEST, 1006, self.to_string())
-            }
-            AppError::Validation(msg) if msg.contains("旧密码") => {
-                (StatusCode::BAD_REQUEST, 1007, self.to_string())
-            }
-            AppError::NotFound(msg) if msg.contains("书籍") => {
-                (StatusCode::NOT_FOUND, 2001, self.to_string())
-            }
-            AppError::Forbidden(msg) if msg.contains("书籍") => {
-                (StatusCode::FORBIDDEN, 2002, self.to_string())
-            }
-            AppError::Validation(msg) if msg.contains("格式") => {
-                (StatusCode::BAD_REQUEST, 2003, self.to_string())
-            }
-            AppError::Validation(msg) if msg.contains("过大") => {
-                (StatusCode::BAD_REQUEST, 2004, self.to_string())
-            }
-            AppError::NotFound(msg) if msg.contains("用户") => {
-                (StatusCode::NOT_FOUND, 3001, self.to_string())
+        let code = self.code();
+        let locale = i18n::current();
+        let request_id = if code == ErrorCode::Internal {
+            tracing::error!("内部服务器错误: {:?}", self);
+            crate::logging::current_request_id()
+        } else {
+            None
+        };
+        let details = match &self {
+            AppError::WithDetails(_, details) => Some(details.clone()),
+            _ => None,
+        };
+
+        let message = match (code, locale) {
+            // 不向客户端暴露内部错误与令牌校验的细节
+            (ErrorCode::Internal | ErrorCode::Unauthenticated, _) => {
+                i18n::error_message(code, locale).to_string()
             }
-            AppError::Validation(_) => (StatusCode::BAD_REQUEST, 400, self.to_string()),
-            AppError::NotFound(_) => (StatusCode::NOT_FOUND, 404, self.to_string()),
-            AppError::Forbidden(_) => (StatusCode::FORBIDDEN, 403, self.to_string()),
-            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, 400, self.to_string()),
+            // 维护提示由管理员填写，原样返回
+            (ErrorCode::Maintenance, _) | (_, Locale::ZhCn) => self.to_string(),
+            // 中文以外的语言使用错误码对应的提示，并注明出错的字段
             _ => {
-                tracing::error!("内部服务器错误: {:?}", self);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    9999,
-                    "服务器内部错误".to_string(),
-                )
+                let message = i18n::error_message(code, locale);
+                match details
+                    .as_ref()
+                    .and_then(|details| details["field"].as_str())
+                {
+                    Some(field) => format!("{}: {}", message, field),
+                    None => message.to_string(),
+                }
             }
         };
 
         let body = Json(ErrorResponse {
-            code: error_code,
+            code: code.code(),
             message,
+            details,
+            request_id,
         });
 
-        (status, body).into_response()
+        (code.status(), body).into_response()
     }
 }
 
@@ -114,7 +290,7 @@ impl From<anyhow::Error> for AppError {
 }
 
 // 定义API统一返回格式
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ApiResponse<T: Serialize> {
     pub code: i32,
     pub message: String,
@@ -122,19 +298,27 @@ pub struct ApiResponse<T: Serialize> {
     pub data: Option<T>,
 }
 
+// 不带数据的成功响应，仅用于接口文档
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct MessageResponse {
+    pub code: i32,
+    pub message: String,
+}
+
 impl<T: Serialize> ApiResponse<T> {
     pub fn success(data: T) -> Self {
         Self {
             code: 0,
-            message: "成功".to_string(),
+            message: Message::Ok.localized().to_string(),
             data: Some(data),
         }
     }
 
-    pub fn message(message: &str) -> ApiResponse<()> {
+    pub fn message(message: Message) -> ApiResponse<()> {
         ApiResponse {
             code: 0,
-            message: message.to_string(),
+            message: message.localized().to_string(),
             data: None,
         }
     }
@@ -0,0 +1,148 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use rust_embed::{EmbeddedFile, RustEmbed};
+
+use crate::error::{AppError, ErrorCode};
+
+// 构建好的前端资源，release 构建时打包进可执行文件，debug 构建时从磁盘读取
+// 目录不存在时按空目录处理，未构建前端也能编译与运行测试
+#[derive(RustEmbed)]
+#[folder = "frontend/dist"]
+#[allow_missing = true]
+struct Assets;
+
+// 单页应用的入口
+const INDEX: &str = "index.html";
+
+// 构建工具输出的带哈希文件名的资源目录，内容变化时文件名随之变化，可以长期缓存
+const HASHED_DIR: &str = "assets/";
+
+// 预压缩文件对应的 Content-Encoding 与扩展名，按优先级排列
+const PRECOMPRESSED: [(&str, &str); 2] = [("br", ".br"), ("gzip", ".gz")];
+
+// 前端路由，处理其余路由都未匹配的请求
+pub fn routes() -> Router<crate::app::AppState> {
+    if Assets::get(INDEX).is_none() {
+        tracing::warn!("未找到内嵌的前端页面，请先构建 frontend/dist 再编译");
+    }
+    Router::new().fallback(serve::<Assets>)
+}
+
+// 返回前端资源，找不到的页面路径交给前端路由处理
+pub async fn serve<E: RustEmbed>(method: Method, uri: Uri, headers: HeaderMap) -> Response {
+    // 未匹配的接口仍然返回 JSON 格式的错误
+    if uri.path() == "/api" || uri.path().starts_with("/api/") {
+        return AppError::NotFound(ErrorCode::NotFound, "接口不存在".to_string()).into_response();
+    }
+    if method != Method::GET && method != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+
+    let mut path = uri.path().trim_start_matches('/');
+    if path.is_empty() || path.ends_with('/') {
+        path = INDEX;
+    }
+    let file = match E::get(path) {
+        Some(file) => file,
+        // 没有扩展名的路径视为前端页面，返回入口由前端路由处理
+        None if !path.rsplit('/').next().unwrap_or_default().contains('.') => {
+            path = INDEX;
+            match E::get(INDEX) {
+                Some(file) => file,
+                None => return StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    // 压缩版本的类型按原文件确定
+    let content_type = file.metadata.mimetype().to_string();
+
+    // 客户端支持时优先返回预压缩的版本
+    let (file, encoding) = PRECOMPRESSED
+        .iter()
+        .filter(|(encoding, _)| accepts_encoding(&headers, encoding))
+        .find_map(|(encoding, extension)| {
+            E::get(&format!("{}{}", path, extension)).map(|variant| (variant, Some(*encoding)))
+        })
+        .unwrap_or((file, None));
+    file_response(path, file, content_type, encoding, &headers)
+}
+
+// 生成资源响应，附带缓存相关的响应头
+fn file_response(
+    path: &str,
+    file: EmbeddedFile,
+    content_type: String,
+    encoding: Option<&'static str>,
+    headers: &HeaderMap,
+) -> Response {
+    let etag = format!(
+        "\"{}\"",
+        file.metadata
+            .sha256_hash()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    );
+    let cache_control = if path.starts_with(HASHED_DIR) {
+        "public, max-age=31536000, immutable"
+    } else {
+        // 入口等文件名不变的资源每次都向服务器确认是否更新
+        "no-cache"
+    };
+    let mut response = if if_none_match(headers, &etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, content_type)],
+            file.data.into_owned(),
+        )
+            .into_response()
+    };
+    let response_headers = response.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    response_headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    if let Some(encoding) = encoding {
+        response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    }
+    response
+}
+
+// 检查 Accept-Encoding 是否接受指定的编码，q=0 表示不接受
+fn accepts_encoding(headers: &HeaderMap, encoding: &str) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+            name.eq_ignore_ascii_case(encoding) && quality.is_some_and(|q| q > 0.0)
+        })
+}
+
+// 客户端缓存的版本与当前一致时返回 304
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        })
+}
@@ -0,0 +1,263 @@
+use std::cell::Cell;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::ErrorCode;
+
+// 支持的界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    ZhCn,
+    En,
+}
+
+impl Locale {
+    // 按语言标签匹配，只比较主语言，如 en-US 视为 en
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        if primary.eq_ignore_ascii_case("zh") {
+            Some(Locale::ZhCn)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Locale::En)
+        } else {
+            None
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::ZhCn => "zh-CN",
+            Locale::En => "en",
+        }
+    }
+
+    // 解析 Accept-Language 请求头，选择权重最高且受支持的语言
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let locale = Locale::from_tag(parts.next()?.trim())?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+        // 权重相同时保持请求头中的顺序
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+}
+
+tokio::task_local! {
+    // 当前请求使用的语言，认证后按用户设置覆盖
+    static LOCALE: Cell<Locale>;
+}
+
+// 根据 Accept-Language 确定请求的语言，并在响应头中注明
+pub async fn negotiate_locale(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::negotiate)
+        .unwrap_or_default();
+
+    let cell = Cell::new(locale);
+    let (mut response, locale) = LOCALE
+        .scope(cell, async {
+            let response = next.run(request).await;
+            (response, current())
+        })
+        .await;
+    response.headers_mut().insert(
+        header::CONTENT_LANGUAGE,
+        HeaderValue::from_static(locale.tag()),
+    );
+    response
+}
+
+// 使用用户在个人资料中设置的语言，不支持的语言保持协商结果
+pub fn set_user_locale(tag: &str) {
+    if let Some(locale) = Locale::from_tag(tag) {
+        let _ = LOCALE.try_with(|current| current.set(locale));
+    }
+}
+
+// 当前请求的语言，不在请求处理过程中时使用默认语言
+pub fn current() -> Locale {
+    LOCALE.try_with(Cell::get).unwrap_or_default()
+}
+
+// 错误码对应的提示信息
+pub fn error_message(code: ErrorCode, locale: Locale) -> &'static str {
+    match locale {
+        Locale::ZhCn => code.description(),
+        Locale::En => match code {
+            ErrorCode::AuthFailed => "Incorrect username or password",
+            ErrorCode::InvalidInviteCode => "Invite code is invalid, used up or expired",
+            ErrorCode::UsernameTaken => "Username already exists",
+            ErrorCode::Unauthenticated => "Not logged in or session expired",
+            ErrorCode::AdminRequired => "Administrator privileges required",
+            ErrorCode::AdminAlreadySetup => "Administrator has already been set up",
+            ErrorCode::WrongPassword => "Old password is incorrect",
+            ErrorCode::PasswordPolicy => "Password does not meet the security policy",
+            ErrorCode::AccountDisabled => "Account has been disabled",
+            ErrorCode::AccountPending => "Account is pending approval",
+            ErrorCode::RegistrationClosed => "Registration is closed",
+            ErrorCode::RoleForbidden => "Your role is not allowed to perform this operation",
+            ErrorCode::BookNotFound => "Book not found",
+            ErrorCode::BookForbidden => "No permission to access this book",
+            ErrorCode::UnsupportedFileType => "Unsupported file format",
+            ErrorCode::FileTooLarge => "File is too large",
+            ErrorCode::QuotaExceeded => "Upload quota exceeded",
+            ErrorCode::UserNotFound => "User not found",
+            ErrorCode::BadRequest => "Invalid request parameters",
+            ErrorCode::Forbidden => "Permission denied",
+            ErrorCode::NotFound => "Resource not found",
+            ErrorCode::Maintenance => "The system is under maintenance",
+            ErrorCode::Unavailable => "Service is not ready",
+            ErrorCode::Internal => "Internal server error",
+        },
+    }
+}
+
+// 成功提示等界面文本，以固定的标识区分，修改中文文案不影响翻译
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    Ok,
+    // 健康检查的响应，不随语言变化
+    Healthy,
+    Updated,
+    NothingToUpdate,
+    Deleted,
+    RegistrationPending,
+    PasswordChanged,
+    UsernameChanged,
+    AccountDeleted,
+    InviteCodeRevoked,
+    InviteCodeDeleted,
+    SettingsUpdated,
+    AnnouncementUpdated,
+    AnnouncementDeleted,
+    AnnouncementDismissed,
+    UserDisabled,
+    UserEnabled,
+    UserDeleted,
+    QuotaUpdated,
+    UserRoleUpdated,
+    RegistrationApproved,
+    RegistrationRejected,
+    UserPasswordReset,
+    BookUnpublished,
+    BookTransferred,
+    Maintenance,
+}
+
+impl Message {
+    // 全部界面文本，用于检查翻译是否完整
+    #[cfg(test)]
+    pub const ALL: [Message; 26] = [
+        Message::Ok,
+        Message::Healthy,
+        Message::Updated,
+        Message::NothingToUpdate,
+        Message::Deleted,
+        Message::RegistrationPending,
+        Message::PasswordChanged,
+        Message::UsernameChanged,
+        Message::AccountDeleted,
+        Message::InviteCodeRevoked,
+        Message::InviteCodeDeleted,
+        Message::SettingsUpdated,
+        Message::AnnouncementUpdated,
+        Message::AnnouncementDeleted,
+        Message::AnnouncementDismissed,
+        Message::UserDisabled,
+        Message::UserEnabled,
+        Message::UserDeleted,
+        Message::QuotaUpdated,
+        Message::UserRoleUpdated,
+        Message::RegistrationApproved,
+        Message::RegistrationRejected,
+        Message::UserPasswordReset,
+        Message::BookUnpublished,
+        Message::BookTransferred,
+        Message::Maintenance,
+    ];
+
+    pub fn text(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::ZhCn => match self {
+                Message::Ok => "成功",
+                Message::Healthy => "ok",
+                Message::Updated => "更新成功",
+                Message::NothingToUpdate => "无更新内容",
+                Message::Deleted => "删除成功",
+                Message::RegistrationPending => "注册成功，请等待管理员审核",
+                Message::PasswordChanged => "密码修改成功",
+                Message::UsernameChanged => "用户名修改成功",
+                Message::AccountDeleted => "账号已注销",
+                Message::InviteCodeRevoked => "邀请码已撤销",
+                Message::InviteCodeDeleted => "邀请码已删除",
+                Message::SettingsUpdated => "设置已更新",
+                Message::AnnouncementUpdated => "公告已更新",
+                Message::AnnouncementDeleted => "公告已删除",
+                Message::AnnouncementDismissed => "公告已关闭",
+                Message::UserDisabled => "用户已禁用",
+                Message::UserEnabled => "用户已启用",
+                Message::UserDeleted => "用户已删除",
+                Message::QuotaUpdated => "配额已更新",
+                Message::UserRoleUpdated => "用户角色已更新",
+                Message::RegistrationApproved => "已通过注册申请",
+                Message::RegistrationRejected => "已拒绝注册申请",
+                Message::UserPasswordReset => "用户密码重置成功",
+                Message::BookUnpublished => "已取消公开",
+                Message::BookTransferred => "书籍所有权已转移",
+                Message::Maintenance => "系统维护中，请稍后再试",
+            },
+            Locale::En => match self {
+                Message::Ok => "OK",
+                Message::Healthy => "ok",
+                Message::Updated => "Updated",
+                Message::NothingToUpdate => "Nothing to update",
+                Message::Deleted => "Deleted",
+                Message::RegistrationPending => {
+                    "Registered, please wait for administrator approval"
+                }
+                Message::PasswordChanged => "Password changed",
+                Message::UsernameChanged => "Username changed",
+                Message::AccountDeleted => "Account deleted",
+                Message::InviteCodeRevoked => "Invite code revoked",
+                Message::InviteCodeDeleted => "Invite code deleted",
+                Message::SettingsUpdated => "Settings updated",
+                Message::AnnouncementUpdated => "Announcement updated",
+                Message::AnnouncementDeleted => "Announcement deleted",
+                Message::AnnouncementDismissed => "Announcement dismissed",
+                Message::UserDisabled => "User disabled",
+                Message::UserEnabled => "User enabled",
+                Message::UserDeleted => "User deleted",
+                Message::QuotaUpdated => "Quota updated",
+                Message::UserRoleUpdated => "User role updated",
+                Message::RegistrationApproved => "Registration approved",
+                Message::RegistrationRejected => "Registration rejected",
+                Message::UserPasswordReset => "User password reset",
+                Message::BookUnpublished => "Book is no longer public",
+                Message::BookTransferred => "Book ownership transferred",
+                Message::Maintenance => "The system is under maintenance, please try again later",
+            },
+        }
+    }
+
+    // 按当前请求的语言返回文本
+    pub fn localized(self) -> &'static str {
+        self.text(current())
+    }
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+use crate::{
+    db::NOW,
+    error::{AppError, ErrorCode},
+    models::InviteCode,
+    utils::generate_invite_code,
+};
+
+// 检查注册时提供的邀请码，仅用于提前给出明确的错误信息
+// 真正的扣减在 consume 中原子完成
+pub async fn check(
+    pool: &Pool<Sqlite>,
+    invite_code: Option<&str>,
+    required: bool,
+) -> Result<(), AppError> {
+    // 如果系统需要邀请码，但用户未提供
+    if required && invite_code.is_none() {
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "注册需要邀请码".to_string(),
+        )
+        .for_field("invite_code"));
+    }
+
+    // 验证邀请码
+    if let Some(invite_code) = invite_code {
+        ensure_usable(&mut *pool.acquire().await?, invite_code).await?;
+    }
+
+    Ok(())
+}
+
+// 在注册事务中扣减一次邀请码并记录使用者
+// 条件更新保证并发注册时使用次数不会超过上限
+pub async fn consume(
+    conn: &mut SqliteConnection,
+    invite_code: &str,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let invite_id = sqlx::query_scalar::<_, i64>(&format!(
+        "UPDATE invite_codes SET used_times = used_times + 1
+         WHERE code = ?
+           AND revoked = 0
+           AND (limit_times < 0 OR used_times < limit_times)
+           AND (expires_at IS NULL OR expires_at > {NOW})
+         RETURNING id"
+    ))
+    .bind(invite_code)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(invite_id) = invite_id else {
+        // 给出具体的失败原因
+        ensure_usable(conn, invite_code).await?;
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "邀请码已用完".to_string(),
+        ));
+    };
+
+    sqlx::query!(
+        "INSERT INTO invite_code_uses (invite_code_id, user_id) VALUES (?, ?)",
+        invite_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// 检查邀请码是否存在且仍可使用
+async fn ensure_usable(conn: &mut SqliteConnection, invite_code: &str) -> Result<(), AppError> {
+    let invite = sqlx::query_as::<_, InviteCode>("SELECT * FROM invite_codes WHERE code = ?")
+        .bind(invite_code)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(ErrorCode::InvalidInviteCode, "邀请码无效".to_string())
+        })?;
+
+    if invite.revoked {
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "邀请码已被撤销".to_string(),
+        ));
+    }
+    if invite.is_expired() {
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "邀请码已过期".to_string(),
+        ));
+    }
+    if invite.limit_times >= 0 && invite.used_times >= invite.limit_times {
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "邀请码已用完".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// 插入一个邀请码，未指定时随机生成，返回最终使用的邀请码
+pub async fn insert(
+    conn: &mut SqliteConnection,
+    code: Option<&str>,
+    limit_times: i64,
+    description: Option<&str>,
+    expires_at: Option<&str>,
+) -> Result<String, AppError> {
+    // 随机生成的邀请码偶尔会重复，重新生成即可
+    const MAX_ATTEMPTS: usize = 5;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate = code.map_or_else(generate_invite_code, str::to_string);
+        let result = sqlx::query!(
+            "INSERT INTO invite_codes (code, limit_times, description, expires_at)
+             VALUES (?, ?, ?, ?)",
+            candidate,
+            limit_times,
+            description,
+            expires_at
+        )
+        .execute(&mut *conn)
+        .await;
+
+        match result {
+            Ok(_) => return Ok(candidate),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                if code.is_some() {
+                    return Err(AppError::Validation(
+                        ErrorCode::InvalidInviteCode,
+                        "邀请码已存在".to_string(),
+                    ));
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(AppError::Internal("无法生成唯一的邀请码".to_string()))
+}
+
+// 自定义邀请码只允许字母、数字、下划线与连字符
+pub fn validate_code(code: &str) -> Result<(), AppError> {
+    if !(4..=32).contains(&code.len())
+        || !code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(AppError::Validation(
+            ErrorCode::InvalidInviteCode,
+            "邀请码须为 4-32 位字母、数字、下划线或连字符".to_string(),
+        )
+        .for_field("code"));
+    }
+
+    Ok(())
+}
+
+// 解析 RFC 3339 格式的过期时间，统一转换为 UTC 存储
+pub fn parse_expires_at(expires_at: Option<&str>) -> Result<Option<String>, AppError> {
+    expires_at
+        .map(|expires_at| {
+            let expires_at = DateTime::parse_from_rfc3339(expires_at)
+                .map_err(|_| AppError::invalid_field("expires_at", "过期时间格式无效"))?
+                .with_timezone(&Utc);
+            if expires_at <= Utc::now() {
+                return Err(AppError::invalid_field(
+                    "expires_at",
+                    "过期时间必须晚于当前时间",
+                ));
+            }
+            Ok(expires_at.to_rfc3339_opts(SecondsFormat::Millis, true))
+        })
+        .transpose()
+}
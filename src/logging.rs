@@ -0,0 +1,90 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderName, Request as HttpRequest},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{dispatcher::DefaultGuard, Span};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::{LogConfig, LogFormat};
+
+// 请求ID所在的请求头，客户端或上游代理已提供时沿用，否则由服务端生成
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// 默认的日志过滤规则
+const DEFAULT_FILTER: &str = "reader_server=debug,tower_http=debug,axum=debug";
+
+tokio::task_local! {
+    // 当前请求的ID，供错误响应使用
+    static REQUEST_ID: String;
+}
+
+// 按配置初始化全局日志
+pub fn init(config: &LogConfig) {
+    let registry = tracing_subscriber::registry().with(env_filter(config));
+    match config.format {
+        LogFormat::Text => registry.with(tracing_subscriber::fmt::layer()).init(),
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false),
+            )
+            .init(),
+    }
+}
+
+// 加载配置之前使用的临时日志，保证加载过程中的提示能够输出
+// 只对当前线程生效，也不接管 log 库，之后仍可调用 init
+pub fn init_bootstrap() -> DefaultGuard {
+    tracing::subscriber::set_default(
+        tracing_subscriber::registry()
+            .with(env_filter(&LogConfig::default()))
+            .with(tracing_subscriber::fmt::layer()),
+    )
+}
+
+fn env_filter(config: &LogConfig) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(config.filter.as_deref().unwrap_or(DEFAULT_FILTER)))
+}
+
+// 每个请求的日志上下文，认证成功后补充 user_id
+pub fn make_request_span(request: &HttpRequest<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %request_id,
+        user_id = tracing::field::Empty,
+    )
+}
+
+// 在请求处理期间保存请求ID
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    REQUEST_ID.scope(request_id, next.run(request)).await
+}
+
+// 当前请求的ID，不在请求处理过程中时返回 None
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID
+        .try_with(|request_id| request_id.clone())
+        .ok()
+        .filter(|request_id| !request_id.is_empty())
+}
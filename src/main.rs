@@ -1,58 +1,119 @@
 mod app;
+mod audit;
 mod auth;
+mod backup;
+mod cache;
+mod cli;
+mod commands;
 mod config;
 mod db;
 mod error;
+#[cfg(feature = "embed-frontend")]
+mod frontend;
+mod i18n;
+mod invite;
+mod logging;
+mod metrics;
 mod models;
+mod openapi;
+mod password_policy;
 mod routes;
+mod server;
+mod storage;
 mod utils;
 
 // 因为是 bin target，所以集成测试必须放在 src 里
 #[cfg(test)]
 mod tests;
 
-use std::{net::SocketAddr, path::Path};
+use std::{path::Path, time::Duration};
 
 use anyhow::Result;
-use tokio::{fs, net::TcpListener};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use clap::Parser;
+use tokio::fs;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 初始化错误处理
     color_eyre::install().map_err(|e| anyhow::anyhow!("Failed to install color_eyre: {}", e))?;
 
-    // 初始化日志
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "reader_server_rs=debug,tower_http=debug,axum=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // 加载配置
-    let config = config::load_config().await?;
+    // 解析命令行参数并加载配置，加载期间使用默认格式输出日志
+    let cli = cli::Cli::parse();
+    let config = {
+        let _guard = logging::init_bootstrap();
+        config::load_config(&cli.config_overrides()).await?
+    };
+
+    // 按配置初始化日志
+    logging::init(&config.log);
+
+    match cli.command {
+        None | Some(cli::Command::Serve) => serve(config).await,
+        // 从备份恢复，此时不能打开数据库
+        Some(cli::Command::Restore { archive }) => Ok(backup::restore(&config, &archive).await?),
+        // 其余子命令在迁移后的数据库上执行
+        Some(command) => {
+            let db_pool = open_database(&config).await?;
+            let result = commands::run(&db_pool, &config, command).await;
+            db_pool.close().await;
+            result
+        }
+    }
+}
 
+// 确保目录存在并打开数据库，同时完成迁移
+async fn open_database(config: &config::Config) -> Result<sqlx::Pool<sqlx::Sqlite>> {
     // 确保目录存在
-    ensure_directories(&config).await?;
+    ensure_directories(config).await?;
 
     // 初始化数据库
-    let db_pool = db::init_db_pool(&config).await?;
+    let db_pool = db::init_db_pool(config).await?;
 
     // 运行数据库迁移
     db::run_migrations(&db_pool).await?;
 
-    // 构建应用
-    let app = app::create_app(db_pool, config.clone()).await?;
+    Ok(db_pool)
+}
+
+async fn serve(config: config::Config) -> Result<()> {
+    let db_pool = open_database(&config).await?;
+
+    // 检查书籍文件与数据库是否一致
+    if config.storage.startup_check != config::StartupCheck::Off {
+        let repair = config.storage.startup_check == config::StartupCheck::Repair;
+        let report = storage::check_consistency(&db_pool, &config.storage.book_dir, repair)
+            .await
+            .map_err(|e| anyhow::anyhow!("一致性检查失败: {}", e))?;
+        if report.is_consistent() {
+            tracing::info!("书籍文件与数据库一致");
+        } else {
+            tracing::warn!(
+                "一致性检查发现 {} 个孤立文件、{} 本文件缺失的书籍、{} 个无效章节{}",
+                report.orphan_files.len(),
+                report.missing_files.len(),
+                report.invalid_chapters.len(),
+                if repair { "，已修复" } else { "" }
+            );
+        }
+    }
+
+    // 定时备份
+    backup::spawn_scheduler(db_pool.clone(), config.clone());
 
-    // 获取服务地址
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
+    // 构建应用
+    let app = app::create_app(db_pool.clone(), config.clone()).await?;
 
-    tracing::info!("服务器启动在 http://{}", addr);
+    // 启动服务器，收到关闭信号并处理完进行中的请求后返回
+    server::serve(app, &config.server).await?;
 
-    // 启动服务器
-    axum::serve(TcpListener::bind(addr).await?, app).await?;
+    // 关闭连接池，写回 WAL；被强制中止的请求可能仍占用连接，因此限制等待时间
+    if tokio::time::timeout(Duration::from_secs(5), db_pool.close())
+        .await
+        .is_err()
+    {
+        tracing::warn!("仍有连接未归还，跳过等待数据库连接池关闭");
+    }
+    tracing::info!("服务器已关闭");
 
     Ok(())
 }
@@ -64,5 +125,11 @@ async fn ensure_directories(config: &config::Config) -> Result<()> {
         fs::create_dir_all(book_dir).await?;
     }
 
+    // 确保头像目录存在
+    let avatar_dir = Path::new(&config.storage.avatar_dir);
+    if !avatar_dir.exists() {
+        fs::create_dir_all(avatar_dir).await?;
+    }
+
     Ok(())
 }
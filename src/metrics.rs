@@ -0,0 +1,237 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::cache::ContentCache;
+
+// 请求耗时直方图的分桶上限（秒）
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// 阅读心跳间隔超过 30 秒不计入阅读时长，同样不再视为正在阅读
+const ACTIVE_READER_WINDOW: Duration = Duration::from_secs(30);
+
+// 运行指标，以 Prometheus 文本格式导出
+#[derive(Default)]
+pub struct Metrics {
+    routes: Mutex<BTreeMap<(String, String), RouteStats>>,
+    // 最近一次阅读心跳的时间
+    readers: Mutex<HashMap<i64, Instant>>,
+    upload_bytes: AtomicU64,
+}
+
+// 单个路由的请求数与耗时分布
+#[derive(Default)]
+struct RouteStats {
+    statuses: BTreeMap<u16, u64>,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    duration_sum: f64,
+    count: u64,
+}
+
+impl Metrics {
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut routes = lock(&self.routes);
+        let stats = routes
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        *stats.statuses.entry(status).or_default() += 1;
+        for (bucket, upper) in stats.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= upper {
+                *bucket += 1;
+            }
+        }
+        stats.duration_sum += seconds;
+        stats.count += 1;
+    }
+
+    pub fn record_heartbeat(&self, user_id: i64) {
+        lock(&self.readers).insert(user_id, Instant::now());
+    }
+
+    pub fn record_upload(&self, bytes: u64) {
+        self.upload_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    // 最近发送过阅读心跳的用户数，同时清理过期的记录
+    fn active_readers(&self) -> usize {
+        let mut readers = lock(&self.readers);
+        readers.retain(|_, last_seen| last_seen.elapsed() < ACTIVE_READER_WINDOW);
+        readers.len()
+    }
+
+    // 生成 Prometheus 文本格式的指标
+    pub fn render(&self, pool: &Pool<Sqlite>, cache: &ContentCache) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "reader_http_requests_total",
+            "counter",
+            "按路由与状态码统计的 HTTP 请求数",
+        );
+        let routes = lock(&self.routes);
+        for ((method, route), stats) in routes.iter() {
+            for (status, count) in &stats.statuses {
+                let _ = writeln!(
+                    out,
+                    "reader_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    method,
+                    escape(route),
+                    status,
+                    count
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "reader_http_request_duration_seconds",
+            "histogram",
+            "按路由统计的 HTTP 请求耗时",
+        );
+        for ((method, route), stats) in routes.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape(route));
+            for (count, upper) in stats.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "reader_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, upper, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "reader_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, stats.count
+            );
+            let _ = writeln!(
+                out,
+                "reader_http_request_duration_seconds_sum{{{}}} {}",
+                labels, stats.duration_sum
+            );
+            let _ = writeln!(
+                out,
+                "reader_http_request_duration_seconds_count{{{}}} {}",
+                labels, stats.count
+            );
+        }
+        drop(routes);
+
+        gauge(
+            &mut out,
+            "reader_active_readers",
+            "最近 30 秒内发送过阅读心跳的用户数",
+            self.active_readers() as u64,
+        );
+        counter(
+            &mut out,
+            "reader_upload_bytes_total",
+            "上传书籍的总字节数",
+            self.upload_bytes.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "reader_content_cache_hits_total",
+            "书籍正文缓存命中次数",
+            cache.hits(),
+        );
+        counter(
+            &mut out,
+            "reader_content_cache_misses_total",
+            "书籍正文缓存未命中次数",
+            cache.misses(),
+        );
+        gauge(
+            &mut out,
+            "reader_content_cache_bytes",
+            "书籍正文缓存占用的字节数",
+            cache.size() as u64,
+        );
+
+        let size = pool.size();
+        let idle = pool.num_idle() as u32;
+        header(
+            &mut out,
+            "reader_db_pool_connections",
+            "gauge",
+            "数据库连接池中的连接数",
+        );
+        let _ = writeln!(
+            out,
+            "reader_db_pool_connections{{state=\"active\"}} {}",
+            size.saturating_sub(idle)
+        );
+        let _ = writeln!(out, "reader_db_pool_connections{{state=\"idle\"}} {}", idle);
+        gauge(
+            &mut out,
+            "reader_db_pool_max_connections",
+            "数据库连接池的最大连接数",
+            pool.options().get_max_connections() as u64,
+        );
+
+        out
+    }
+}
+
+// 记录每个请求的路由、状态码与耗时
+// 使用路由模板而不是实际路径作为标签，避免书籍 ID 等参数导致标签数量无限增长
+pub async fn track_requests(
+    State(metrics): State<std::sync::Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics.record_request(&method, &route, response.status().as_u16(), start.elapsed());
+
+    response
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, "counter", help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+// 转义标签值中的反斜杠、双引号与换行
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// 指标只用于观测，锁中毒时继续使用
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
 // 用户模型
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
@@ -8,12 +9,23 @@ pub struct User {
     pub username: String,
     #[serde(skip_serializing)]
     pub password_hash: String,
+    pub has_password: bool,
+    pub display_name: Option<String>,
+    #[serde(skip_serializing)]
+    pub avatar_path: Option<String>,
+    pub timezone: String,
+    pub locale: String,
+    pub role: Role,
+    pub status: String,
+    pub storage_quota: Option<i64>,
+    pub book_quota: Option<i64>,
+    pub last_active_at: Option<String>,
     pub created_at: String,
     pub total_reading_time: i64,
 }
 
 // 创建用户请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateUserRequest {
     pub username: String,
     pub password: String,
@@ -21,7 +33,7 @@ pub struct CreateUserRequest {
 }
 
 // 登录请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
@@ -29,65 +41,329 @@ pub struct LoginRequest {
 }
 
 // 用户信息响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserInfoResponse {
     pub user_id: i64,
     pub username: String,
+    pub role: Role,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub timezone: String,
+    pub locale: String,
+    // 是否设置了本地密码，没有时修改密码与注销账号需要刚登录
+    pub has_password: bool,
+    // 按用户时区显示的注册时间
+    pub created_at: String,
     pub total_reading_time: i64,
     pub book_count: i64,
 }
 
+// 注册响应，需要审核时不签发令牌
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegisterResponse {
+    pub user_id: i64,
+    pub username: String,
+    // active 或 pending
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+// 登录成功后返回的令牌
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    pub user_id: i64,
+    pub username: String,
+    pub token: String,
+}
+
+// 密码策略，供前端在提交前提示
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PasswordPolicyResponse {
+    pub min_length: i64,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub check_breached: bool,
+}
+
+// 上传头像表单（multipart/form-data），仅用于接口文档
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct AvatarUploadForm {
+    // PNG、JPEG、GIF 或 WebP 图片
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+// 上传头像响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvatarResponse {
+    pub avatar_url: String,
+}
+
+// 更新个人资料请求
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct UpdateProfileRequest {
+    pub display_name: Option<String>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+}
+
+// 修改用户名请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangeUsernameRequest {
+    pub new_username: String,
+}
+
 // 修改密码请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChangePasswordRequest {
-    pub old_password: String,
+    // 没有本地密码的用户（反向代理或 OIDC 创建）可以省略，此时需要刚登录
+    pub old_password: Option<String>,
     pub new_password: String,
 }
 
-// 管理员模型
-#[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct Admin {
-    pub id: i64,
-    #[serde(skip_serializing)]
-    pub password_hash: String,
-    pub created_at: String,
+// OpenID Connect 回调请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+// OpenID Connect 授权地址
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthorizeUrlResponse {
+    pub authorize_url: String,
+}
+
+// 注销账号请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    // 没有本地密码的用户（反向代理或 OIDC 创建）可以省略，此时需要刚登录
+    pub password: Option<String>,
 }
 
-// 管理员设置密码请求
-#[derive(Debug, Serialize, Deserialize)]
+// 用户角色
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, clap::ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Role {
+    // 管理员，拥有全部权限
+    Admin,
+    // 版主，可管理所有书籍
+    Moderator,
+    // 可上传书籍
+    Uploader,
+    // 只能阅读
+    Reader,
+}
+
+// 首次设置管理员请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AdminSetupRequest {
+    // 管理员用户名，默认为 admin
+    #[serde(default)]
+    pub username: Option<String>,
     pub password: String,
 }
 
+// 首次设置管理员响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminSetupResponse {
+    pub user_id: i64,
+    pub username: String,
+    pub admin_token: String,
+}
+
+// 是否已设置管理员
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SetupStatusResponse {
+    pub is_setup: bool,
+}
+
+// 修改用户角色请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateUserRoleRequest {
+    pub role: Role,
+}
+
+// 注册模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum RegistrationMode {
+    // 开放注册，邀请码可选
+    Open,
+    // 必须使用邀请码
+    Invite,
+    // 关闭注册
+    Closed,
+    // 注册后需管理员审核
+    Approval,
+}
+
 // 系统设置
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Settings {
     pub id: i64,
-    pub invite_code_required: bool,
+    pub registration_mode: RegistrationMode,
+    pub password_min_length: i64,
+    pub password_require_uppercase: bool,
+    pub password_require_lowercase: bool,
+    pub password_require_digit: bool,
+    pub password_require_symbol: bool,
+    pub password_check_breached: bool,
+    pub maintenance_mode: bool,
+    pub maintenance_message: String,
+}
+
+// 与 schema.sql 中的默认值保持一致
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            id: 1,
+            registration_mode: RegistrationMode::Open,
+            password_min_length: 6,
+            password_require_uppercase: false,
+            password_require_lowercase: false,
+            password_require_digit: false,
+            password_require_symbol: false,
+            password_check_breached: false,
+            maintenance_mode: false,
+            maintenance_message: String::new(),
+        }
+    }
 }
 
 // 邀请码模型
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct InviteCode {
     pub id: i64,
     pub code: String,
     pub limit_times: i64,
     pub used_times: i64,
     pub description: Option<String>,
+    pub expires_at: Option<String>,
+    pub revoked: bool,
     pub created_at: String,
 }
 
+impl InviteCode {
+    // 是否已过期，无法解析的时间视为已过期
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.as_deref().is_some_and(|expires_at| {
+            expires_at
+                .parse::<chrono::DateTime<chrono::Utc>>()
+                .map_or(true, |expires_at| expires_at <= chrono::Utc::now())
+        })
+    }
+}
+
 // 创建邀请码请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct CreateInviteCodeRequest {
     pub limit_times: i64,
     pub description: Option<String>,
+    // 自定义邀请码，为空时随机生成
+    #[serde(default)]
+    pub code: Option<String>,
+    // 过期时间（RFC 3339），为空表示永不过期
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+// 批量生成邀请码请求
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct BatchCreateInviteCodesRequest {
+    pub count: u32,
+    pub limit_times: i64,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+// 创建邀请码响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateInviteCodeResponse {
+    pub invite_code: String,
+    pub limit_times: i64,
+    pub description: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+// 批量生成邀请码响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchCreateInviteCodesResponse {
+    pub invite_codes: Vec<String>,
+    pub limit_times: i64,
+    pub description: Option<String>,
+    pub expires_at: Option<String>,
 }
 
-// 设置更新请求
-#[derive(Debug, Serialize, Deserialize)]
+// 邀请码列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InviteCodeListResponse {
+    pub invite_codes: Vec<InviteCode>,
+}
+
+// 邀请码使用记录
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct InviteCodeUse {
+    pub user_id: i64,
+    pub username: String,
+    pub used_at: String,
+}
+
+// 邀请码使用记录列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InviteCodeUsesResponse {
+    pub uses: Vec<InviteCodeUse>,
+}
+
+// 设置更新请求，未提供的字段保持不变
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct UpdateSettingsRequest {
-    pub invite_code_required: bool,
+    pub registration_mode: Option<RegistrationMode>,
+    pub password_min_length: Option<i64>,
+    pub password_require_uppercase: Option<bool>,
+    pub password_require_lowercase: Option<bool>,
+    pub password_require_digit: Option<bool>,
+    pub password_require_symbol: Option<bool>,
+    pub password_check_breached: Option<bool>,
+    pub maintenance_mode: Option<bool>,
+    // 传入空字符串恢复默认提示
+    pub maintenance_message: Option<String>,
+}
+
+// 公告模型
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Announcement {
+    pub id: i64,
+    pub title: String,
+    pub content: String,
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+}
+
+// 创建或修改公告请求，时间为 RFC 3339 格式，省略表示不限制
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct AnnouncementRequest {
+    pub title: String,
+    pub content: String,
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+}
+
+// 公告列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnnouncementListResponse {
+    pub announcements: Vec<Announcement>,
 }
 
 // 书籍模型
@@ -98,6 +374,7 @@ pub struct Book {
     pub title: String,
     pub author: Option<String>,
     pub file_path: String,
+    pub file_size: i64,
     pub is_public: bool,
     pub created_at: String,
 }
@@ -124,7 +401,7 @@ pub struct ReadingProgress {
 }
 
 // 阅读设置模型
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct ReadingSettings {
     pub id: i64,
     pub user_id: i64,
@@ -139,8 +416,20 @@ pub struct ReadingSettings {
     pub simplified_chinese: bool,
 }
 
+// 上传书籍表单（multipart/form-data），仅用于接口文档
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct UploadBookForm {
+    // TXT 书籍文件
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    pub title: String,
+    pub author: Option<String>,
+    pub is_public: Option<bool>,
+}
+
 // 上传书籍响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UploadBookResponse {
     pub book_id: i64,
     pub title: String,
@@ -149,7 +438,7 @@ pub struct UploadBookResponse {
 }
 
 // 章节响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ChapterResponse {
     pub chapter_id: i64,
     pub title: String,
@@ -157,7 +446,7 @@ pub struct ChapterResponse {
 }
 
 // 书籍列表项响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BookListItem {
     pub book_id: i64,
     pub title: String,
@@ -169,8 +458,15 @@ pub struct BookListItem {
     pub reading_time: i64,
 }
 
+// 书籍列表响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BookListResponse {
+    pub total: i64,
+    pub books: Vec<BookListItem>,
+}
+
 // 书籍详情响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BookDetailResponse {
     pub book_id: i64,
     pub title: String,
@@ -184,24 +480,44 @@ pub struct BookDetailResponse {
 }
 
 // 公开书籍列表项
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PublicBookListItem {
     pub book_id: i64,
     pub title: String,
     pub author: Option<String>,
     pub owner_username: String,
+    pub owner_display_name: Option<String>,
     pub created_at: String,
 }
 
+// 公开书籍列表响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicBookListResponse {
+    pub total: i64,
+    pub books: Vec<PublicBookListItem>,
+}
+
+// 随机公开书籍响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RandomPublicBooksResponse {
+    pub books: Vec<PublicBookListItem>,
+}
+
 // 书籍内容响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BookContentResponse {
     pub content: String,
     pub next_position: i64,
 }
 
+// 章节在书籍中的位置
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChapterPositionResponse {
+    pub position: i64,
+}
+
 // 更新书籍请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
 pub struct UpdateBookRequest {
     pub title: Option<String>,
     pub author: Option<String>,
@@ -209,7 +525,7 @@ pub struct UpdateBookRequest {
 }
 
 // 心跳包请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct HeartbeatRequest {
     pub book_id: i64,
     pub position: i64,
@@ -217,7 +533,7 @@ pub struct HeartbeatRequest {
 }
 
 // 心跳包响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HeartbeatResponse {
     pub synced: bool,
     pub position: i64,
@@ -225,7 +541,7 @@ pub struct HeartbeatResponse {
 }
 
 // 更新阅读设置请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateReadingSettingsRequest {
     pub font_size: Option<i64>,
     pub background_color: Option<String>,
@@ -239,17 +555,198 @@ pub struct UpdateReadingSettingsRequest {
 }
 
 // 管理员用户列表项
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, FromRow, ToSchema)]
 pub struct AdminUserListItem {
     pub user_id: i64,
     pub username: String,
+    pub role: Role,
+    pub status: String,
     pub created_at: String,
+    pub last_active_at: Option<String>,
     pub book_count: i64,
+    pub storage_used: i64,
+    pub storage_quota: Option<i64>,
+    pub book_quota: Option<i64>,
     pub total_reading_time: i64,
 }
 
+// 管理员用户列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminUserListResponse {
+    pub users: Vec<AdminUserListItem>,
+}
+
+// 审计日志条目
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub actor_type: String,
+    pub actor_id: Option<i64>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<i64>,
+    #[schema(value_type = Option<Object>)]
+    pub detail: Option<sqlx::types::Json<serde_json::Value>>,
+    pub ip: Option<String>,
+    pub created_at: String,
+}
+
+// 审计日志查询结果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    pub total: i64,
+    pub entries: Vec<AuditLogEntry>,
+}
+
+// 按日统计的数据点
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct DailyStat {
+    pub date: String,
+    pub value: i64,
+}
+
+// 阅读时长最多的公开书籍
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct TopBook {
+    pub book_id: i64,
+    pub title: String,
+    pub author: Option<String>,
+    pub reader_count: i64,
+    pub reading_time: i64,
+}
+
+// 仪表盘统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatsResponse {
+    pub users: UserStats,
+    pub books: BookStats,
+    pub total_reading_time: i64,
+    pub uploads_per_day: Vec<DailyStat>,
+    pub reading_time_per_day: Vec<DailyStat>,
+    pub top_books: Vec<TopBook>,
+}
+
+// 用户数量统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserStats {
+    pub total: i64,
+    // 最近 7 天活跃的用户数
+    pub active_7d: i64,
+    // 最近 30 天活跃的用户数
+    pub active_30d: i64,
+    pub pending: i64,
+    pub disabled: i64,
+}
+
+// 书籍数量与存储用量统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BookStats {
+    pub total: i64,
+    pub public: i64,
+    pub total_storage: i64,
+}
+
+// 等待审核的注册申请
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct PendingRegistration {
+    pub user_id: i64,
+    pub username: String,
+    pub created_at: String,
+}
+
+// 注册申请列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegistrationListResponse {
+    pub registrations: Vec<PendingRegistration>,
+}
+
+// 设置用户配额请求，字段为空表示不限制
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateUserQuotaRequest {
+    pub storage_quota: Option<i64>,
+    pub book_quota: Option<i64>,
+}
+
+// 管理员书籍列表项
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct AdminBookListItem {
+    pub book_id: i64,
+    pub title: String,
+    pub author: Option<String>,
+    pub owner_id: i64,
+    pub owner_username: String,
+    pub is_public: bool,
+    pub file_size: i64,
+    pub chapter_count: i64,
+    pub created_at: String,
+}
+
+// 管理员书籍列表响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminBookListResponse {
+    pub total: i64,
+    pub books: Vec<AdminBookListItem>,
+}
+
+// 转移书籍所有权请求
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferBookRequest {
+    pub new_owner_id: i64,
+}
+
 // 重置用户密码请求
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResetUserPasswordRequest {
     pub new_password: String,
 }
+
+// 书籍文件与数据库一致性检查结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConsistencyReport {
+    // 书籍目录中没有对应记录的文件
+    pub orphan_files: Vec<String>,
+    // 文件已不存在的书籍
+    pub missing_files: Vec<MissingBookFile>,
+    // 位置超出文件末尾的章节
+    pub invalid_chapters: Vec<InvalidChapter>,
+    // 是否已修复上述问题
+    pub repaired: bool,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphan_files.is_empty()
+            && self.missing_files.is_empty()
+            && self.invalid_chapters.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MissingBookFile {
+    pub book_id: i64,
+    pub file_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InvalidChapter {
+    pub chapter_id: i64,
+    pub book_id: i64,
+    // 章节起始行号
+    pub position: i64,
+    // 文件实际行数
+    pub line_count: i64,
+}
+
+// 备份文件信息
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BackupInfo {
+    pub name: String,
+    pub size: i64,
+    pub created_at: String,
+}
+
+// 备份文件列表
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BackupListResponse {
+    pub backups: Vec<BackupInfo>,
+}
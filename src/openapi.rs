@@ -0,0 +1,97 @@
+use axum::{routing::get, Json, Router};
+use utoipa::{
+    openapi::{
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        ContentBuilder, OpenApi as OpenApiSpec, Ref, ResponseBuilder,
+    },
+    OpenApi,
+};
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::{
+    error::ErrorResponse,
+    routes::{admin, announcements, auth, books, health, oidc, reading},
+};
+
+// 接口文档的公共部分，各模块的接口在 openapi() 中合并
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Reader Server API",
+        description = "在线小说阅读服务接口。除文件下载外，响应均为 JSON，失败时返回带错误码的 ErrorResponse。"
+    ),
+    tags(
+        (name = "auth", description = "注册、登录与个人资料"),
+        (name = "oidc", description = "OpenID Connect 登录"),
+        (name = "books", description = "书籍上传与阅读"),
+        (name = "reading", description = "阅读设置与进度同步"),
+        (name = "announcements", description = "公告"),
+        (name = "admin", description = "管理员接口"),
+        (name = "health", description = "健康检查与监控"),
+    ),
+    components(schemas(ErrorResponse))
+)]
+struct ApiDoc;
+
+// 生成完整的 OpenAPI 文档
+pub fn openapi() -> OpenApiSpec {
+    let mut doc = ApiDoc::openapi();
+    // Cargo.toml 未声明许可证，不输出空的 license 字段
+    doc.info.license = None;
+    for api in [
+        auth::ApiDoc::openapi(),
+        oidc::ApiDoc::openapi(),
+        books::ApiDoc::openapi(),
+        reading::ApiDoc::openapi(),
+        announcements::ApiDoc::openapi(),
+        admin::ApiDoc::openapi(),
+        health::ApiDoc::openapi(),
+    ] {
+        doc.merge(api);
+    }
+
+    // 登录接口返回的令牌通过 Authorization: Bearer 传递
+    if let Some(components) = doc.components.as_mut() {
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+
+    // 所有接口失败时都返回统一的错误响应
+    let error_response = ResponseBuilder::new()
+        .description("失败，code 为错误码")
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                .build(),
+        )
+        .build();
+    for item in doc.paths.paths.values_mut() {
+        for operation in [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            operation
+                .responses
+                .responses
+                .insert("default".to_string(), error_response.clone().into());
+        }
+    }
+
+    doc
+}
+
+// 接口文档路由：JSON 格式的规范与交互式文档页面
+pub fn routes() -> Router<crate::app::AppState> {
+    let doc = openapi();
+    Router::new()
+        .route("/api/openapi.json", get(move || async move { Json(doc) }))
+        .merge(Scalar::with_url("/api/docs", openapi()).title("Reader Server API"))
+}
@@ -0,0 +1,80 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+use sqlx::{Pool, Sqlite};
+
+use crate::{config::Config, db, error::AppError, models::Settings};
+
+// 内置的常见/泄露密码列表，每行一个
+static COMMON_PASSWORDS: LazyLock<HashSet<String>> =
+    LazyLock::new(|| parse_list(include_str!("common_passwords.txt")));
+
+// 按系统设置中的密码策略校验密码，所有设置密码的地方都应调用
+pub async fn enforce(pool: &Pool<Sqlite>, config: &Config, password: &str) -> Result<(), AppError> {
+    let settings = db::load_settings(pool).await?;
+    check_rules(password, &settings)?;
+
+    if settings.password_check_breached && is_breached(config, password) {
+        return Err(AppError::PasswordPolicy(
+            "该密码已出现在泄露密码列表中，请更换".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// 校验长度与字符类别
+fn check_rules(password: &str, settings: &Settings) -> Result<(), AppError> {
+    let min_length = settings.password_min_length.max(1);
+    if (password.chars().count() as i64) < min_length {
+        return Err(AppError::PasswordPolicy(format!(
+            "密码长度不能少于 {} 个字符",
+            min_length
+        )));
+    }
+
+    let rules = [
+        (
+            settings.password_require_uppercase,
+            password.chars().any(|c| c.is_uppercase()),
+            "密码必须包含大写字母",
+        ),
+        (
+            settings.password_require_lowercase,
+            password.chars().any(|c| c.is_lowercase()),
+            "密码必须包含小写字母",
+        ),
+        (
+            settings.password_require_digit,
+            password.chars().any(|c| c.is_ascii_digit()),
+            "密码必须包含数字",
+        ),
+        (
+            settings.password_require_symbol,
+            password
+                .chars()
+                .any(|c| !c.is_alphanumeric() && !c.is_whitespace()),
+            "密码必须包含符号",
+        ),
+    ];
+    for (required, satisfied, message) in rules {
+        if required && !satisfied {
+            return Err(AppError::PasswordPolicy(message.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+// 解析密码列表，每行一个，统一转为小写以便忽略大小写比较
+pub fn parse_list(list: &str) -> HashSet<String> {
+    list.lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// 在内置列表与配置的额外列表中查找密码，忽略大小写
+fn is_breached(config: &Config, password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS.contains(&password) || config.password.breached_passwords.contains(&password)
+}
@@ -1,77 +1,663 @@
+use std::collections::HashMap;
+
 use axum::{
-    extract::{Path, State},
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
-use sqlx::{Pool, Sqlite};
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use serde::Deserialize;
+use sqlx::{Pool, QueryBuilder, Sqlite};
+use tokio_util::io::ReaderStream;
+use utoipa::{IntoParams, OpenApi};
 
 use crate::{
-    auth::AuthAdmin,
-    error::{ApiResponse, AppError},
+    audit::{self, Actor, ClientIp},
+    auth::{AuthAdmin, AuthModerator},
+    backup,
+    cache::SettingsCache,
+    config::Config,
+    db,
+    error::{ApiResponse, AppError, ErrorCode, MessageResponse},
+    i18n::Message,
+    invite,
     models::{
-        AdminUserListItem, CreateInviteCodeRequest, InviteCode, ResetUserPasswordRequest, Settings,
-        UpdateSettingsRequest, User,
+        AdminBookListItem, AdminBookListResponse, AdminUserListItem, AdminUserListResponse,
+        Announcement, AnnouncementListResponse, AnnouncementRequest, AuditLogEntry,
+        AuditLogResponse, BackupInfo, BackupListResponse, BatchCreateInviteCodesRequest,
+        BatchCreateInviteCodesResponse, BookStats, ConsistencyReport, CreateInviteCodeRequest,
+        CreateInviteCodeResponse, DailyStat, InviteCode, InviteCodeListResponse, InviteCodeUse,
+        InviteCodeUsesResponse, PendingRegistration, RegistrationListResponse,
+        ResetUserPasswordRequest, Role, Settings, SetupStatusResponse, StatsResponse, TopBook,
+        TransferBookRequest, UpdateBookRequest, UpdateSettingsRequest, UpdateUserQuotaRequest,
+        UpdateUserRoleRequest, UserStats,
     },
-    utils::{generate_invite_code, hash_password},
+    password_policy, storage,
+    utils::{escape_like, hash_password},
 };
 
+// 书籍列表查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BookListParams {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    // 按标题或作者模糊搜索
+    pub keyword: Option<String>,
+    pub user_id: Option<i64>,
+    pub is_public: Option<bool>,
+}
+
+// 审计日志查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogParams {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub action: Option<String>,
+    pub actor_type: Option<String>,
+    pub actor_id: Option<i64>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i64>,
+    pub ip: Option<String>,
+    // 时间范围（RFC 3339）
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+// 统计查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsParams {
+    // 按日统计的天数，默认 30 天
+    pub days: Option<i64>,
+}
+
 // 管理员路由
 pub fn routes() -> Router<crate::app::AppState> {
     Router::new()
         .route("/check_setup", get(check_setup))
+        .route("/stats", get(get_stats))
+        .route("/audit_log", get(list_audit_log))
+        .route("/consistency", get(check_consistency))
+        .route("/backups", get(list_backups).post(create_backup))
+        .route("/backups/{name}", get(download_backup))
+        .route("/consistency/repair", post(repair_consistency))
         .route("/invite_code", post(create_invite_code))
         .route("/invite_codes", get(list_invite_codes))
+        .route("/invite_codes/batch", post(batch_create_invite_codes))
+        .route(
+            "/invite_codes/{invite_id}",
+            axum::routing::delete(delete_invite_code),
+        )
+        .route("/invite_codes/{invite_id}/revoke", post(revoke_invite_code))
+        .route("/invite_codes/{invite_id}/uses", get(list_invite_code_uses))
         .route("/settings", get(get_settings).put(update_settings))
+        .route(
+            "/announcements",
+            get(list_announcements).post(create_announcement),
+        )
+        .route(
+            "/announcements/{announcement_id}",
+            axum::routing::put(update_announcement).delete(delete_announcement),
+        )
         .route("/users", get(list_users))
+        .route("/users/{user_id}", axum::routing::delete(delete_user))
         .route("/users/{user_id}/reset_password", post(reset_password))
+        .route("/users/{user_id}/disable", post(disable_user))
+        .route("/users/{user_id}/enable", post(enable_user))
+        .route(
+            "/users/{user_id}/quota",
+            axum::routing::put(update_user_quota),
+        )
+        .route(
+            "/users/{user_id}/role",
+            axum::routing::put(update_user_role),
+        )
+        .route("/registrations", get(list_registrations))
+        .route(
+            "/registrations/{user_id}/approve",
+            post(approve_registration),
+        )
+        .route("/registrations/{user_id}/reject", post(reject_registration))
+        .route("/books", get(list_books))
+        .route(
+            "/books/{book_id}",
+            axum::routing::put(update_book).delete(delete_book),
+        )
+        .route("/books/{book_id}/unpublish", post(unpublish_book))
+        .route("/books/{book_id}/transfer", post(transfer_book))
 }
 
-// 检查是否已设置管理员
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(
+    check_setup,
+    get_stats,
+    list_audit_log,
+    create_invite_code,
+    batch_create_invite_codes,
+    revoke_invite_code,
+    delete_invite_code,
+    list_invite_code_uses,
+    list_invite_codes,
+    get_settings,
+    update_settings,
+    check_consistency,
+    repair_consistency,
+    create_backup,
+    list_backups,
+    download_backup,
+    list_announcements,
+    create_announcement,
+    update_announcement,
+    delete_announcement,
+    list_users,
+    disable_user,
+    enable_user,
+    delete_user,
+    update_user_quota,
+    update_user_role,
+    list_registrations,
+    approve_registration,
+    reject_registration,
+    reset_password,
+    list_books,
+    update_book,
+    unpublish_book,
+    transfer_book,
+    delete_book,
+))]
+pub struct ApiDoc;
+
+/// 检查是否已设置管理员
+#[utoipa::path(
+    get,
+    path = "/api/admin/check_setup",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<SetupStatusResponse>)),
+)]
 async fn check_setup(
     State(pool): State<Pool<Sqlite>>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<SetupStatusResponse>>, AppError> {
     // 检查是否已有管理员
-    let admin_exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM admin)")
+    let admin_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE role = 'admin')")
+            .fetch_one(&pool)
+            .await?;
+
+    Ok(Json(ApiResponse::success(SetupStatusResponse {
+        is_setup: admin_exists,
+    })))
+}
+
+/// 仪表盘统计
+#[utoipa::path(
+    get,
+    path = "/api/admin/stats",
+    tag = "admin",
+    params(
+        StatsParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<StatsResponse>)),
+    security(("bearer" = [])),
+)]
+async fn get_stats(
+    _: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<StatsResponse>>, AppError> {
+    let days = params.days.unwrap_or(30).clamp(1, 365);
+    let today = Utc::now().date_naive();
+    let since = (today - Duration::days(days - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    // 用户数量
+    let (total_users, active_users_7d, active_users_30d, pending_users, disabled_users) =
+        sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+            "SELECT COUNT(*),
+                    COUNT(*) FILTER (WHERE last_active_at >= strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-7 days')),
+                    COUNT(*) FILTER (WHERE last_active_at >= strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-30 days')),
+                    COUNT(*) FILTER (WHERE status = 'pending'),
+                    COUNT(*) FILTER (WHERE status = 'disabled')
+             FROM users",
+        )
         .fetch_one(&pool)
         .await?;
 
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "is_setup": admin_exists
-    }))))
+    // 书籍数量与存储用量
+    let (total_books, public_books, total_storage) = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE is_public), COALESCE(SUM(file_size), 0)
+         FROM books",
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    // 总阅读时长
+    let total_reading_time =
+        sqlx::query_scalar::<_, i64>("SELECT COALESCE(SUM(total_reading_time), 0) FROM users")
+            .fetch_one(&pool)
+            .await?;
+
+    // 每日上传数量
+    let uploads_per_day = sqlx::query_as::<_, DailyStat>(
+        "SELECT substr(created_at, 1, 10) AS date, COUNT(*) AS value
+         FROM books
+         WHERE substr(created_at, 1, 10) >= ?
+         GROUP BY date",
+    )
+    .bind(&since)
+    .fetch_all(&pool)
+    .await?;
+
+    // 每日阅读时长
+    let reading_time_per_day = sqlx::query_as::<_, DailyStat>(
+        "SELECT date, SUM(reading_time) AS value
+         FROM reading_daily_stats
+         WHERE date >= ?
+         GROUP BY date",
+    )
+    .bind(&since)
+    .fetch_all(&pool)
+    .await?;
+
+    // 阅读时长最多的公开书籍
+    let top_books = sqlx::query_as::<_, TopBook>(
+        "SELECT b.id AS book_id, b.title, b.author,
+                COUNT(p.id) AS reader_count, SUM(p.reading_time) AS reading_time
+         FROM books b
+         JOIN reading_progress p ON p.book_id = b.id
+         WHERE b.is_public
+         GROUP BY b.id
+         HAVING SUM(p.reading_time) > 0
+         ORDER BY reading_time DESC
+         LIMIT 10",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(StatsResponse {
+        users: UserStats {
+            total: total_users,
+            active_7d: active_users_7d,
+            active_30d: active_users_30d,
+            pending: pending_users,
+            disabled: disabled_users,
+        },
+        books: BookStats {
+            total: total_books,
+            public: public_books,
+            total_storage,
+        },
+        total_reading_time,
+        uploads_per_day: fill_days(uploads_per_day, today, days),
+        reading_time_per_day: fill_days(reading_time_per_day, today, days),
+        top_books,
+    })))
 }
 
-// 创建邀请码
-async fn create_invite_code(
+// 补齐没有数据的日期，按日期升序返回最近 days 天
+fn fill_days(stats: Vec<DailyStat>, today: NaiveDate, days: i64) -> Vec<DailyStat> {
+    let values: HashMap<String, i64> = stats
+        .into_iter()
+        .map(|stat| (stat.date, stat.value))
+        .collect();
+
+    (0..days)
+        .rev()
+        .map(|offset| {
+            let date = (today - Duration::days(offset))
+                .format("%Y-%m-%d")
+                .to_string();
+            let value = values.get(&date).copied().unwrap_or(0);
+            DailyStat { date, value }
+        })
+        .collect()
+}
+
+/// 查询审计日志
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit_log",
+    tag = "admin",
+    params(
+        AuditLogParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<AuditLogResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_audit_log(
     _: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
+    Query(params): Query<AuditLogParams>,
+) -> Result<Json<ApiResponse<AuditLogResponse>>, AppError> {
+    // 解析分页参数
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let offset = (page - 1) * limit;
+
+    // 统一转换为 UTC；与 created_at 比较时通过 julianday() 换算，不依赖两边的小数位数一致
+    let parse_time = |field: &str, time: &Option<String>| {
+        time.as_deref()
+            .map(|time| {
+                DateTime::parse_from_rfc3339(time)
+                    .map(|time| {
+                        time.with_timezone(&Utc)
+                            .to_rfc3339_opts(SecondsFormat::Micros, true)
+                    })
+                    .map_err(|_| AppError::invalid_field(field, "时间格式无效"))
+            })
+            .transpose()
+    };
+    let since = parse_time("since", &params.since)?;
+    let until = parse_time("until", &params.until)?;
+
+    // 构建筛选条件
+    let push_filters = |builder: &mut QueryBuilder<Sqlite>| {
+        builder.push(" WHERE 1 = 1");
+        if let Some(action) = &params.action {
+            builder.push(" AND action = ").push_bind(action.clone());
+        }
+        if let Some(actor_type) = &params.actor_type {
+            builder
+                .push(" AND actor_type = ")
+                .push_bind(actor_type.clone());
+        }
+        if let Some(actor_id) = params.actor_id {
+            builder.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(target_type) = &params.target_type {
+            builder
+                .push(" AND target_type = ")
+                .push_bind(target_type.clone());
+        }
+        if let Some(target_id) = params.target_id {
+            builder.push(" AND target_id = ").push_bind(target_id);
+        }
+        if let Some(ip) = &params.ip {
+            builder.push(" AND ip = ").push_bind(ip.clone());
+        }
+        if let Some(since) = &since {
+            builder
+                .push(" AND julianday(created_at) >= julianday(")
+                .push_bind(since.clone())
+                .push(")");
+        }
+        if let Some(until) = &until {
+            builder
+                .push(" AND julianday(created_at) < julianday(")
+                .push_bind(until.clone())
+                .push(")");
+        }
+    };
+
+    // 获取总数
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM audit_log");
+    push_filters(&mut count_query);
+    let total = count_query
+        .build_query_scalar::<i64>()
+        .fetch_one(&pool)
+        .await?;
+
+    // 获取日志列表，最新的在前
+    let mut list_query = QueryBuilder::new("SELECT * FROM audit_log");
+    push_filters(&mut list_query);
+    list_query
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let entries = list_query
+        .build_query_as::<AuditLogEntry>()
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(ApiResponse::success(AuditLogResponse {
+        total,
+        entries,
+    })))
+}
+
+/// 创建邀请码
+#[utoipa::path(
+    post,
+    path = "/api/admin/invite_code",
+    tag = "admin",
+    request_body = CreateInviteCodeRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<CreateInviteCodeResponse>)),
+    security(("bearer" = [])),
+)]
+async fn create_invite_code(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
     Json(req): Json<CreateInviteCodeRequest>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
-    // 生成邀请码
-    let invite_code = generate_invite_code();
+) -> Result<Json<ApiResponse<CreateInviteCodeResponse>>, AppError> {
+    // 校验自定义邀请码与过期时间
+    if let Some(code) = &req.code {
+        invite::validate_code(code)?;
+    }
+    let expires_at = invite::parse_expires_at(req.expires_at.as_deref())?;
 
     // 插入数据库
-    sqlx::query!(
-        "INSERT INTO invite_codes (code, limit_times, description) VALUES (?, ?, ?)",
-        invite_code,
+    let invite_code = invite::insert(
+        &mut *pool.acquire().await?,
+        req.code.as_deref(),
         req.limit_times,
-        req.description
+        req.description.as_deref(),
+        expires_at.as_deref(),
     )
-    .execute(&pool)
     .await?;
 
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "invite_code.create",
+        None,
+        Some(serde_json::json!({ "invite_code": invite_code, "limit_times": req.limit_times, "expires_at": expires_at })),
+        &ip,
+    )
+    .await;
+
     // 返回创建的邀请码
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "invite_code": invite_code,
-        "limit_times": req.limit_times,
-        "description": req.description
-    }))))
+    Ok(Json(ApiResponse::success(CreateInviteCodeResponse {
+        invite_code,
+        limit_times: req.limit_times,
+        description: req.description,
+        expires_at,
+    })))
+}
+
+/// 批量生成邀请码
+#[utoipa::path(
+    post,
+    path = "/api/admin/invite_codes/batch",
+    tag = "admin",
+    request_body = BatchCreateInviteCodesRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<BatchCreateInviteCodesResponse>)),
+    security(("bearer" = [])),
+)]
+async fn batch_create_invite_codes(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Json(req): Json<BatchCreateInviteCodesRequest>,
+) -> Result<Json<ApiResponse<BatchCreateInviteCodesResponse>>, AppError> {
+    if !(1..=100).contains(&req.count) {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "生成数量必须在 1-100 之间".to_string(),
+        ));
+    }
+    let expires_at = invite::parse_expires_at(req.expires_at.as_deref())?;
+
+    // 全部生成成功才提交
+    let mut tx = pool.begin().await?;
+    let mut invite_codes = Vec::with_capacity(req.count as usize);
+    for _ in 0..req.count {
+        invite_codes.push(
+            invite::insert(
+                &mut tx,
+                None,
+                req.limit_times,
+                req.description.as_deref(),
+                expires_at.as_deref(),
+            )
+            .await?,
+        );
+    }
+    tx.commit().await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "invite_code.create",
+        None,
+        Some(serde_json::json!({ "invite_codes": invite_codes, "limit_times": req.limit_times, "expires_at": expires_at })),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(BatchCreateInviteCodesResponse {
+        invite_codes,
+        limit_times: req.limit_times,
+        description: req.description,
+        expires_at,
+    })))
 }
 
-// 查看所有邀请码
+/// 撤销邀请码，已注册的用户不受影响
+#[utoipa::path(
+    post,
+    path = "/api/admin/invite_codes/{invite_id}/revoke",
+    tag = "admin",
+    params(
+        ("invite_id" = i64, Path, description = "邀请码ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn revoke_invite_code(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(invite_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let result = sqlx::query!(
+        "UPDATE invite_codes SET revoked = 1 WHERE id = ?",
+        invite_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "邀请码不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "invite_code.revoke",
+        Some(("invite_code", invite_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::InviteCodeRevoked)))
+}
+
+/// 删除邀请码及其使用记录
+#[utoipa::path(
+    delete,
+    path = "/api/admin/invite_codes/{invite_id}",
+    tag = "admin",
+    params(
+        ("invite_id" = i64, Path, description = "邀请码ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn delete_invite_code(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(invite_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let result = sqlx::query!("DELETE FROM invite_codes WHERE id = ?", invite_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "邀请码不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "invite_code.delete",
+        Some(("invite_code", invite_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::InviteCodeDeleted)))
+}
+
+/// 查看通过某个邀请码注册的用户
+#[utoipa::path(
+    get,
+    path = "/api/admin/invite_codes/{invite_id}/uses",
+    tag = "admin",
+    params(
+        ("invite_id" = i64, Path, description = "邀请码ID"),
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<InviteCodeUsesResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_invite_code_uses(
+    _: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    Path(invite_id): Path<i64>,
+) -> Result<Json<ApiResponse<InviteCodeUsesResponse>>, AppError> {
+    let uses = sqlx::query_as::<_, InviteCodeUse>(
+        "SELECT u.id AS user_id, u.username, i.used_at
+         FROM invite_code_uses i
+         JOIN users u ON u.id = i.user_id
+         WHERE i.invite_code_id = ?
+         ORDER BY i.used_at DESC",
+    )
+    .bind(invite_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(InviteCodeUsesResponse { uses })))
+}
+
+/// 查看所有邀请码
+#[utoipa::path(
+    get,
+    path = "/api/admin/invite_codes",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<InviteCodeListResponse>)),
+    security(("bearer" = [])),
+)]
 async fn list_invite_codes(
     _: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<InviteCodeListResponse>>, AppError> {
     // 查询所有邀请码
     let invite_codes =
         sqlx::query_as::<_, InviteCode>("SELECT * FROM invite_codes ORDER BY created_at DESC")
@@ -79,105 +665,577 @@ async fn list_invite_codes(
             .await?;
 
     // 返回邀请码列表
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "invite_codes": invite_codes
-    }))))
+    Ok(Json(ApiResponse::success(InviteCodeListResponse {
+        invite_codes,
+    })))
 }
 
-// 获取系统设置
+/// 获取系统设置
+#[utoipa::path(
+    get,
+    path = "/api/admin/settings",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<Settings>)),
+    security(("bearer" = [])),
+)]
 async fn get_settings(
     _: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
 ) -> Result<Json<ApiResponse<Settings>>, AppError> {
     // 查询系统设置
-    let settings = sqlx::query_as::<_, Settings>("SELECT * FROM settings WHERE id = 1")
-        .fetch_optional(&pool)
-        .await?
-        .unwrap_or(Settings {
-            id: 1,
-            invite_code_required: true,
-        });
+    let settings = db::load_settings(&pool).await?;
 
     // 返回系统设置
     Ok(Json(ApiResponse::success(settings)))
 }
 
-// 更新系统设置
+/// 更新系统设置
+#[utoipa::path(
+    put,
+    path = "/api/admin/settings",
+    tag = "admin",
+    request_body = UpdateSettingsRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
 async fn update_settings(
-    _: AuthAdmin,
+    admin: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
+    State(settings): State<SettingsCache>,
+    ip: ClientIp,
     Json(req): Json<UpdateSettingsRequest>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
-    // 检查是否已有设置
-    let settings_exists =
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM settings WHERE id = 1)")
-            .fetch_one(&pool)
-            .await?;
+    // 验证密码最小长度
+    if let Some(min_length) = req.password_min_length {
+        if !(1..=128).contains(&min_length) {
+            return Err(AppError::Validation(
+                ErrorCode::BadRequest,
+                "密码最小长度必须在 1-128 之间".to_string(),
+            ));
+        }
+    }
 
-    if settings_exists {
-        // 更新设置
-        sqlx::query!(
-            "UPDATE settings SET invite_code_required = ? WHERE id = 1",
-            req.invite_code_required
-        )
+    // 确保设置存在
+    sqlx::query!("INSERT OR IGNORE INTO settings (id) VALUES (1)")
         .execute(&pool)
         .await?;
-    } else {
-        // 创建设置
-        sqlx::query!(
-            "INSERT INTO settings (id, invite_code_required) VALUES (1, ?)",
-            req.invite_code_required
-        )
+
+    // 更新设置，未提供的字段保持不变
+    sqlx::query!(
+        "UPDATE settings SET
+             registration_mode = COALESCE(?, registration_mode),
+             password_min_length = COALESCE(?, password_min_length),
+             password_require_uppercase = COALESCE(?, password_require_uppercase),
+             password_require_lowercase = COALESCE(?, password_require_lowercase),
+             password_require_digit = COALESCE(?, password_require_digit),
+             password_require_symbol = COALESCE(?, password_require_symbol),
+             password_check_breached = COALESCE(?, password_check_breached),
+             maintenance_mode = COALESCE(?, maintenance_mode),
+             maintenance_message = COALESCE(?, maintenance_message)
+         WHERE id = 1",
+        req.registration_mode,
+        req.password_min_length,
+        req.password_require_uppercase,
+        req.password_require_lowercase,
+        req.password_require_digit,
+        req.password_require_symbol,
+        req.password_check_breached,
+        req.maintenance_mode,
+        req.maintenance_message
+    )
+    .execute(&pool)
+    .await?;
+    settings.refresh(&pool).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "settings.update",
+        None,
+        serde_json::to_value(&req).ok(),
+        &ip,
+    )
+    .await;
+
+    // 返回成功信息
+    Ok(Json(ApiResponse::<()>::message(Message::SettingsUpdated)))
+}
+
+/// 检查书籍文件与数据库是否一致
+#[utoipa::path(
+    get,
+    path = "/api/admin/consistency",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<ConsistencyReport>)),
+    security(("bearer" = [])),
+)]
+async fn check_consistency(
+    _: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+) -> Result<Json<ApiResponse<ConsistencyReport>>, AppError> {
+    let report = storage::check_consistency(&pool, &config.storage.book_dir, false).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// 检查并修复书籍文件与数据库的不一致
+#[utoipa::path(
+    post,
+    path = "/api/admin/consistency/repair",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<ConsistencyReport>)),
+    security(("bearer" = [])),
+)]
+async fn repair_consistency(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    ip: ClientIp,
+) -> Result<Json<ApiResponse<ConsistencyReport>>, AppError> {
+    let report = storage::check_consistency(&pool, &config.storage.book_dir, true).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "storage.repair",
+        None,
+        Some(serde_json::json!({
+            "orphan_files": report.orphan_files.len(),
+            "missing_files": report.missing_files.len(),
+            "invalid_chapters": report.invalid_chapters.len(),
+        })),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// 立即创建备份
+#[utoipa::path(
+    post,
+    path = "/api/admin/backups",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<BackupInfo>)),
+    security(("bearer" = [])),
+)]
+async fn create_backup(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    ip: ClientIp,
+) -> Result<Json<ApiResponse<BackupInfo>>, AppError> {
+    let info = backup::create_backup(&pool, &config).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "backup.create",
+        None,
+        Some(serde_json::json!({ "name": info.name, "size": info.size })),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(info)))
+}
+
+/// 查看已有的备份
+#[utoipa::path(
+    get,
+    path = "/api/admin/backups",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<BackupListResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_backups(
+    _: AuthAdmin,
+    State(config): State<Config>,
+) -> Result<Json<ApiResponse<BackupListResponse>>, AppError> {
+    let backups = backup::list_backups(&config.backup.dir).await?;
+
+    Ok(Json(ApiResponse::success(BackupListResponse { backups })))
+}
+
+/// 下载备份文件
+#[utoipa::path(
+    get,
+    path = "/api/admin/backups/{name}",
+    tag = "admin",
+    params(
+        ("name" = String, Path, description = "备份文件名"),
+    ),
+    responses((status = 200, description = "备份 ZIP 文件", content_type = "application/zip", body = Vec<u8>)),
+    security(("bearer" = [])),
+)]
+async fn download_backup(
+    _: AuthAdmin,
+    State(config): State<Config>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let path = backup::backup_path(&config.backup.dir, &name)
+        .ok_or_else(|| AppError::NotFound(ErrorCode::NotFound, "备份不存在".to_string()))?;
+    // 备份可能很大，按块读取并发送，不一次性读入内存
+    let file = tokio::fs::File::open(&path).await.map_err(AppError::Io)?;
+    let size = file.metadata().await.map_err(AppError::Io)?.len();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_LENGTH, size.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", name),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    ))
+}
+
+/// 查看所有公告
+#[utoipa::path(
+    get,
+    path = "/api/admin/announcements",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<AnnouncementListResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_announcements(
+    _: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+) -> Result<Json<ApiResponse<AnnouncementListResponse>>, AppError> {
+    let announcements =
+        sqlx::query_as::<_, Announcement>("SELECT * FROM announcements ORDER BY created_at DESC")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(ApiResponse::success(AnnouncementListResponse {
+        announcements,
+    })))
+}
+
+/// 发布公告
+#[utoipa::path(
+    post,
+    path = "/api/admin/announcements",
+    tag = "admin",
+    request_body = AnnouncementRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<Announcement>)),
+    security(("bearer" = [])),
+)]
+async fn create_announcement(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Json(req): Json<AnnouncementRequest>,
+) -> Result<Json<ApiResponse<Announcement>>, AppError> {
+    let (starts_at, ends_at) = validate_announcement(&req)?;
+
+    let announcement = sqlx::query_as::<_, Announcement>(
+        "INSERT INTO announcements (title, content, starts_at, ends_at, created_by)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING *",
+    )
+    .bind(req.title.trim())
+    .bind(&req.content)
+    .bind(starts_at)
+    .bind(ends_at)
+    .bind(admin.user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "announcement.create",
+        Some(("announcement", announcement.id)),
+        Some(serde_json::json!({ "title": announcement.title })),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(announcement)))
+}
+
+/// 修改公告
+#[utoipa::path(
+    put,
+    path = "/api/admin/announcements/{announcement_id}",
+    tag = "admin",
+    params(
+        ("announcement_id" = i64, Path, description = "公告ID"),
+    ),
+    request_body = AnnouncementRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn update_announcement(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(announcement_id): Path<i64>,
+    Json(req): Json<AnnouncementRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let (starts_at, ends_at) = validate_announcement(&req)?;
+    let title = req.title.trim();
+
+    let result = sqlx::query!(
+        "UPDATE announcements SET title = ?, content = ?, starts_at = ?, ends_at = ? WHERE id = ?",
+        title,
+        req.content,
+        starts_at,
+        ends_at,
+        announcement_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "公告不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "announcement.update",
+        Some(("announcement", announcement_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(
+        Message::AnnouncementUpdated,
+    )))
+}
+
+/// 删除公告
+#[utoipa::path(
+    delete,
+    path = "/api/admin/announcements/{announcement_id}",
+    tag = "admin",
+    params(
+        ("announcement_id" = i64, Path, description = "公告ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn delete_announcement(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(announcement_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let result = sqlx::query!("DELETE FROM announcements WHERE id = ?", announcement_id)
         .execute(&pool)
         .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "公告不存在".to_string(),
+        ));
     }
 
-    // 返回成功信息
-    Ok(Json(ApiResponse::<()>::message("设置已更新")))
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "announcement.delete",
+        Some(("announcement", announcement_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(
+        Message::AnnouncementDeleted,
+    )))
+}
+
+// 校验公告内容，返回统一为 UTC 的展示时间范围
+fn validate_announcement(
+    req: &AnnouncementRequest,
+) -> Result<(Option<String>, Option<String>), AppError> {
+    if req.title.trim().is_empty() {
+        return Err(AppError::invalid_field("title", "公告标题不能为空"));
+    }
+
+    let parse = |field: &str, value: Option<&str>| {
+        value
+            .map(|value| {
+                DateTime::parse_from_rfc3339(value)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(|_| AppError::invalid_field(field, "公告时间必须是 RFC 3339 时间"))
+            })
+            .transpose()
+    };
+    let starts_at = parse("starts_at", req.starts_at.as_deref())?;
+    let ends_at = parse("ends_at", req.ends_at.as_deref())?;
+    if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at) {
+        if ends_at <= starts_at {
+            return Err(AppError::invalid_field(
+                "ends_at",
+                "公告结束时间必须晚于开始时间",
+            ));
+        }
+    }
+
+    let format = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Millis, true);
+    Ok((starts_at.map(format), ends_at.map(format)))
 }
 
-// 查看所有用户
+/// 查看所有用户
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<AdminUserListResponse>)),
+    security(("bearer" = [])),
+)]
 async fn list_users(
     _: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
-    // 查询所有用户
-    let users = sqlx::query_as::<_, User>("SELECT * FROM users ORDER BY created_at DESC")
-        .fetch_all(&pool)
+) -> Result<Json<ApiResponse<AdminUserListResponse>>, AppError> {
+    // 查询所有用户及其书籍数量与存储用量
+    let user_list = sqlx::query_as::<_, AdminUserListItem>(
+        "SELECT u.id AS user_id, u.username, u.role, u.status, u.created_at, u.last_active_at,
+                COUNT(b.id) AS book_count, COALESCE(SUM(b.file_size), 0) AS storage_used,
+                u.storage_quota, u.book_quota, u.total_reading_time
+         FROM users u
+         LEFT JOIN books b ON b.user_id = u.id
+         GROUP BY u.id
+         ORDER BY u.created_at DESC",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    // 返回用户列表
+    Ok(Json(ApiResponse::success(AdminUserListResponse {
+        users: user_list,
+    })))
+}
+
+/// 禁用用户，已签发的令牌随之失效
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/disable",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn disable_user(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    if db::is_last_admin(&pool, user_id).await? {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "不能禁用唯一的管理员".to_string(),
+        ));
+    }
+
+    let result = sqlx::query!("UPDATE users SET status = 'disabled' WHERE id = ?", user_id)
+        .execute(&pool)
         .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::UserNotFound,
+            "用户不存在".to_string(),
+        ));
+    }
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.disable",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
 
-    // 查询每个用户的书籍数量
-    let mut user_list = Vec::new();
-    for user in users {
-        let book_count =
-            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM books WHERE user_id = ?")
-                .bind(user.id)
-                .fetch_one(&pool)
-                .await?;
+    Ok(Json(ApiResponse::<()>::message(Message::UserDisabled)))
+}
 
-        user_list.push(AdminUserListItem {
-            user_id: user.id,
-            username: user.username,
-            created_at: user.created_at,
-            book_count,
-            total_reading_time: user.total_reading_time,
-        });
+/// 启用用户
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/enable",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn enable_user(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 只能启用被禁用的用户，待审核的用户需要通过注册审核接口处理
+    let status = sqlx::query_scalar::<_, String>("SELECT status FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(ErrorCode::UserNotFound, "用户不存在".to_string()))?;
+    if status == "pending" {
+        return Err(AppError::Forbidden(
+            ErrorCode::AccountPending,
+            "用户尚未通过审核，请通过注册审核接口处理".to_string(),
+        ));
     }
+    let result = sqlx::query!(
+        "UPDATE users SET status = 'active' WHERE id = ? AND status = 'disabled'",
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "用户未被禁用".to_string(),
+        ));
+    }
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.enable",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
 
-    // 返回用户列表
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "users": user_list
-    }))))
+    Ok(Json(ApiResponse::<()>::message(Message::UserEnabled)))
 }
 
-// 重置用户密码
-async fn reset_password(
-    _: AuthAdmin,
+/// 删除用户及其全部书籍文件
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{user_id}",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn delete_user(
+    admin: AuthAdmin,
     State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
     Path(user_id): Path<i64>,
-    Json(req): Json<ResetUserPasswordRequest>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
-    // 检查用户是否存在
     let user_exists =
         sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
             .bind(user_id)
@@ -185,16 +1243,297 @@ async fn reset_password(
             .await?;
 
     if !user_exists {
-        return Err(AppError::NotFound("用户不存在".to_string()));
+        return Err(AppError::NotFound(
+            ErrorCode::UserNotFound,
+            "用户不存在".to_string(),
+        ));
+    }
+
+    if db::is_last_admin(&pool, user_id).await? {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "不能删除唯一的管理员".to_string(),
+        ));
+    }
+
+    storage::delete_user(&pool, user_id).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.delete",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::UserDeleted)))
+}
+
+/// 设置用户配额
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{user_id}/quota",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    request_body = UpdateUserQuotaRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn update_user_quota(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+    Json(req): Json<UpdateUserQuotaRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    if req.storage_quota.is_some_and(|quota| quota < 0)
+        || req.book_quota.is_some_and(|quota| quota < 0)
+    {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "配额不能为负数".to_string(),
+        ));
     }
 
-    // 验证新密码
-    if req.new_password.len() < 6 {
+    let result = sqlx::query!(
+        "UPDATE users SET storage_quota = ?, book_quota = ? WHERE id = ?",
+        req.storage_quota,
+        req.book_quota,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::UserNotFound,
+            "用户不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.quota",
+        Some(("user", user_id)),
+        serde_json::to_value(&req).ok(),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::QuotaUpdated)))
+}
+
+/// 设置用户角色
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{user_id}/role",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    request_body = UpdateUserRoleRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn update_user_role(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+    Json(req): Json<UpdateUserRoleRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    if req.role != Role::Admin && db::is_last_admin(&pool, user_id).await? {
         return Err(AppError::Validation(
-            "新密码长度必须大于6个字符".to_string(),
+            ErrorCode::BadRequest,
+            "不能取消唯一的管理员的管理员角色".to_string(),
+        ));
+    }
+
+    let result = sqlx::query!("UPDATE users SET role = ? WHERE id = ?", req.role, user_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::UserNotFound,
+            "用户不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.role",
+        Some(("user", user_id)),
+        serde_json::to_value(&req).ok(),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::UserRoleUpdated)))
+}
+
+/// 查看等待审核的注册申请
+#[utoipa::path(
+    get,
+    path = "/api/admin/registrations",
+    tag = "admin",
+    responses((status = 200, description = "成功", body = ApiResponse<RegistrationListResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_registrations(
+    _: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+) -> Result<Json<ApiResponse<RegistrationListResponse>>, AppError> {
+    let registrations = sqlx::query_as::<_, PendingRegistration>(
+        "SELECT id AS user_id, username, created_at FROM users
+         WHERE status = 'pending'
+         ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(RegistrationListResponse {
+        registrations,
+    })))
+}
+
+/// 通过注册申请
+#[utoipa::path(
+    post,
+    path = "/api/admin/registrations/{user_id}/approve",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn approve_registration(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let result = sqlx::query!(
+        "UPDATE users SET status = 'active' WHERE id = ? AND status = 'pending'",
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "注册申请不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "registration.approve",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(
+        Message::RegistrationApproved,
+    )))
+}
+
+/// 拒绝注册申请，删除该用户
+#[utoipa::path(
+    post,
+    path = "/api/admin/registrations/{user_id}/reject",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn reject_registration(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(user_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let is_pending = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE id = ? AND status = 'pending')",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if !is_pending {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "注册申请不存在".to_string(),
         ));
     }
 
+    storage::delete_user(&pool, user_id).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "registration.reject",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(
+        Message::RegistrationRejected,
+    )))
+}
+
+/// 重置用户密码
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{user_id}/reset_password",
+    tag = "admin",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    request_body = ResetUserPasswordRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn reset_password(
+    admin: AuthAdmin,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    State(config): State<Config>,
+    Path(user_id): Path<i64>,
+    Json(req): Json<ResetUserPasswordRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 检查用户是否存在
+    let user_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
+
+    if !user_exists {
+        return Err(AppError::NotFound(
+            ErrorCode::UserNotFound,
+            "用户不存在".to_string(),
+        ));
+    }
+
+    // 验证密码策略
+    password_policy::enforce(&pool, &config, &req.new_password).await?;
+
     // 哈希新密码
     let new_password_hash = hash_password(&req.new_password)?;
 
@@ -207,6 +1546,308 @@ async fn reset_password(
     .execute(&pool)
     .await?;
 
+    audit::record(
+        &pool,
+        Actor::Admin(admin.user_id),
+        "user.password_reset",
+        Some(("user", user_id)),
+        None,
+        &ip,
+    )
+    .await;
+
     // 返回成功信息
-    Ok(Json(ApiResponse::<()>::message("用户密码重置成功")))
+    Ok(Json(ApiResponse::<()>::message(Message::UserPasswordReset)))
+}
+
+/// 查看全局书籍
+#[utoipa::path(
+    get,
+    path = "/api/admin/books",
+    tag = "admin",
+    params(
+        BookListParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<AdminBookListResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_books(
+    _: AuthModerator,
+    State(pool): State<Pool<Sqlite>>,
+    Query(params): Query<BookListParams>,
+) -> Result<Json<ApiResponse<AdminBookListResponse>>, AppError> {
+    // 解析分页参数
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    // 构建筛选条件
+    let push_filters = |builder: &mut QueryBuilder<Sqlite>| {
+        builder.push(" WHERE 1 = 1");
+        if let Some(keyword) = &params.keyword {
+            let pattern = format!("%{}%", escape_like(keyword));
+            builder
+                .push(" AND (b.title LIKE ")
+                .push_bind(pattern.clone())
+                .push(" ESCAPE '\\' OR b.author LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\')");
+        }
+        if let Some(user_id) = params.user_id {
+            builder.push(" AND b.user_id = ").push_bind(user_id);
+        }
+        if let Some(is_public) = params.is_public {
+            builder.push(" AND b.is_public = ").push_bind(is_public);
+        }
+    };
+
+    // 获取总数
+    let mut count_query = QueryBuilder::new("SELECT COUNT(*) FROM books b");
+    push_filters(&mut count_query);
+    let total = count_query
+        .build_query_scalar::<i64>()
+        .fetch_one(&pool)
+        .await?;
+
+    // 获取书籍列表
+    let mut list_query = QueryBuilder::new(
+        r#"
+        SELECT b.id AS book_id, b.title, b.author, b.user_id AS owner_id,
+               u.username AS owner_username, b.is_public, b.file_size, b.created_at,
+               (SELECT COUNT(*) FROM chapters c WHERE c.book_id = b.id) AS chapter_count
+        FROM books b
+        JOIN users u ON b.user_id = u.id
+        "#,
+    );
+    push_filters(&mut list_query);
+    list_query
+        .push(" ORDER BY b.created_at DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let books = list_query
+        .build_query_as::<AdminBookListItem>()
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(ApiResponse::success(AdminBookListResponse {
+        total,
+        books,
+    })))
+}
+
+/// 编辑书籍信息
+#[utoipa::path(
+    put,
+    path = "/api/admin/books/{book_id}",
+    tag = "admin",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    request_body = UpdateBookRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn update_book(
+    moderator: AuthModerator,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(book_id): Path<i64>,
+    Json(req): Json<UpdateBookRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    if req
+        .title
+        .as_deref()
+        .is_some_and(|title| title.trim().is_empty())
+    {
+        return Err(AppError::invalid_field("title", "标题不能为空"));
+    }
+
+    let result = sqlx::query!(
+        "UPDATE books SET
+             title = COALESCE(?, title),
+             author = COALESCE(?, author),
+             is_public = COALESCE(?, is_public)
+         WHERE id = ?",
+        req.title,
+        req.author,
+        req.is_public,
+        book_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::BookNotFound,
+            "书籍不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::from(&moderator),
+        "book.update",
+        Some(("book", book_id)),
+        serde_json::to_value(&req).ok(),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::Updated)))
+}
+
+/// 强制取消公开书籍
+#[utoipa::path(
+    post,
+    path = "/api/admin/books/{book_id}/unpublish",
+    tag = "admin",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn unpublish_book(
+    moderator: AuthModerator,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(book_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let result = sqlx::query!("UPDATE books SET is_public = 0 WHERE id = ?", book_id)
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            ErrorCode::BookNotFound,
+            "书籍不存在".to_string(),
+        ));
+    }
+
+    audit::record(
+        &pool,
+        Actor::from(&moderator),
+        "book.unpublish",
+        Some(("book", book_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::BookUnpublished)))
+}
+
+/// 转移书籍所有权
+#[utoipa::path(
+    post,
+    path = "/api/admin/books/{book_id}/transfer",
+    tag = "admin",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    request_body = TransferBookRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn transfer_book(
+    moderator: AuthModerator,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(book_id): Path<i64>,
+    Json(req): Json<TransferBookRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 检查与更新在同一写事务中完成，并发转移或上传不会超出新所有者的配额
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    let (owner_id, file_size) =
+        sqlx::query_as::<_, (i64, i64)>("SELECT user_id, file_size FROM books WHERE id = ?")
+            .bind(book_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
+
+    // 新所有者必须是正常状态的用户，且接收这本书后不超出其配额
+    let status = sqlx::query_scalar::<_, String>("SELECT status FROM users WHERE id = ?")
+        .bind(req.new_owner_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(ErrorCode::UserNotFound, "用户不存在".to_string()))?;
+    match status.as_str() {
+        "disabled" => {
+            return Err(AppError::Forbidden(
+                ErrorCode::AccountDisabled,
+                "新所有者的账号已被禁用".to_string(),
+            ))
+        }
+        "pending" => {
+            return Err(AppError::Forbidden(
+                ErrorCode::AccountPending,
+                "新所有者的账号尚未通过审核".to_string(),
+            ))
+        }
+        _ => {}
+    }
+    if owner_id != req.new_owner_id {
+        storage::check_quota(&mut tx, req.new_owner_id, file_size).await?;
+    }
+
+    sqlx::query!(
+        "UPDATE books SET user_id = ? WHERE id = ?",
+        req.new_owner_id,
+        book_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    audit::record(
+        &pool,
+        Actor::from(&moderator),
+        "book.transfer",
+        Some(("book", book_id)),
+        Some(serde_json::json!({ "new_owner_id": req.new_owner_id })),
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::BookTransferred)))
+}
+
+/// 删除书籍及其文件
+#[utoipa::path(
+    delete,
+    path = "/api/admin/books/{book_id}",
+    tag = "admin",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn delete_book(
+    moderator: AuthModerator,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Path(book_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let file_path = sqlx::query_scalar::<_, String>("SELECT file_path FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
+
+    storage::delete_book(&pool, book_id, &file_path).await?;
+
+    audit::record(
+        &pool,
+        Actor::from(&moderator),
+        "book.delete",
+        Some(("book", book_id)),
+        None,
+        &ip,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::Deleted)))
 }
@@ -0,0 +1,100 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use sqlx::{Pool, Sqlite};
+use utoipa::OpenApi;
+
+use crate::{
+    auth::AuthUser,
+    db::NOW,
+    error::{ApiResponse, AppError, ErrorCode, MessageResponse},
+    i18n::Message,
+    models::{Announcement, AnnouncementListResponse},
+};
+
+// 公告路由
+pub fn routes() -> Router<crate::app::AppState> {
+    Router::new()
+        .route("/", get(list_announcements))
+        .route("/{announcement_id}/dismiss", post(dismiss_announcement))
+}
+
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(list_announcements, dismiss_announcement,))]
+pub struct ApiDoc;
+
+/// 获取当前展示中且未被关闭的公告
+#[utoipa::path(
+    get,
+    path = "/api/announcements",
+    tag = "announcements",
+    responses((status = 200, description = "成功", body = ApiResponse<AnnouncementListResponse>)),
+    security(("bearer" = [])),
+)]
+async fn list_announcements(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+) -> Result<Json<ApiResponse<AnnouncementListResponse>>, AppError> {
+    let announcements = sqlx::query_as::<_, Announcement>(&format!(
+        "SELECT a.* FROM announcements a
+         WHERE (a.starts_at IS NULL OR a.starts_at <= {NOW})
+           AND (a.ends_at IS NULL OR a.ends_at > {NOW})
+           AND NOT EXISTS (
+               SELECT 1 FROM announcement_dismissals d
+               WHERE d.announcement_id = a.id AND d.user_id = ?
+           )
+         ORDER BY a.created_at DESC"
+    ))
+    .bind(auth.user_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(AnnouncementListResponse {
+        announcements,
+    })))
+}
+
+/// 关闭公告，之后不再向该用户展示
+#[utoipa::path(
+    post,
+    path = "/api/announcements/{announcement_id}/dismiss",
+    tag = "announcements",
+    params(
+        ("announcement_id" = i64, Path, description = "公告ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn dismiss_announcement(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+    Path(announcement_id): Path<i64>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM announcements WHERE id = ?)")
+            .bind(announcement_id)
+            .fetch_one(&pool)
+            .await?;
+
+    if !exists {
+        return Err(AppError::NotFound(
+            ErrorCode::NotFound,
+            "公告不存在".to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        "INSERT OR IGNORE INTO announcement_dismissals (user_id, announcement_id) VALUES (?, ?)",
+        auth.user_id,
+        announcement_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::<()>::message(
+        Message::AnnouncementDismissed,
+    )))
+}
@@ -1,19 +1,40 @@
+use std::io::{Seek, Write};
+
 use axum::{
-    extract::State,
-    routing::{get, post},
+    body::Body,
+    extract::{multipart::Multipart, Path, State},
+    http::header,
+    response::IntoResponse,
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use sqlx::{Pool, Sqlite};
+use tokio::fs;
+use tokio_util::io::ReaderStream;
+use utoipa::OpenApi;
+use zip::{write::SimpleFileOptions, ZipWriter};
 
 use crate::{
-    auth::{create_token, AuthUser, Claims},
+    audit::{self, Actor, ClientIp},
+    auth::{create_token, ensure_active, AuthUser, Claims},
+    cache::SettingsCache,
     config::Config,
-    error::{ApiResponse, AppError},
+    db,
+    error::{ApiResponse, AppError, ErrorCode, MessageResponse},
+    i18n::Message,
+    invite,
     models::{
-        Admin, AdminSetupRequest, ChangePasswordRequest, CreateUserRequest, LoginRequest, User,
+        AdminSetupRequest, AdminSetupResponse, AvatarResponse, AvatarUploadForm, Book,
+        ChangePasswordRequest, ChangeUsernameRequest, ChapterResponse, CreateUserRequest,
+        DeleteAccountRequest, LoginRequest, LoginResponse, PasswordPolicyResponse, ReadingProgress,
+        ReadingSettings, RegisterResponse, RegistrationMode, UpdateProfileRequest, User,
         UserInfoResponse,
     },
-    utils::{hash_password, verify_password},
+    password_policy, storage,
+    utils::{
+        hash_password, localize_time, validate_locale, validate_timezone, validate_username,
+        verify_password,
+    },
 };
 
 // 认证路由
@@ -23,61 +44,56 @@ pub fn routes() -> Router<crate::app::AppState> {
         .route("/login", post(login))
         .route("/user_info", get(user_info))
         .route("/change_password", post(change_password))
+        .route("/password_policy", get(get_password_policy))
+        .route("/profile", put(update_profile))
+        .route("/change_username", post(change_username))
+        .route("/avatar", post(upload_avatar))
+        .route("/avatar/{user_id}", get(get_avatar))
+        .route("/account", delete(delete_account))
+        .route("/export", get(export_data))
         .route("/admin/setup", post(admin_setup))
-        .route("/admin/login", post(admin_login))
 }
 
-// 用户注册
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(
+    register,
+    login,
+    user_info,
+    change_password,
+    get_password_policy,
+    update_profile,
+    change_username,
+    upload_avatar,
+    get_avatar,
+    delete_account,
+    export_data,
+    admin_setup,
+))]
+pub struct ApiDoc;
+
+/// 用户注册
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = CreateUserRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<RegisterResponse>)),
+)]
 async fn register(
     State(pool): State<Pool<Sqlite>>,
     State(config): State<Config>,
+    ip: ClientIp,
     Json(req): Json<CreateUserRequest>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<RegisterResponse>>, AppError> {
     // 验证用户名格式
-    if req.username.len() < 2 || req.username.len() > 20 {
-        return Err(AppError::Validation(
-            "用户名长度必须在 2-20 个字符之间".to_string(),
-        ));
-    }
-
-    // 验证密码格式
-    if req.password.is_empty() {
-        return Err(AppError::Validation("密码不能为空".to_string()));
-    }
+    validate_username(&req.username)?;
 
-    // 检查系统设置
-    let invite_code_required =
-        sqlx::query_scalar::<_, bool>("SELECT invite_code_required FROM settings WHERE id = 1")
-            .fetch_optional(&pool)
-            .await?
-            .unwrap_or(false);
+    // 验证密码策略
+    password_policy::enforce(&pool, &config, &req.password).await?;
 
-    // 如果系统需要邀请码，但用户未提供
-    if invite_code_required && req.invite_code.is_none() {
-        return Err(AppError::Validation("注册需要邀请码".to_string()));
-    }
-
-    // 验证邀请码
-    if let Some(invite_code) = &req.invite_code {
-        let invite = sqlx::query!(
-            "SELECT id, limit_times, used_times FROM invite_codes WHERE code = ?",
-            invite_code
-        )
-        .fetch_optional(&pool)
-        .await?;
-
-        match invite {
-            Some(invite) if invite.limit_times >= 0 && invite.used_times >= invite.limit_times => {
-                return Err(AppError::Validation("邀请码已用完".to_string()));
-            }
-            None => {
-                return Err(AppError::Validation("邀请码无效".to_string()));
-            }
-            _ => {
-                // 邀请码有效
-            }
-        }
-    }
+    // 检查注册模式与邀请码
+    let status = check_registration(&pool, req.invite_code.as_deref()).await?;
 
     // 检查用户名是否已存在
     let exists =
@@ -87,80 +103,163 @@ async fn register(
             .await?;
 
     if exists {
-        return Err(AppError::Validation("用户名已存在".to_string()));
+        return Err(
+            AppError::Validation(ErrorCode::UsernameTaken, "用户名已存在".to_string())
+                .for_field("username"),
+        );
     }
 
     // 哈希密码
     let password_hash = hash_password(&req.password)?;
 
-    // 创建用户
-    let user_id = sqlx::query!(
-        "INSERT INTO users (username, password_hash) VALUES (?, ?)",
-        req.username,
-        password_hash
+    // 创建用户（同时创建默认阅读设置）并扣减邀请码，二者在同一事务中完成
+    let mut tx = pool.begin().await?;
+    let user_id = db::create_user(&mut tx, &req.username, &password_hash, status).await?;
+    if let Some(invite_code) = &req.invite_code {
+        invite::consume(&mut tx, invite_code, user_id).await?;
+    }
+    tx.commit().await?;
+
+    audit::record(
+        &pool,
+        Actor::User(user_id),
+        "user.register",
+        Some(("user", user_id)),
+        Some(serde_json::json!({ "username": req.username, "status": status })),
+        &ip,
     )
-    .execute(&pool)
-    .await?
-    .last_insert_rowid();
-
-    // 更新邀请码使用次数
+    .await;
     if let Some(invite_code) = &req.invite_code {
-        sqlx::query!(
-            "UPDATE invite_codes SET used_times = used_times + 1 WHERE code = ?",
-            invite_code
+        audit::record(
+            &pool,
+            Actor::User(user_id),
+            "invite_code.use",
+            None,
+            Some(serde_json::json!({ "invite_code": invite_code })),
+            &ip,
         )
-        .execute(&pool)
-        .await?;
+        .await;
     }
 
-    // 为用户创建默认阅读设置
-    sqlx::query!("INSERT INTO reading_settings (user_id) VALUES (?)", user_id)
-        .execute(&pool)
-        .await?;
+    // 需要审核的用户暂不签发令牌
+    if status == "pending" {
+        return Ok(Json(ApiResponse {
+            code: 0,
+            message: Message::RegistrationPending.localized().to_string(),
+            data: Some(RegisterResponse {
+                user_id,
+                username: req.username,
+                status: status.to_string(),
+                token: None,
+            }),
+        }));
+    }
 
     // 生成JWT令牌
     let claims = Claims::new_user(user_id, &config);
     let token = create_token(&claims, &config)?;
 
     // 返回用户信息和令牌
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "user_id": user_id,
-        "username": req.username,
-        "token": token
-    }))))
+    Ok(Json(ApiResponse::success(RegisterResponse {
+        user_id,
+        username: req.username,
+        status: status.to_string(),
+        token: Some(token),
+    })))
 }
 
-// 用户登录
+// 根据注册模式检查能否注册，返回新用户的初始状态
+pub(crate) async fn check_registration(
+    pool: &Pool<Sqlite>,
+    invite_code: Option<&str>,
+) -> Result<&'static str, AppError> {
+    let mode = db::load_settings(pool).await?.registration_mode;
+    if mode == RegistrationMode::Closed {
+        return Err(AppError::Forbidden(
+            ErrorCode::RegistrationClosed,
+            "系统已关闭注册".to_string(),
+        ));
+    }
+
+    invite::check(pool, invite_code, mode == RegistrationMode::Invite).await?;
+
+    Ok(match mode {
+        RegistrationMode::Approval => "pending",
+        _ => "active",
+    })
+}
+
+/// 用户登录
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<LoginResponse>)),
+)]
 async fn login(
     State(pool): State<Pool<Sqlite>>,
     State(config): State<Config>,
+    ip: ClientIp,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
     // 查找用户
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?")
         .bind(&req.username)
         .fetch_optional(&pool)
-        .await?
-        .ok_or_else(|| AppError::Auth("用户名或密码错误".to_string()))?;
+        .await?;
 
-    // 验证密码
-    if !verify_password(&req.password, &user.password_hash)? {
-        return Err(AppError::Auth("用户名或密码错误".to_string()));
+    // 验证密码与账号状态
+    let result = match &user {
+        Some(user) if verify_password(&req.password, &user.password_hash)? => {
+            ensure_active(&user.status)
+        }
+        _ => Err(AppError::Auth("用户名或密码错误".to_string())),
+    };
+    if let Err(e) = result {
+        audit::record(
+            &pool,
+            Actor::Anonymous,
+            "login.failure",
+            user.as_ref().map(|user| ("user", user.id)),
+            Some(serde_json::json!({ "username": req.username, "reason": e.to_string() })),
+            &ip,
+        )
+        .await;
+        return Err(e);
     }
+    let user = user.expect("登录成功时用户一定存在");
+
+    audit::record(
+        &pool,
+        Actor::User(user.id),
+        "login.success",
+        Some(("user", user.id)),
+        Some(serde_json::json!({ "device_id": req.device_id })),
+        &ip,
+    )
+    .await;
 
     // 生成JWT令牌
     let claims = Claims::new_user(user.id, &config);
     let token = create_token(&claims, &config)?;
 
     // 返回用户信息和令牌
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "user_id": user.id,
-        "username": user.username,
-        "token": token
-    }))))
+    Ok(Json(ApiResponse::success(LoginResponse {
+        user_id: user.id,
+        username: user.username,
+        token,
+    })))
 }
 
-// 获取用户信息
+/// 获取用户信息
+#[utoipa::path(
+    get,
+    path = "/api/auth/user_info",
+    tag = "auth",
+    responses((status = 200, description = "成功", body = ApiResponse<UserInfoResponse>)),
+    security(("bearer" = [])),
+)]
 async fn user_info(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
@@ -181,15 +280,34 @@ async fn user_info(
     Ok(Json(ApiResponse::success(UserInfoResponse {
         user_id: user.id,
         username: user.username,
+        display_name: user.display_name,
+        avatar_url: user
+            .avatar_path
+            .map(|_| format!("/api/auth/avatar/{}", user.id)),
+        created_at: localize_time(&user.created_at, &user.timezone),
+        timezone: user.timezone,
+        locale: user.locale,
+        has_password: user.has_password,
+        role: user.role,
         total_reading_time: user.total_reading_time,
         book_count,
     })))
 }
 
-// 修改密码
+/// 修改密码
+#[utoipa::path(
+    post,
+    path = "/api/auth/change_password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
 async fn change_password(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    ip: ClientIp,
     Json(req): Json<ChangePasswordRequest>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
     // 查询用户信息
@@ -198,117 +316,637 @@ async fn change_password(
         .fetch_one(&pool)
         .await?;
 
-    // 验证旧密码
-    if !verify_password(&req.old_password, &user.password_hash)? {
-        return Err(AppError::Validation("旧密码不正确".to_string()));
+    // 验证旧密码，没有本地密码的用户需要刚登录
+    if user.has_password {
+        let old_password = req
+            .old_password
+            .as_deref()
+            .ok_or_else(|| AppError::invalid_field("old_password", "请输入旧密码"))?;
+        if !verify_password(old_password, &user.password_hash)? {
+            return Err(
+                AppError::Validation(ErrorCode::WrongPassword, "旧密码不正确".to_string())
+                    .for_field("old_password"),
+            );
+        }
+    } else {
+        require_recent_login(&auth, "old_password")?;
     }
 
-    // 验证新密码
-    if req.new_password.len() < 6 {
-        return Err(AppError::Validation(
-            "新密码长度必须大于6个字符".to_string(),
-        ));
-    }
+    // 验证密码策略
+    password_policy::enforce(&pool, &config, &req.new_password).await?;
 
     // 哈希新密码
     let new_password_hash = hash_password(&req.new_password)?;
 
     // 更新密码
     sqlx::query!(
-        "UPDATE users SET password_hash = ? WHERE id = ?",
+        "UPDATE users SET password_hash = ?, has_password = 1 WHERE id = ?",
         new_password_hash,
         auth.user_id
     )
     .execute(&pool)
     .await?;
 
+    audit::record(
+        &pool,
+        Actor::User(auth.user_id),
+        "password.change",
+        Some(("user", auth.user_id)),
+        None,
+        &ip,
+    )
+    .await;
+
     // 返回成功信息
-    Ok(Json(ApiResponse::<()>::message("密码修改成功")))
+    Ok(Json(ApiResponse::<()>::message(Message::PasswordChanged)))
 }
 
-// 管理员首次设置密码
-async fn admin_setup(
+/// 获取当前密码策略，供前端提示
+#[utoipa::path(
+    get,
+    path = "/api/auth/password_policy",
+    tag = "auth",
+    responses((status = 200, description = "成功", body = ApiResponse<PasswordPolicyResponse>)),
+)]
+async fn get_password_policy(
+    State(pool): State<Pool<Sqlite>>,
+) -> Result<Json<ApiResponse<PasswordPolicyResponse>>, AppError> {
+    let settings = db::load_settings(&pool).await?;
+
+    Ok(Json(ApiResponse::success(PasswordPolicyResponse {
+        min_length: settings.password_min_length,
+        require_uppercase: settings.password_require_uppercase,
+        require_lowercase: settings.password_require_lowercase,
+        require_digit: settings.password_require_digit,
+        require_symbol: settings.password_require_symbol,
+        check_breached: settings.password_check_breached,
+    })))
+}
+
+/// 更新个人资料
+#[utoipa::path(
+    put,
+    path = "/api/auth/profile",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn update_profile(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+    Json(req): Json<UpdateProfileRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 验证字段
+    if let Some(display_name) = &req.display_name {
+        if display_name.chars().count() > 30 {
+            return Err(AppError::invalid_field(
+                "display_name",
+                "昵称长度不能超过 30 个字符",
+            ));
+        }
+    }
+    if let Some(timezone) = &req.timezone {
+        validate_timezone(timezone)?;
+    }
+    if let Some(locale) = &req.locale {
+        validate_locale(locale)?;
+    }
+
+    // 空昵称表示清除昵称
+    let display_name = req
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let clear_display_name = req.display_name.is_some() && display_name.is_none();
+
+    sqlx::query!(
+        "UPDATE users SET
+             display_name = CASE WHEN ? THEN NULL ELSE COALESCE(?, display_name) END,
+             timezone = COALESCE(?, timezone),
+             locale = COALESCE(?, locale)
+         WHERE id = ?",
+        clear_display_name,
+        display_name,
+        req.timezone,
+        req.locale,
+        auth.user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::<()>::message(Message::Updated)))
+}
+
+/// 修改用户名
+#[utoipa::path(
+    post,
+    path = "/api/auth/change_username",
+    tag = "auth",
+    request_body = ChangeUsernameRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn change_username(
+    auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     State(config): State<Config>,
-    Json(req): Json<AdminSetupRequest>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
-    // 验证密码格式
-    if req.password.len() < 6 {
-        return Err(AppError::Validation("密码长度必须大于6个字符".to_string()));
+    Json(req): Json<ChangeUsernameRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 反向代理认证以用户名识别用户，修改后会被当作新用户
+    if config.proxy_auth.enabled {
+        return Err(AppError::Validation(
+            ErrorCode::BadRequest,
+            "已启用反向代理认证，无法修改用户名".to_string(),
+        ));
+    }
+
+    // 验证用户名格式
+    validate_username(&req.new_username).map_err(|e| e.for_field("new_username"))?;
+
+    // 检查用户名是否已存在
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE username = ? AND id != ?)",
+    )
+    .bind(&req.new_username)
+    .bind(auth.user_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if exists {
+        return Err(
+            AppError::Validation(ErrorCode::UsernameTaken, "用户名已存在".to_string())
+                .for_field("new_username"),
+        );
+    }
+
+    // 唯一约束兜底并发修改
+    match sqlx::query!(
+        "UPDATE users SET username = ? WHERE id = ?",
+        req.new_username,
+        auth.user_id
+    )
+    .execute(&pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::<()>::message(Message::UsernameChanged))),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(AppError::Validation(
+            ErrorCode::UsernameTaken,
+            "用户名已存在".to_string(),
+        )
+        .for_field("new_username")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 上传头像
+#[utoipa::path(
+    post,
+    path = "/api/auth/avatar",
+    tag = "auth",
+    request_body(content = AvatarUploadForm, content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = ApiResponse<AvatarResponse>)),
+    security(("bearer" = [])),
+)]
+async fn upload_avatar(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<AvatarResponse>>, AppError> {
+    // 读取头像文件
+    let mut avatar = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("解析表单数据失败: {}", e)))?
+    {
+        if field.name() == Some("file") {
+            let file_name = field.file_name().unwrap_or_default().to_lowercase();
+            let content = field
+                .bytes()
+                .await
+                .map_err(|e| AppError::BadRequest(format!("读取文件内容失败: {}", e)))?;
+            avatar = Some((file_name, content));
+        }
+    }
+    let (file_name, content) =
+        avatar.ok_or_else(|| AppError::invalid_field("file", "文件不能为空"))?;
+
+    // 验证文件大小
+    if content.len() > 2 * 1024 * 1024 {
+        return Err(AppError::Validation(
+            ErrorCode::FileTooLarge,
+            "头像大小不能超过2MB".to_string(),
+        ));
+    }
+
+    // 验证文件格式，扩展名与文件头必须一致
+    let extension = file_name.rsplit('.').next().unwrap_or_default();
+    let content_type = avatar_content_type(extension);
+    if content_type.is_none() || content_type != sniff_image_type(&content) {
+        return Err(AppError::Validation(
+            ErrorCode::UnsupportedFileType,
+            "头像只支持 PNG、JPG、GIF、WEBP 格式".to_string(),
+        ));
+    }
+
+    // 保存新头像，数据库更新失败时删除新文件
+    let avatar_file =
+        storage::write_new_file(&config.storage.avatar_dir, extension, &content).await?;
+
+    let mut tx = pool.begin().await?;
+    let old_avatar =
+        sqlx::query_scalar::<_, Option<String>>("SELECT avatar_path FROM users WHERE id = ?")
+            .bind(auth.user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let avatar_path = avatar_file.path();
+    sqlx::query!(
+        "UPDATE users SET avatar_path = ? WHERE id = ?",
+        avatar_path,
+        auth.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    avatar_file.keep();
+
+    // 提交后再删除旧头像，删除失败只留下孤立文件
+    if let Some(old_avatar) = old_avatar {
+        if let Err(e) = storage::remove_file_if_exists(&old_avatar).await {
+            tracing::warn!("删除旧头像 {} 失败: {}", old_avatar, e);
+        }
     }
 
-    // 检查是否已有管理员
-    let admin_exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM admin)")
+    Ok(Json(ApiResponse::success(AvatarResponse {
+        avatar_url: format!("/api/auth/avatar/{}", auth.user_id),
+    })))
+}
+
+/// 获取用户头像，不需要认证，便于直接用作图片地址
+#[utoipa::path(
+    get,
+    path = "/api/auth/avatar/{user_id}",
+    tag = "auth",
+    params(
+        ("user_id" = i64, Path, description = "用户ID"),
+    ),
+    responses((status = 200, description = "头像图片", content_type = "image/*", body = Vec<u8>)),
+)]
+async fn get_avatar(
+    State(pool): State<Pool<Sqlite>>,
+    Path(user_id): Path<i64>,
+) -> Result<impl IntoResponse, AppError> {
+    let avatar_path =
+        sqlx::query_scalar::<_, Option<String>>("SELECT avatar_path FROM users WHERE id = ?")
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await?
+            .flatten()
+            .ok_or_else(|| AppError::NotFound(ErrorCode::NotFound, "头像不存在".to_string()))?;
+
+    let extension = avatar_path.rsplit('.').next().unwrap_or_default();
+    let content_type = avatar_content_type(extension).unwrap_or("application/octet-stream");
+    let content = fs::read(&avatar_path).await.map_err(AppError::Io)?;
+
+    Ok(([(header::CONTENT_TYPE, content_type)], content))
+}
+
+// 根据扩展名获取头像的 MIME 类型，不支持的格式返回 None
+fn avatar_content_type(extension: &str) -> Option<&'static str> {
+    match extension {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+// 根据文件头识别图片的 MIME 类型，不是支持的图片格式时返回 None
+fn sniff_image_type(content: &[u8]) -> Option<&'static str> {
+    if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if content.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+// 没有本地密码的用户要求刚通过身份源完成认证（反向代理或几分钟内重新登录）
+// 反向代理和 OIDC 创建的用户使用随机密码，只能这样确认身份
+fn require_recent_login(auth: &AuthUser, field: &str) -> Result<(), AppError> {
+    if auth.recently_authenticated {
+        Ok(())
+    } else {
+        Err(AppError::invalid_field(
+            field,
+            "请输入密码，或重新登录后再试",
+        ))
+    }
+}
+
+/// 注销账号，删除用户及其上传的书籍文件
+#[utoipa::path(
+    delete,
+    path = "/api/auth/account",
+    tag = "auth",
+    request_body = DeleteAccountRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
+async fn delete_account(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    // 查询用户信息
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(auth.user_id)
         .fetch_one(&pool)
         .await?;
 
-    if admin_exists {
+    // 验证密码，没有本地密码的用户需要刚登录
+    if user.has_password {
+        let password = req
+            .password
+            .as_deref()
+            .ok_or_else(|| AppError::invalid_field("password", "请输入密码"))?;
+        if !verify_password(password, &user.password_hash)? {
+            return Err(AppError::invalid_field("password", "密码不正确"));
+        }
+    } else {
+        require_recent_login(&auth, "password")?;
+    }
+
+    if db::is_last_admin(&pool, auth.user_id).await? {
         return Err(AppError::Validation(
-            "管理员已设置，无法重复设置".to_string(),
+            ErrorCode::BadRequest,
+            "不能注销唯一的管理员账号".to_string(),
         ));
     }
 
-    // 哈希密码
-    let password_hash = hash_password(&req.password)?;
-
-    // 创建管理员
-    let admin_id = sqlx::query!(
-        "INSERT INTO admin (password_hash) VALUES (?)",
-        password_hash
+    storage::delete_user(&pool, auth.user_id).await?;
+    tracing::info!("用户 {} (id={}) 已注销账号", user.username, user.id);
+    audit::record(
+        &pool,
+        Actor::User(user.id),
+        "user.delete",
+        Some(("user", user.id)),
+        Some(serde_json::json!({ "username": user.username })),
+        &ip,
     )
-    .execute(&pool)
-    .await?
-    .last_insert_rowid();
+    .await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::AccountDeleted)))
+}
+
+/// 导出用户数据，打包为 ZIP
+#[utoipa::path(
+    get,
+    path = "/api/auth/export",
+    tag = "auth",
+    responses((status = 200, description = "用户数据 ZIP 包", content_type = "application/zip", body = Vec<u8>)),
+    security(("bearer" = [])),
+)]
+async fn export_data(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+) -> Result<impl IntoResponse, AppError> {
+    // 查询用户信息
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(auth.user_id)
+        .fetch_one(&pool)
+        .await?;
 
-    // 初始化系统设置
-    sqlx::query!("INSERT OR IGNORE INTO settings (id, invite_code_required) VALUES (1, 1)")
-        .execute(&pool)
+    // 查询用户上传的书籍及章节
+    let books = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE user_id = ? ORDER BY id")
+        .bind(auth.user_id)
+        .fetch_all(&pool)
         .await?;
 
-    // 不创建初始邀请码
-    // let invite_code = generate_invite_code();
-    // sqlx::query!(
-    //     "INSERT INTO invite_codes (code, limit_times, description) VALUES (?, ?,
-    // ?)",     invite_code,
-    //     10,
-    //     "初始邀请码"
-    // )
-    // .execute(&pool)
-    // .await?;
+    let mut book_entries = Vec::new();
+    let mut disk_files = Vec::new();
+    for book in &books {
+        let chapters = sqlx::query!(
+            "SELECT id, title, position FROM chapters WHERE book_id = ? ORDER BY position",
+            book.id
+        )
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|chapter| ChapterResponse {
+            chapter_id: chapter.id,
+            title: chapter.title,
+            position: chapter.position,
+        })
+        .collect::<Vec<_>>();
+
+        let file_name = format!("books/{}.txt", book.id);
+        disk_files.push((file_name.clone(), book.file_path.clone()));
+
+        book_entries.push(serde_json::json!({
+            "book_id": book.id,
+            "title": book.title,
+            "author": book.author,
+            "is_public": book.is_public,
+            "created_at": book.created_at,
+            "file": file_name,
+            "chapters": chapters,
+        }));
+    }
 
-    // 生成JWT令牌
-    let claims = Claims::new_admin(admin_id, &config);
-    let token = create_token(&claims, &config)?;
+    // 查询阅读进度（包括阅读过的公开书籍）
+    let progress = sqlx::query_as::<_, ReadingProgress>(
+        "SELECT * FROM reading_progress WHERE user_id = ? ORDER BY book_id",
+    )
+    .bind(auth.user_id)
+    .fetch_all(&pool)
+    .await?;
 
-    // 返回管理员令牌
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "admin_token": token
-    }))))
+    // 查询阅读设置
+    let settings =
+        sqlx::query_as::<_, ReadingSettings>("SELECT * FROM reading_settings WHERE user_id = ?")
+            .bind(auth.user_id)
+            .fetch_optional(&pool)
+            .await?;
+
+    let stats = serde_json::json!({
+        "total_reading_time": user.total_reading_time,
+        "book_count": books.len(),
+        "read_book_count": progress.iter().filter(|p| p.reading_time > 0).count(),
+    });
+
+    let json_files = [
+        ("user.json", serde_json::to_vec_pretty(&user)),
+        ("books.json", serde_json::to_vec_pretty(&book_entries)),
+        (
+            "reading_progress.json",
+            serde_json::to_vec_pretty(&progress),
+        ),
+        (
+            "reading_settings.json",
+            serde_json::to_vec_pretty(&settings),
+        ),
+        ("stats.json", serde_json::to_vec_pretty(&stats)),
+    ];
+    let mut files = Vec::new();
+    for (name, content) in json_files {
+        let content =
+            content.map_err(|e| AppError::Internal(format!("序列化导出数据失败: {}", e)))?;
+        files.push((name.to_string(), content));
+    }
+
+    // 头像按原扩展名放在压缩包根目录
+    if let Some(avatar_path) = &user.avatar_path {
+        let extension = std::path::Path::new(avatar_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("bin");
+        disk_files.push((format!("avatar.{}", extension), avatar_path.clone()));
+    }
+
+    // 书籍文件可能很多，压缩包写入临时文件后按块发送，不在内存中保存
+    // 临时文件没有目录项，句柄关闭后由系统回收；压缩是同步操作，放到阻塞线程中执行
+    let archive = tokio::task::spawn_blocking(move || write_export_archive(files, disk_files))
+        .await
+        .map_err(|e| AppError::Internal(format!("导出任务失败: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("生成导出文件失败: {}", e)))?;
+    let archive = fs::File::from_std(archive);
+    let size = archive.metadata().await.map_err(AppError::Io)?.len();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_LENGTH, size.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"reader-export-{}.zip\"", user.id),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(archive)),
+    ))
 }
 
-// 管理员登录
-async fn admin_login(
+// 写入导出压缩包：JSON 数据来自内存，书籍与头像文件从磁盘逐个复制
+// 返回定位到开头的匿名临时文件
+fn write_export_archive(
+    files: Vec<(String, Vec<u8>)>,
+    disk_files: Vec<(String, String)>,
+) -> zip::result::ZipResult<std::fs::File> {
+    let mut zip = ZipWriter::new(tempfile::tempfile()?);
+    let options = SimpleFileOptions::default();
+    for (name, content) in files {
+        zip.start_file(name, options)?;
+        zip.write_all(&content)?;
+    }
+    for (name, file_path) in disk_files {
+        match std::fs::File::open(&file_path) {
+            Ok(mut file) => {
+                zip.start_file(name, options)?;
+                std::io::copy(&mut file, &mut zip)?;
+            }
+            Err(e) => tracing::warn!("导出时读取文件 {} 失败: {}", file_path, e),
+        }
+    }
+    let mut archive = zip.finish()?;
+    archive.rewind()?;
+    Ok(archive)
+}
+
+/// 首次运行时创建管理员账号
+#[utoipa::path(
+    post,
+    path = "/api/auth/admin/setup",
+    tag = "auth",
+    request_body = AdminSetupRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<AdminSetupResponse>)),
+)]
+async fn admin_setup(
     State(pool): State<Pool<Sqlite>>,
     State(config): State<Config>,
+    State(settings): State<SettingsCache>,
+    ip: ClientIp,
     Json(req): Json<AdminSetupRequest>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
-    // 查找管理员
-    let admin = sqlx::query_as::<_, Admin>("SELECT * FROM admin LIMIT 1")
-        .fetch_optional(&pool)
-        .await?
-        .ok_or_else(|| AppError::Auth("管理员未设置".to_string()))?;
+) -> Result<Json<ApiResponse<AdminSetupResponse>>, AppError> {
+    let username = req.username.unwrap_or_else(|| "admin".to_string());
+    validate_username(&username)?;
 
-    // 验证密码
-    if !verify_password(&req.password, &admin.password_hash)? {
-        return Err(AppError::Auth("管理员密码错误".to_string()));
+    // 验证密码策略
+    password_policy::enforce(&pool, &config, &req.password).await?;
+
+    // 提前检查是否已有管理员，避免无谓的密码哈希，最终以创建时的条件插入为准
+    let admin_exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE role = 'admin')")
+            .fetch_one(&pool)
+            .await?;
+
+    if admin_exists {
+        return Err(AppError::Validation(
+            ErrorCode::AdminAlreadySetup,
+            "管理员已设置，无法重复设置".to_string(),
+        ));
     }
 
-    // 生成JWT令牌
-    let claims = Claims::new_admin(admin.id, &config);
+    // 检查用户名是否已存在
+    let exists =
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE username = ?)")
+            .bind(&username)
+            .fetch_one(&pool)
+            .await?;
+
+    if exists {
+        return Err(
+            AppError::Validation(ErrorCode::UsernameTaken, "用户名已存在".to_string())
+                .for_field("username"),
+        );
+    }
+
+    // 哈希密码
+    let password_hash = hash_password(&req.password)?;
+
+    // 创建管理员账号，并初始化系统设置
+    let mut tx = pool.begin().await?;
+    let admin_id = db::create_first_admin(&mut tx, &username, &password_hash)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(
+                ErrorCode::AdminAlreadySetup,
+                "管理员已设置，无法重复设置".to_string(),
+            )
+        })?;
+    sqlx::query!("INSERT OR IGNORE INTO settings (id, registration_mode) VALUES (1, 'invite')")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    settings.refresh(&pool).await?;
+
+    audit::record(
+        &pool,
+        Actor::Admin(admin_id),
+        "admin.setup",
+        Some(("user", admin_id)),
+        Some(serde_json::json!({ "username": username })),
+        &ip,
+    )
+    .await;
+
+    // 管理员使用普通用户令牌，权限由角色决定
+    let claims = Claims::new_user(admin_id, &config);
     let token = create_token(&claims, &config)?;
 
     // 返回管理员令牌
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "admin_token": token
-    }))))
+    Ok(Json(ApiResponse::success(AdminSetupResponse {
+        user_id: admin_id,
+        username,
+        admin_token: token,
+    })))
 }
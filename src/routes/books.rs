@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::sync::Arc;
 
 use axum::{
     extract::{multipart::Multipart, Path, Query, State},
@@ -8,41 +8,51 @@ use axum::{
 use rand::prelude::IndexedRandom;
 use serde::Deserialize;
 use sqlx::{Pool, Sqlite};
-use tokio::{fs, io::AsyncWriteExt};
+use utoipa::{IntoParams, OpenApi};
 
 use crate::{
-    auth::AuthUser,
+    audit::{self, Actor, ClientIp},
+    auth::{AuthUser, Permission},
+    cache::ContentCache,
     config::Config,
-    error::{ApiResponse, AppError},
+    error::{ApiResponse, AppError, ErrorCode, MessageResponse},
+    i18n::Message,
+    metrics::Metrics,
     models::{
-        Book, BookContentResponse, BookDetailResponse, BookListItem, ChapterResponse,
-        PublicBookListItem, UpdateBookRequest, UploadBookResponse,
+        Book, BookContentResponse, BookDetailResponse, BookListItem, BookListResponse,
+        ChapterPositionResponse, ChapterResponse, PublicBookListItem, PublicBookListResponse,
+        RandomPublicBooksResponse, UpdateBookRequest, UploadBookForm, UploadBookResponse,
     },
-    utils::{extract_chapters, generate_uuid},
+    storage,
+    utils::extract_chapters,
 };
 
 // 分页查询参数
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationParams {
     pub page: Option<u32>,
     pub limit: Option<u32>,
 }
 
 // 获取内容查询参数
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ContentParams {
     pub position: i64,
     pub length: Option<i64>,
 }
 
 // 跳转章节查询参数
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct JumpToChapterParams {
     pub chapter_id: i64,
 }
 
 // 随机公开书籍查询参数
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RandomPublicParams {
     pub count: Option<i64>,
 }
@@ -62,13 +72,41 @@ pub fn routes() -> Router<crate::app::AppState> {
         .route("/random_public", get(get_random_public_books))
 }
 
-// 上传书籍
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(
+    upload_book,
+    list_books,
+    get_book_detail,
+    update_book,
+    delete_book,
+    get_book_content,
+    jump_to_chapter,
+    list_public_books,
+    get_random_public_books,
+))]
+pub struct ApiDoc;
+
+/// 上传书籍
+#[utoipa::path(
+    post,
+    path = "/api/books/upload",
+    tag = "books",
+    request_body(content = UploadBookForm, content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = ApiResponse<UploadBookResponse>)),
+    security(("bearer" = [])),
+)]
 async fn upload_book(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     State(config): State<Config>,
+    State(metrics): State<Arc<Metrics>>,
+    ip: ClientIp,
     mut multipart: Multipart,
 ) -> Result<Json<ApiResponse<UploadBookResponse>>, AppError> {
+    // 只读角色不能上传
+    auth.require(Permission::UploadBooks, "当前角色没有上传权限")?;
+
     // 解析multipart表单数据
     let mut title = None;
     let mut author = None;
@@ -121,13 +159,16 @@ async fn upload_book(
     }
 
     // 验证必要字段
-    let title = title.ok_or_else(|| AppError::Validation("标题不能为空".to_string()))?;
+    let title = title.ok_or_else(|| AppError::invalid_field("title", "标题不能为空"))?;
     let file_content =
-        file_content.ok_or_else(|| AppError::Validation("文件不能为空".to_string()))?;
+        file_content.ok_or_else(|| AppError::invalid_field("file", "文件不能为空"))?;
 
     // 验证文件大小
     if file_content.len() > 10 * 1024 * 1024 {
-        return Err(AppError::Validation("文件大小不能超过10MB".to_string()));
+        return Err(AppError::Validation(
+            ErrorCode::FileTooLarge,
+            "文件大小不能超过10MB".to_string(),
+        ));
     }
 
     // 验证文件格式
@@ -136,70 +177,51 @@ async fn upload_book(
         .to_lowercase()
         .ends_with(".txt")
     {
-        return Err(AppError::Validation("只支持TXT格式的书籍".to_string()));
+        return Err(AppError::Validation(
+            ErrorCode::UnsupportedFileType,
+            "只支持TXT格式的书籍".to_string(),
+        ));
     }
 
     // 将文件内容转换为UTF-8文本
-    let content = String::from_utf8(file_content.to_vec())
-        .map_err(|_| AppError::Validation("文件编码不是有效的UTF-8".to_string()))?;
+    let content = String::from_utf8(file_content.to_vec()).map_err(|_| {
+        AppError::Validation(ErrorCode::BadRequest, "文件编码不是有效的UTF-8".to_string())
+    })?;
 
     // 提取章节
     let chapters = extract_chapters(&content);
 
-    // 生成唯一文件名
-    let file_id = generate_uuid();
-    let file_path = PathBuf::from(&config.storage.book_dir).join(format!("{}.txt", file_id));
-    let file_path_string = file_path.to_string_lossy();
-
     // 保存文件
-    let mut file = fs::File::create(&file_path).await.map_err(AppError::Io)?;
-    file.write_all(content.as_bytes())
-        .await
-        .map_err(AppError::Io)?;
-
-    // 将书籍信息保存到数据库
-    let book_id = sqlx::query!(
-        "INSERT INTO books (user_id, title, author, file_path, is_public) VALUES (?, ?, ?, ?, ?)",
-        auth.user_id,
-        title,
-        author,
-        file_path_string,
-        is_public
-    )
-    .execute(&pool)
-    .await?
-    .last_insert_rowid();
-
-    // 保存章节信息
-    let mut chapter_responses = Vec::new();
-    for (chapter_title, position) in chapters {
-        let position_temp = position as i64;
-        let chapter_id = sqlx::query!(
-            "INSERT INTO chapters (book_id, title, position) VALUES (?, ?, ?)",
-            book_id,
-            chapter_title,
-            position_temp
+    let book_file = storage::write_book_file(&config.storage.book_dir, &content).await?;
+    let file_size = content.len() as i64;
+
+    // 超出配额、写入数据库失败或请求被中止时 book_file 被丢弃，已保存的文件随之删除
+    let new_book = storage::NewBook {
+        user_id: auth.user_id,
+        title: &title,
+        author: author.as_deref(),
+        file_path: book_file.path(),
+        file_size,
+        is_public,
+        enforce_quota: true,
+    };
+    let (book_id, chapter_responses) = storage::save_book(&pool, &new_book, chapters).await?;
+    book_file.keep();
+    metrics.record_upload(file_size as u64);
+
+    // 上传时直接公开也视为一次发布
+    if is_public {
+        audit::record(
+            &pool,
+            Actor::User(auth.user_id),
+            "book.publish",
+            Some(("book", book_id)),
+            None,
+            &ip,
         )
-        .execute(&pool)
-        .await?
-        .last_insert_rowid();
-
-        chapter_responses.push(ChapterResponse {
-            chapter_id,
-            title: chapter_title,
-            position: position as i64,
-        });
+        .await;
     }
 
-    // 创建初始阅读进度
-    sqlx::query!(
-        "INSERT INTO reading_progress (user_id, book_id) VALUES (?, ?)",
-        auth.user_id,
-        book_id
-    )
-    .execute(&pool)
-    .await?;
-
     // 返回响应
     let response = UploadBookResponse {
         book_id,
@@ -211,12 +233,22 @@ async fn upload_book(
     Ok(Json(ApiResponse::success(response)))
 }
 
-// 获取用户书籍列表
+/// 获取用户书籍列表
+#[utoipa::path(
+    get,
+    path = "/api/books",
+    tag = "books",
+    params(
+        PaginationParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<BookListResponse>)),
+    security(("bearer" = [])),
+)]
 async fn list_books(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<BookListResponse>>, AppError> {
     // 解析分页参数
     let page = params.page.unwrap_or(1);
     let limit = params.limit.unwrap_or(10);
@@ -262,13 +294,23 @@ async fn list_books(
         })
         .collect();
 
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "total": total,
-        "books": book_list
-    }))))
+    Ok(Json(ApiResponse::success(BookListResponse {
+        total,
+        books: book_list,
+    })))
 }
 
-// 获取书籍详情
+/// 获取书籍详情
+#[utoipa::path(
+    get,
+    path = "/api/books/{book_id}",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<BookDetailResponse>)),
+    security(("bearer" = [])),
+)]
 async fn get_book_detail(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
@@ -279,13 +321,16 @@ async fn get_book_detail(
         .bind(book_id)
         .fetch_optional(&pool)
         .await?
-        .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+        .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 检查权限
     if book.user_id != auth.user_id {
         // 如果不是书籍所有者，检查书籍是否公开
         if !book.is_public {
-            return Err(AppError::Forbidden("无权访问该书籍".to_string()));
+            return Err(AppError::Forbidden(
+                ErrorCode::BookForbidden,
+                "无权访问该书籍".to_string(),
+            ));
         }
     }
 
@@ -350,22 +395,37 @@ async fn get_book_detail(
     Ok(Json(ApiResponse::success(response)))
 }
 
-// 更新书籍信息
+/// 更新书籍信息
+#[utoipa::path(
+    put,
+    path = "/api/books/{book_id}",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    request_body = UpdateBookRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
 async fn update_book(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
+    ip: ClientIp,
     Path(book_id): Path<i64>,
     Json(req): Json<UpdateBookRequest>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
     // 检查书籍是否存在并属于当前用户
-    let book = sqlx::query!("SELECT user_id FROM books WHERE id = ?", book_id)
+    let book = sqlx::query!("SELECT user_id, is_public FROM books WHERE id = ?", book_id)
         .fetch_optional(&pool)
         .await?
-        .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+        .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 验证权限
     if book.user_id != auth.user_id {
-        return Err(AppError::Forbidden("无权修改该书籍".to_string()));
+        return Err(AppError::Forbidden(
+            ErrorCode::BookForbidden,
+            "无权修改该书籍".to_string(),
+        ));
     }
 
     // 构建更新SQL
@@ -389,7 +449,7 @@ async fn update_book(
 
     // 如果没有需要更新的字段，直接返回成功
     if updates.is_empty() {
-        return Ok(Json(ApiResponse::<()>::message("无更新内容")));
+        return Ok(Json(ApiResponse::<()>::message(Message::NothingToUpdate)));
     }
 
     // 构建SQL语句
@@ -404,52 +464,95 @@ async fn update_book(
 
     query.execute(&pool).await?;
 
-    Ok(Json(ApiResponse::<()>::message("更新成功")))
+    // 记录公开状态的变化
+    if let Some(is_public) = req
+        .is_public
+        .filter(|is_public| *is_public != book.is_public)
+    {
+        let action = if is_public {
+            "book.publish"
+        } else {
+            "book.unpublish"
+        };
+        audit::record(
+            &pool,
+            Actor::User(auth.user_id),
+            action,
+            Some(("book", book_id)),
+            None,
+            &ip,
+        )
+        .await;
+    }
+
+    Ok(Json(ApiResponse::<()>::message(Message::Updated)))
 }
 
-// 删除书籍
+/// 删除书籍
+#[utoipa::path(
+    delete,
+    path = "/api/books/{book_id}",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+    ),
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
 async fn delete_book(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
-    State(_config): State<Config>,
+    ip: ClientIp,
     Path(book_id): Path<i64>,
 ) -> Result<Json<ApiResponse<()>>, AppError> {
-    // 开始事务
-    let mut tx = pool.begin().await?;
-
     // 检查书籍是否存在并属于当前用户
-    let book = sqlx::query!("SELECT user_id, file_path FROM books WHERE id = ?", book_id)
-        .fetch_optional(&mut *tx)
-        .await?
-        .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+    let book = sqlx::query!(
+        "SELECT user_id, title, file_path FROM books WHERE id = ?",
+        book_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 验证权限
     if book.user_id != auth.user_id {
-        return Err(AppError::Forbidden("无权删除该书籍".to_string()));
+        return Err(AppError::Forbidden(
+            ErrorCode::BookForbidden,
+            "无权删除该书籍".to_string(),
+        ));
     }
 
-    // 删除书籍文件
-    let file_path = book.file_path;
-    if PathBuf::from(&file_path).exists() {
-        fs::remove_file(&file_path).await.map_err(AppError::Io)?;
-    }
-
-    // 删除数据库中的书籍记录
-    // 注意：由于设置了外键约束，章节和阅读进度会自动删除
-    sqlx::query!("DELETE FROM books WHERE id = ?", book_id)
-        .execute(&mut *tx)
-        .await?;
-
-    // 提交事务
-    tx.commit().await?;
+    // 删除书籍记录与文件
+    storage::delete_book(&pool, book_id, &book.file_path).await?;
+    audit::record(
+        &pool,
+        Actor::User(auth.user_id),
+        "book.delete",
+        Some(("book", book_id)),
+        Some(serde_json::json!({ "title": book.title })),
+        &ip,
+    )
+    .await;
 
-    Ok(Json(ApiResponse::<()>::message("删除成功")))
+    Ok(Json(ApiResponse::<()>::message(Message::Deleted)))
 }
 
-// 获取书籍内容
+/// 获取书籍内容
+#[utoipa::path(
+    get,
+    path = "/api/books/{book_id}/content",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+        ContentParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<BookContentResponse>)),
+    security(("bearer" = [])),
+)]
 async fn get_book_content(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
+    State(cache): State<Arc<ContentCache>>,
     Path(book_id): Path<i64>,
     Query(params): Query<ContentParams>,
 ) -> Result<Json<ApiResponse<BookContentResponse>>, AppError> {
@@ -460,20 +563,19 @@ async fn get_book_content(
     )
     .fetch_optional(&pool)
     .await?
-    .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 检查权限
     if book.user_id != auth.user_id && !book.is_public {
-        return Err(AppError::Forbidden("无权访问该书籍".to_string()));
+        return Err(AppError::Forbidden(
+            ErrorCode::BookForbidden,
+            "无权访问该书籍".to_string(),
+        ));
     }
 
-    // 获取文件内容
-    let content = fs::read_to_string(&book.file_path)
-        .await
-        .map_err(AppError::Io)?;
-
-    // 计算内容长度
-    let content_length = content.chars().count();
+    // 获取文件内容，优先从缓存读取
+    let content = cache.get(&book.file_path).await?;
+    let content_length = content.char_count;
 
     // 确保位置有效
     let position = params.position.max(0) as usize;
@@ -487,6 +589,7 @@ async fn get_book_content(
     // 提取内容
     let end_pos = (position + length).min(content_length);
     let content_slice: String = content
+        .text
         .chars()
         .skip(position)
         .take(end_pos - position)
@@ -501,22 +604,36 @@ async fn get_book_content(
     Ok(Json(ApiResponse::success(response)))
 }
 
-// 跳转到指定章节
+/// 跳转到指定章节
+#[utoipa::path(
+    get,
+    path = "/api/books/{book_id}/jump_to_chapter",
+    tag = "books",
+    params(
+        ("book_id" = i64, Path, description = "书籍ID"),
+        JumpToChapterParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<ChapterPositionResponse>)),
+    security(("bearer" = [])),
+)]
 async fn jump_to_chapter(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     Path(book_id): Path<i64>,
     Query(params): Query<JumpToChapterParams>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<ChapterPositionResponse>>, AppError> {
     // 查询书籍信息
     let book = sqlx::query!("SELECT user_id, is_public FROM books WHERE id = ?", book_id)
         .fetch_optional(&pool)
         .await?
-        .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+        .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 检查权限
     if book.user_id != auth.user_id && !book.is_public {
-        return Err(AppError::Forbidden("无权访问该书籍".to_string()));
+        return Err(AppError::Forbidden(
+            ErrorCode::BookForbidden,
+            "无权访问该书籍".to_string(),
+        ));
     }
 
     // 查询章节信息
@@ -527,20 +644,30 @@ async fn jump_to_chapter(
     )
     .fetch_optional(&pool)
     .await?
-    .ok_or_else(|| AppError::NotFound("章节不存在".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::NotFound, "章节不存在".to_string()))?;
 
     // 返回章节位置
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "position": chapter.position
-    }))))
+    Ok(Json(ApiResponse::success(ChapterPositionResponse {
+        position: chapter.position,
+    })))
 }
 
-// 获取公开书籍列表
+/// 获取公开书籍列表
+#[utoipa::path(
+    get,
+    path = "/api/books/public",
+    tag = "books",
+    params(
+        PaginationParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<PublicBookListResponse>)),
+    security(("bearer" = [])),
+)]
 async fn list_public_books(
     _auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<PublicBookListResponse>>, AppError> {
     // 解析分页参数
     let page = params.page.unwrap_or(1);
     let limit = params.limit.unwrap_or(10);
@@ -554,7 +681,8 @@ async fn list_public_books(
     // 获取公开书籍列表
     let books = sqlx::query!(
         r#"
-        SELECT b.id, b.title, b.author, b.created_at, u.username as owner_username
+        SELECT b.id, b.title, b.author, b.created_at, u.username as owner_username,
+               u.display_name as owner_display_name
         FROM books b
         JOIN users u ON b.user_id = u.id
         WHERE b.is_public = 1
@@ -575,29 +703,41 @@ async fn list_public_books(
             title: book.title,
             author: book.author,
             owner_username: book.owner_username,
+            owner_display_name: book.owner_display_name,
             created_at: book.created_at,
         })
         .collect();
 
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "total": total,
-        "books": book_list
-    }))))
+    Ok(Json(ApiResponse::success(PublicBookListResponse {
+        total,
+        books: book_list,
+    })))
 }
 
-// 随机获取公开书籍
+/// 随机获取公开书籍
+#[utoipa::path(
+    get,
+    path = "/api/books/random_public",
+    tag = "books",
+    params(
+        RandomPublicParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<RandomPublicBooksResponse>)),
+    security(("bearer" = [])),
+)]
 async fn get_random_public_books(
     _auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
     Query(params): Query<RandomPublicParams>,
-) -> Result<Json<ApiResponse<serde_json::Value>>, AppError> {
+) -> Result<Json<ApiResponse<RandomPublicBooksResponse>>, AppError> {
     // 确定要返回的书籍数量
     let count = params.count.unwrap_or(1).clamp(1, 10);
 
     // 获取所有公开书籍
     let books = sqlx::query!(
         r#"
-        SELECT b.id, b.title, b.author, b.created_at, u.username as owner_username
+        SELECT b.id, b.title, b.author, b.created_at, u.username as owner_username,
+               u.display_name as owner_display_name
         FROM books b
         JOIN users u ON b.user_id = u.id
         WHERE b.is_public = 1
@@ -615,11 +755,12 @@ async fn get_random_public_books(
             title: book.title.clone(),
             author: book.author.clone(),
             owner_username: book.owner_username.clone(),
+            owner_display_name: book.owner_display_name.clone(),
             created_at: book.created_at.clone(),
         })
         .collect();
 
-    Ok(Json(ApiResponse::success(serde_json::json!({
-        "books": selected_books
-    }))))
+    Ok(Json(ApiResponse::success(RandomPublicBooksResponse {
+        books: selected_books,
+    })))
 }
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Json, Router};
+use sqlx::{Pool, Sqlite};
+use tokio::fs;
+use utoipa::OpenApi;
+
+use crate::{
+    cache::ContentCache,
+    config::Config,
+    error::{ApiResponse, AppError, MessageResponse},
+    i18n::Message,
+    metrics::Metrics,
+    utils::generate_uuid,
+};
+
+// 健康检查与监控路由，不受维护模式影响
+pub fn routes() -> Router<crate::app::AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics))
+}
+
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(healthz, readyz, metrics,))]
+pub struct ApiDoc;
+
+/// 进程存活检查
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "成功", body = MessageResponse)),
+)]
+async fn healthz() -> Json<ApiResponse<()>> {
+    Json(ApiResponse::<()>::message(Message::Healthy))
+}
+
+/// 就绪检查：数据库可访问且书籍目录可写
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses((status = 200, description = "成功", body = MessageResponse)),
+)]
+async fn readyz(
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    sqlx::query("SELECT 1")
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::Unavailable(format!("数据库不可用: {}", e)))?;
+
+    let probe = config
+        .storage
+        .book_dir
+        .join(format!(".readyz-{}", generate_uuid()));
+    fs::write(&probe, b"")
+        .await
+        .map_err(|e| AppError::Unavailable(format!("书籍目录不可写: {}", e)))?;
+    let _ = fs::remove_file(&probe).await;
+
+    Ok(Json(ApiResponse::<()>::message(Message::Healthy)))
+}
+
+/// Prometheus 指标
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Prometheus 文本格式指标", content_type = "text/plain", body = String)),
+)]
+async fn metrics(
+    State(metrics): State<Arc<Metrics>>,
+    State(pool): State<Pool<Sqlite>>,
+    State(cache): State<Arc<ContentCache>>,
+) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics.render(&pool, &cache),
+    )
+}
@@ -1,4 +1,7 @@
 pub mod admin;
+pub mod announcements;
 pub mod auth;
 pub mod books;
+pub mod health;
+pub mod oidc;
 pub mod reading;
@@ -0,0 +1,572 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use utoipa::{IntoParams, OpenApi};
+
+use crate::{
+    audit::{self, Actor, ClientIp},
+    auth::{create_token, ensure_active, AuthUser, Claims},
+    config::{Config, OidcConfig},
+    db,
+    error::{ApiResponse, AppError, ErrorCode},
+    invite,
+    models::{AuthorizeUrlResponse, LoginResponse, OidcCallbackRequest},
+    routes::auth::check_registration,
+    utils::{generate_invite_code, generate_uuid, hash_password, validate_username},
+};
+
+// 登录状态有效期，单位为秒
+const LOGIN_STATE_TTL: i64 = 10 * 60;
+
+// 访问身份提供方的超时时间，避免响应缓慢时长时间占用请求
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// 发现文档与 JWKS 的缓存时间
+const DISCOVERY_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+// 发起登录查询参数
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthorizeParams {
+    pub invite_code: Option<String>,
+}
+
+// 身份提供方的发现文档
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+// 令牌端点响应
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+// OpenID Connect 路由
+pub fn routes() -> Router<crate::app::AppState> {
+    Router::new()
+        .route("/authorize", get(authorize))
+        .route("/link", post(link))
+        .route("/callback", post(callback))
+}
+
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(authorize, link, callback,))]
+pub struct ApiDoc;
+
+// 获取 OIDC 配置，未配置时返回错误
+fn oidc_config(config: &Config) -> Result<&OidcConfig, AppError> {
+    config.oidc.as_ref().ok_or_else(|| {
+        AppError::NotFound(
+            ErrorCode::NotFound,
+            "未启用 OpenID Connect 登录".to_string(),
+        )
+    })
+}
+
+// 访问身份提供方的客户端，所有请求共用连接池与超时设置
+// 发现文档与 JWKS 缓存 DISCOVERY_CACHE_TTL，避免每次登录都重新获取
+#[derive(Clone)]
+pub struct OidcClient {
+    http: reqwest::Client,
+    cache: Arc<Mutex<OidcCache>>,
+}
+
+#[derive(Default)]
+struct OidcCache {
+    metadata: Option<(Instant, Arc<ProviderMetadata>)>,
+    // 以 jwks_uri 为键，发现文档变化后不会使用旧地址的公钥
+    jwks: Option<(Instant, String, Arc<JwkSet>)>,
+}
+
+impl OidcClient {
+    pub fn new() -> Result<Self, reqwest::Error> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(OidcClient {
+            http,
+            cache: Arc::default(),
+        })
+    }
+
+    // 获取身份提供方的发现文档
+    async fn discover(&self, oidc: &OidcConfig) -> Result<Arc<ProviderMetadata>, AppError> {
+        if let Some((fetched_at, metadata)) = &self.lock().metadata {
+            if fetched_at.elapsed() < DISCOVERY_CACHE_TTL {
+                return Ok(metadata.clone());
+            }
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            oidc.issuer.trim_end_matches('/')
+        );
+        let metadata = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AppError::Internal(format!("获取 OIDC 发现文档失败: {}", e)))?
+            .json::<ProviderMetadata>()
+            .await
+            .map_err(|e| AppError::Internal(format!("解析 OIDC 发现文档失败: {}", e)))?;
+
+        // 发现文档中的 issuer 必须与配置一致，否则 ID 令牌会按伪造的签发者校验
+        if metadata.issuer.trim_end_matches('/') != oidc.issuer.trim_end_matches('/') {
+            return Err(AppError::Internal(format!(
+                "OIDC 发现文档中的 issuer {} 与配置的 {} 不一致",
+                metadata.issuer, oidc.issuer
+            )));
+        }
+
+        let metadata = Arc::new(metadata);
+        self.lock().metadata = Some((Instant::now(), metadata.clone()));
+        Ok(metadata)
+    }
+
+    // 获取签名公钥，refresh 为 true 时忽略缓存，用于身份提供方轮换密钥后重新获取
+    async fn jwks(&self, jwks_uri: &str, refresh: bool) -> Result<Arc<JwkSet>, AppError> {
+        if !refresh {
+            if let Some((fetched_at, uri, jwks)) = &self.lock().jwks {
+                if uri == jwks_uri && fetched_at.elapsed() < DISCOVERY_CACHE_TTL {
+                    return Ok(jwks.clone());
+                }
+            }
+        }
+
+        let jwks = self
+            .http
+            .get(jwks_uri)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AppError::Internal(format!("获取 JWKS 失败: {}", e)))?
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| AppError::Internal(format!("解析 JWKS 失败: {}", e)))?;
+
+        let jwks = Arc::new(jwks);
+        self.lock().jwks = Some((Instant::now(), jwks_uri.to_string(), jwks.clone()));
+        Ok(jwks)
+    }
+
+    // 缓存中只有可重新获取的数据，锁中毒时继续使用
+    fn lock(&self) -> std::sync::MutexGuard<'_, OidcCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// 生成授权地址，并保存本次登录的 state、nonce 与 PKCE 校验码
+async fn start_login(
+    pool: &Pool<Sqlite>,
+    client: &OidcClient,
+    oidc: &OidcConfig,
+    link_user_id: Option<i64>,
+    invite_code: Option<String>,
+) -> Result<String, AppError> {
+    let metadata = client.discover(oidc).await?;
+
+    let state = generate_uuid();
+    let nonce = generate_uuid();
+    let code_verifier = format!("{}{}", generate_uuid(), generate_uuid()).replace('-', "");
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+    // 清理过期的登录状态
+    let expired_before = (chrono::Utc::now() - chrono::Duration::seconds(LOGIN_STATE_TTL))
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    sqlx::query!(
+        "DELETE FROM oidc_login_states WHERE created_at < ?",
+        expired_before
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO oidc_login_states (state, code_verifier, nonce, link_user_id, invite_code)
+         VALUES (?, ?, ?, ?, ?)",
+        state,
+        code_verifier,
+        nonce,
+        link_user_id,
+        invite_code
+    )
+    .execute(pool)
+    .await?;
+
+    let mut url = Url::parse(&metadata.authorization_endpoint)
+        .map_err(|e| AppError::Internal(format!("无效的授权地址: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &oidc.client_id)
+        .append_pair("redirect_uri", &oidc.redirect_uri)
+        .append_pair("scope", &oidc.scopes.join(" "))
+        .append_pair("state", &state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &code_challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(url.to_string())
+}
+
+/// 发起 OIDC 登录
+#[utoipa::path(
+    get,
+    path = "/api/auth/oidc/authorize",
+    tag = "oidc",
+    params(
+        AuthorizeParams,
+    ),
+    responses((status = 200, description = "成功", body = ApiResponse<AuthorizeUrlResponse>)),
+)]
+async fn authorize(
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    State(client): State<OidcClient>,
+    Query(params): Query<AuthorizeParams>,
+) -> Result<Json<ApiResponse<AuthorizeUrlResponse>>, AppError> {
+    let oidc = oidc_config(&config)?;
+    let authorize_url = start_login(&pool, &client, oidc, None, params.invite_code).await?;
+
+    Ok(Json(ApiResponse::success(AuthorizeUrlResponse {
+        authorize_url,
+    })))
+}
+
+/// 为当前用户绑定 OIDC 身份
+#[utoipa::path(
+    post,
+    path = "/api/auth/oidc/link",
+    tag = "oidc",
+    responses((status = 200, description = "成功", body = ApiResponse<AuthorizeUrlResponse>)),
+    security(("bearer" = [])),
+)]
+async fn link(
+    auth: AuthUser,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    State(client): State<OidcClient>,
+) -> Result<Json<ApiResponse<AuthorizeUrlResponse>>, AppError> {
+    let oidc = oidc_config(&config)?;
+    let authorize_url = start_login(&pool, &client, oidc, Some(auth.user_id), None).await?;
+
+    Ok(Json(ApiResponse::success(AuthorizeUrlResponse {
+        authorize_url,
+    })))
+}
+
+/// 处理身份提供方回调
+#[utoipa::path(
+    post,
+    path = "/api/auth/oidc/callback",
+    tag = "oidc",
+    request_body = OidcCallbackRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<LoginResponse>)),
+)]
+async fn callback(
+    auth: Result<AuthUser, AppError>,
+    State(pool): State<Pool<Sqlite>>,
+    State(config): State<Config>,
+    State(client): State<OidcClient>,
+    ip: ClientIp,
+    Json(req): Json<OidcCallbackRequest>,
+) -> Result<Json<ApiResponse<LoginResponse>>, AppError> {
+    let oidc = oidc_config(&config)?;
+
+    // 取出并作废登录状态，删除与读取在同一条语句中完成，防止并发重放
+    let login_state = sqlx::query!(
+        "DELETE FROM oidc_login_states WHERE state = ?
+         RETURNING code_verifier, nonce, link_user_id, invite_code, created_at",
+        req.state
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::Auth("登录状态无效或已过期".to_string()))?;
+
+    // 绑定身份必须由发起绑定的用户本人完成回调，防止攻击者诱导他人把身份绑定到自己的账号
+    if let Some(link_user_id) = login_state.link_user_id {
+        if auth?.user_id != link_user_id {
+            return Err(AppError::Forbidden(
+                ErrorCode::Forbidden,
+                "绑定请求与当前登录用户不一致".to_string(),
+            ));
+        }
+    }
+
+    let created_at = login_state
+        .created_at
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .map_err(|e| AppError::Internal(format!("解析登录状态时间失败: {}", e)))?;
+    if (chrono::Utc::now() - created_at).num_seconds() > LOGIN_STATE_TTL {
+        return Err(AppError::Auth("登录状态无效或已过期".to_string()));
+    }
+
+    // 使用授权码换取 ID 令牌
+    let metadata = client.discover(oidc).await?;
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", req.code.as_str()),
+        ("redirect_uri", oidc.redirect_uri.as_str()),
+        ("client_id", oidc.client_id.as_str()),
+        ("code_verifier", login_state.code_verifier.as_str()),
+    ];
+    if let Some(client_secret) = &oidc.client_secret {
+        form.push(("client_secret", client_secret.as_str()));
+    }
+    let token = client
+        .http
+        .post(&metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| AppError::Auth(format!("OIDC 授权码兑换失败: {}", e)))?
+        .json::<TokenResponse>()
+        .await
+        .map_err(|e| AppError::Auth(format!("解析 OIDC 令牌响应失败: {}", e)))?;
+
+    // 校验 ID 令牌
+    let id_claims = verify_id_token(&client, &token.id_token, oidc, &metadata).await?;
+    if id_claims.get("nonce").and_then(|v| v.as_str()) != Some(login_state.nonce.as_str()) {
+        return Err(AppError::Auth("ID 令牌 nonce 不匹配".to_string()));
+    }
+    let subject = id_claims
+        .get("sub")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::Auth("ID 令牌缺少 sub".to_string()))?
+        .to_string();
+
+    // 查找已绑定的用户
+    let linked_user_id = sqlx::query_scalar::<_, i64>(
+        "SELECT user_id FROM user_identities WHERE issuer = ? AND subject = ?",
+    )
+    .bind(&metadata.issuer)
+    .bind(&subject)
+    .fetch_optional(&pool)
+    .await?;
+
+    let user_id = match (login_state.link_user_id, linked_user_id) {
+        // 绑定到当前登录用户
+        (Some(link_user_id), None) => {
+            insert_identity(
+                &mut *pool.acquire().await?,
+                link_user_id,
+                &metadata.issuer,
+                &subject,
+            )
+            .await?;
+            link_user_id
+        }
+        (Some(link_user_id), Some(user_id)) if link_user_id != user_id => {
+            return Err(AppError::Validation(
+                ErrorCode::BadRequest,
+                "该身份已绑定到其他用户".to_string(),
+            ));
+        }
+        // 已绑定的身份直接登录
+        (_, Some(user_id)) => user_id,
+        // 首次登录，按注册规则创建新用户
+        (None, None) => {
+            let status = check_registration(&pool, login_state.invite_code.as_deref()).await?;
+
+            let username = available_username(&pool, &id_claims, oidc, &subject).await?;
+            let password_hash = hash_password(&generate_uuid())?;
+            let mut tx = pool.begin().await?;
+            let user_id =
+                db::create_passwordless_user(&mut tx, &username, &password_hash, status).await?;
+            insert_identity(&mut tx, user_id, &metadata.issuer, &subject).await?;
+            if let Some(invite_code) = &login_state.invite_code {
+                invite::consume(&mut tx, invite_code, user_id).await?;
+            }
+            tx.commit().await?;
+
+            tracing::info!("通过 OIDC 创建用户: {} (id={})", username, user_id);
+            user_id
+        }
+    };
+
+    let (username, status) =
+        sqlx::query_as::<_, (String, String)>("SELECT username, status FROM users WHERE id = ?")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
+    ensure_active(&status)?;
+
+    audit::record(
+        &pool,
+        Actor::User(user_id),
+        "login.success",
+        Some(("user", user_id)),
+        Some(serde_json::json!({ "method": "oidc", "issuer": metadata.issuer })),
+        &ip,
+    )
+    .await;
+
+    // 生成JWT令牌
+    let claims = Claims::new_user(user_id, &config);
+    let token = create_token(&claims, &config)?;
+
+    // 返回用户信息和令牌
+    Ok(Json(ApiResponse::success(LoginResponse {
+        user_id,
+        username,
+        token,
+    })))
+}
+
+// 校验 ID 令牌签名、签发者与受众，返回其中的全部声明
+async fn verify_id_token(
+    client: &OidcClient,
+    id_token: &str,
+    oidc: &OidcConfig,
+    metadata: &ProviderMetadata,
+) -> Result<HashMap<String, serde_json::Value>, AppError> {
+    let header = decode_header(id_token)?;
+
+    // 只接受发现文档声明的签名算法（未声明时按规范为 RS256），不能由令牌自行指定
+    let mut algorithms: Vec<Algorithm> = metadata
+        .id_token_signing_alg_values_supported
+        .iter()
+        .filter_map(|alg| alg.parse().ok())
+        .collect();
+    if algorithms.is_empty() {
+        algorithms.push(Algorithm::RS256);
+    }
+    if !algorithms.contains(&header.alg) {
+        return Err(AppError::Auth(format!(
+            "不支持的 ID 令牌签名算法: {:?}",
+            header.alg
+        )));
+    }
+
+    let key = match header.alg {
+        // 对称签名使用客户端密钥
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            let secret = oidc.client_secret.as_deref().ok_or_else(|| {
+                AppError::Auth("ID 令牌使用对称签名，但未配置客户端密钥".to_string())
+            })?;
+            DecodingKey::from_secret(secret.as_bytes())
+        }
+        // 非对称签名从 JWKS 中查找公钥
+        _ => {
+            let jwks_uri = metadata
+                .jwks_uri
+                .as_deref()
+                .ok_or_else(|| AppError::Internal("OIDC 发现文档缺少 jwks_uri".to_string()))?;
+            let find_key = |jwks: &JwkSet| match &header.kid {
+                Some(kid) => jwks.find(kid).cloned(),
+                None => jwks.keys.first().cloned(),
+            };
+            // 缓存中找不到时重新获取一次，身份提供方可能已经轮换了密钥
+            let jwk = match find_key(&*client.jwks(jwks_uri, false).await?) {
+                Some(jwk) => Some(jwk),
+                None => find_key(&*client.jwks(jwks_uri, true).await?),
+            }
+            .ok_or_else(|| AppError::Auth("找不到 ID 令牌对应的公钥".to_string()))?;
+            // 公钥声明了算法时只接受该算法
+            if let Some(alg) = jwk
+                .common
+                .key_algorithm
+                .and_then(|alg| alg.to_string().parse::<Algorithm>().ok())
+            {
+                algorithms.retain(|allowed| *allowed == alg);
+            }
+            DecodingKey::from_jwk(&jwk)?
+        }
+    };
+
+    let mut validation = Validation::default();
+    validation.algorithms = algorithms;
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[&oidc.client_id]);
+
+    let data = decode::<HashMap<String, serde_json::Value>>(id_token, &key, &validation)?;
+    Ok(data.claims)
+}
+
+// 保存用户与第三方身份的绑定关系
+async fn insert_identity(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    issuer: &str,
+    subject: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO user_identities (user_id, issuer, subject) VALUES (?, ?, ?)",
+        user_id,
+        issuer,
+        subject
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// 根据 ID 令牌中的声明生成一个未被占用的用户名
+async fn available_username(
+    pool: &Pool<Sqlite>,
+    id_claims: &HashMap<String, serde_json::Value>,
+    oidc: &OidcConfig,
+    subject: &str,
+) -> Result<String, AppError> {
+    let preferred = id_claims
+        .get(&oidc.username_claim)
+        .and_then(|v| v.as_str())
+        .or_else(|| {
+            id_claims
+                .get("email")
+                .and_then(|v| v.as_str())
+                .and_then(|email| email.split('@').next())
+        })
+        .unwrap_or(subject);
+
+    // 用户名长度限制为 2-20 字节，截取前 15 字节（不拆开字符），为重名时的后缀留出空间
+    let preferred = preferred.trim();
+    let mut end = preferred.len().min(15);
+    while !preferred.is_char_boundary(end) {
+        end -= 1;
+    }
+    let base = match validate_username(&preferred[..end]) {
+        Ok(()) => preferred[..end].to_string(),
+        Err(_) => "user".to_string(),
+    };
+
+    let mut username = base.clone();
+    loop {
+        validate_username(&username)?;
+        let exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE username = ?)")
+                .bind(&username)
+                .fetch_one(pool)
+                .await?;
+        if !exists {
+            return Ok(username);
+        }
+        username = format!("{}_{}", base, &generate_invite_code()[..4].to_lowercase());
+    }
+}
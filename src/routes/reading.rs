@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use axum::{
     extract::State,
     routing::{get, post},
@@ -5,11 +7,15 @@ use axum::{
 };
 use chrono::{DateTime, SecondsFormat, Utc};
 use sqlx::{Pool, Sqlite};
+use utoipa::OpenApi;
 
 use crate::{
     auth::AuthUser,
-    error::{ApiResponse, AppError},
+    error::{ApiResponse, AppError, ErrorCode, MessageResponse},
+    i18n::Message,
+    metrics::Metrics,
     models::{HeartbeatRequest, HeartbeatResponse, ReadingSettings, UpdateReadingSettingsRequest},
+    utils::local_date,
 };
 
 // 阅读路由
@@ -22,7 +28,19 @@ pub fn routes() -> Router<crate::app::AppState> {
         .route("/heartbeat", post(process_heartbeat))
 }
 
-// 获取阅读设置
+// 接口文档
+#[derive(OpenApi)]
+#[openapi(paths(get_reading_settings, update_reading_settings, process_heartbeat,))]
+pub struct ApiDoc;
+
+/// 获取阅读设置
+#[utoipa::path(
+    get,
+    path = "/api/reading/settings",
+    tag = "reading",
+    responses((status = 200, description = "成功", body = ApiResponse<ReadingSettings>)),
+    security(("bearer" = [])),
+)]
 async fn get_reading_settings(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
@@ -65,7 +83,15 @@ async fn get_reading_settings(
     Ok(Json(ApiResponse::success(settings)))
 }
 
-// 更新阅读设置
+/// 更新阅读设置
+#[utoipa::path(
+    put,
+    path = "/api/reading/settings",
+    tag = "reading",
+    request_body = UpdateReadingSettingsRequest,
+    responses((status = 200, description = "成功", body = MessageResponse)),
+    security(("bearer" = [])),
+)]
 async fn update_reading_settings(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
@@ -146,7 +172,7 @@ async fn update_reading_settings(
 
     // 如果没有需要更新的字段，直接返回成功
     if sql_parts.is_empty() {
-        return Ok(Json(ApiResponse::<()>::message("无更新内容")));
+        return Ok(Json(ApiResponse::<()>::message(Message::NothingToUpdate)));
     }
 
     // 构建SQL语句
@@ -164,13 +190,22 @@ async fn update_reading_settings(
 
     query.execute(&pool).await?;
 
-    Ok(Json(ApiResponse::<()>::message("更新成功")))
+    Ok(Json(ApiResponse::<()>::message(Message::Updated)))
 }
 
-// 处理心跳包
+/// 处理心跳包
+#[utoipa::path(
+    post,
+    path = "/api/reading/heartbeat",
+    tag = "reading",
+    request_body = HeartbeatRequest,
+    responses((status = 200, description = "成功", body = ApiResponse<HeartbeatResponse>)),
+    security(("bearer" = [])),
+)]
 async fn process_heartbeat(
     auth: AuthUser,
     State(pool): State<Pool<Sqlite>>,
+    State(metrics): State<Arc<Metrics>>,
     Json(req): Json<HeartbeatRequest>,
 ) -> Result<Json<ApiResponse<HeartbeatResponse>>, AppError> {
     // 检查书籍是否存在
@@ -180,12 +215,16 @@ async fn process_heartbeat(
     )
     .fetch_optional(&pool)
     .await?
-    .ok_or_else(|| AppError::NotFound("书籍不存在".to_string()))?;
+    .ok_or_else(|| AppError::NotFound(ErrorCode::BookNotFound, "书籍不存在".to_string()))?;
 
     // 检查权限
     if book.user_id != auth.user_id && !book.is_public {
-        return Err(AppError::Forbidden("无权访问该书籍".to_string()));
+        return Err(AppError::Forbidden(
+            ErrorCode::BookForbidden,
+            "无权访问该书籍".to_string(),
+        ));
     }
+    metrics.record_heartbeat(auth.user_id);
 
     // 获取当前进度
     let progress = sqlx::query!(
@@ -281,6 +320,22 @@ async fn process_heartbeat(
         )
         .execute(&pool)
         .await?;
+
+        // 按用户时区累加当日阅读时长
+        let timezone = sqlx::query_scalar!("SELECT timezone FROM users WHERE id = ?", auth.user_id)
+            .fetch_one(&pool)
+            .await?;
+        let today = local_date(now, &timezone);
+        sqlx::query!(
+            "INSERT INTO reading_daily_stats (user_id, date, reading_time) VALUES (?, ?, ?)
+             ON CONFLICT (user_id, date) DO UPDATE
+             SET reading_time = reading_time + excluded.reading_time",
+            auth.user_id,
+            today,
+            reading_time_increment
+        )
+        .execute(&pool)
+        .await?;
     }
 
     // 返回同步状态和最新阅读时间
@@ -0,0 +1,267 @@
+use std::{future::Future, net::SocketAddr, path::Path, time::Duration};
+
+use anyhow::{Context, Result};
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use tokio::{net::TcpListener, sync::watch};
+
+use crate::config::{ServerConfig, TlsConfig};
+
+// 按配置启动所有监听：TCP（可选 TLS）以及可选的 Unix 域套接字
+// 收到 SIGINT/SIGTERM 后停止接受新连接，等待进行中的请求完成后返回
+pub async fn serve(app: Router, config: &ServerConfig) -> Result<()> {
+    serve_with_shutdown(app, config, shutdown_signal()).await
+}
+
+// 与 serve 相同，但由调用方提供关闭信号
+pub async fn serve_with_shutdown(
+    app: Router,
+    config: &ServerConfig,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let addr = resolve_addr(config).await?;
+    let drain_timeout = Duration::from_secs(config.shutdown_timeout);
+
+    // 所有监听共用同一个关闭信号
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        signal.await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    let tcp = async {
+        match &config.tls {
+            Some(tls) => {
+                serve_tls(app.clone(), addr, tls, shutdown_rx.clone(), drain_timeout).await
+            }
+            None => serve_tcp(app.clone(), addr, shutdown_rx.clone()).await,
+        }
+    };
+    let unix = async {
+        match &config.unix_socket {
+            Some(path) => {
+                serve_unix(
+                    app.clone(),
+                    path,
+                    &config.unix_socket_mode,
+                    shutdown_rx.clone(),
+                )
+                .await
+            }
+            None => Ok(()),
+        }
+    };
+    let drain_deadline = async {
+        shutdown_requested(shutdown_rx.clone()).await;
+        tokio::time::sleep(drain_timeout).await;
+    };
+
+    // 超过等待时间仍未完成的请求被直接中止，未提交的事务会回滚
+    tokio::select! {
+        result = async { tokio::try_join!(tcp, unix) } => {
+            result?;
+            tracing::info!("进行中的请求已全部完成");
+        }
+        _ = drain_deadline => {
+            tracing::warn!(
+                "等待进行中的请求超时（{} 秒），强制关闭",
+                config.shutdown_timeout
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// 等待 SIGINT（Ctrl+C）或 SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("无法监听 SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("无法监听 SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("收到关闭信号，停止接受新连接并等待进行中的请求完成");
+}
+
+// 等待关闭信号被触发
+async fn shutdown_requested(mut shutdown_rx: watch::Receiver<bool>) {
+    // 发送端只会在发出信号后才被丢弃，出错时同样视为需要关闭
+    let _ = shutdown_rx.wait_for(|&shutdown| shutdown).await;
+}
+
+// 解析监听地址，支持 IPv4、IPv6 与主机名
+pub(crate) async fn resolve_addr(config: &ServerConfig) -> Result<SocketAddr> {
+    // IPv6 地址可以写成 [::1] 的形式
+    let host = config.host.trim_start_matches('[').trim_end_matches(']');
+    tokio::net::lookup_host((host, config.port))
+        .await
+        .with_context(|| format!("无法解析监听地址 {}", config.host))?
+        .next()
+        .with_context(|| format!("无法解析监听地址 {}", config.host))
+}
+
+async fn serve_tcp(
+    app: Router,
+    addr: SocketAddr,
+    shutdown_rx: watch::Receiver<bool>,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("服务器启动在 http://{}", listener.local_addr()?);
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_requested(shutdown_rx))
+    .await?;
+
+    Ok(())
+}
+
+async fn serve_tls(
+    app: Router,
+    addr: SocketAddr,
+    tls: &TlsConfig,
+    shutdown_rx: watch::Receiver<bool>,
+    drain_timeout: Duration,
+) -> Result<()> {
+    // 进程中只需安装一次，重复安装时忽略
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let rustls_config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+        .await
+        .context("加载 TLS 证书失败")?;
+    spawn_tls_reloader(rustls_config.clone(), tls.clone());
+
+    // axum-server 通过 Handle 停止监听，并在超时后关闭剩余连接
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_requested(shutdown_rx).await;
+        shutdown_handle.graceful_shutdown(Some(drain_timeout));
+    });
+
+    tracing::info!("服务器启动在 https://{}", addr);
+    axum_server::bind_rustls(addr, rustls_config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+
+    Ok(())
+}
+
+// 收到 SIGHUP 时重新加载证书，便于证书续期后无需重启
+#[cfg(unix)]
+fn spawn_tls_reloader(rustls_config: RustlsConfig, tls: TlsConfig) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::error!("无法监听 SIGHUP: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            match rustls_config
+                .reload_from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+            {
+                Ok(()) => tracing::info!("已重新加载 TLS 证书"),
+                Err(e) => tracing::error!("重新加载 TLS 证书失败，继续使用原证书: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn spawn_tls_reloader(_rustls_config: RustlsConfig, _tls: TlsConfig) {}
+
+// 经 Unix 域套接字建立的连接在请求扩展中带有此标记，这类连接没有对端地址
+// 是否信任其中的代理请求头由 proxy_auth.trust_unix_socket 决定
+#[derive(Debug, Clone, Copy)]
+pub struct UnixSocketConnection;
+
+#[cfg(unix)]
+async fn serve_unix(
+    app: Router,
+    path: &Path,
+    mode: &str,
+    shutdown_rx: watch::Receiver<bool>,
+) -> Result<()> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    use axum::Extension;
+    use tokio::fs;
+
+    let mode = u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .with_context(|| format!("无效的套接字权限 {}", mode))?;
+
+    // 删除上次运行遗留的套接字文件，路径上是其他文件时拒绝启动，避免误删
+    if let Ok(metadata) = fs::symlink_metadata(path).await {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} 已存在且不是套接字文件", path.display());
+        }
+        fs::remove_file(path)
+            .await
+            .with_context(|| format!("无法删除旧的套接字文件 {}", path.display()))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("无法监听 Unix 套接字 {}", path.display()))?;
+    fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .await
+        .with_context(|| format!("无法设置套接字 {} 的权限", path.display()))?;
+    tracing::info!(
+        "服务器监听 Unix 套接字 {}（权限 {:o}）",
+        path.display(),
+        mode
+    );
+
+    let app = app.layer(Extension(UnixSocketConnection));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_requested(shutdown_rx))
+        .await?;
+
+    // 正常退出时删除套接字文件
+    if let Err(e) = fs::remove_file(path).await {
+        tracing::warn!("删除套接字文件 {} 失败: {}", path.display(), e);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn serve_unix(
+    _app: Router,
+    path: &Path,
+    _mode: &str,
+    _shutdown_rx: watch::Receiver<bool>,
+) -> Result<()> {
+    anyhow::bail!("当前平台不支持 Unix 套接字: {}", path.display())
+}
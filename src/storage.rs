@@ -0,0 +1,396 @@
+use std::{collections::HashSet, ffi::OsStr, path::Path, time::Duration};
+
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    error::{AppError, ErrorCode},
+    models::{ChapterResponse, ConsistencyReport, InvalidChapter, MissingBookFile},
+    utils::{extract_chapters, generate_uuid},
+};
+
+// 删除书籍或头像文件，文件已不存在时忽略
+pub async fn remove_file_if_exists(file_path: &str) -> Result<(), AppError> {
+    if Path::new(file_path).exists() {
+        fs::remove_file(file_path).await.map_err(AppError::Io)?;
+    }
+
+    Ok(())
+}
+
+// 删除书籍记录及其文件
+// 章节与阅读进度由外键级联删除
+pub async fn delete_book(
+    pool: &Pool<Sqlite>,
+    book_id: i64,
+    file_path: &str,
+) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM books WHERE id = ?", book_id)
+        .execute(pool)
+        .await?;
+
+    remove_file_if_exists(file_path).await
+}
+
+// 删除用户及其上传的书籍与头像文件
+// 数据库中的书籍、章节、进度与设置由外键级联删除
+pub async fn delete_user(pool: &Pool<Sqlite>, user_id: i64) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    let mut file_paths =
+        sqlx::query_scalar::<_, String>("SELECT file_path FROM books WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+    // 头像文件
+    let avatar_path =
+        sqlx::query_scalar::<_, Option<String>>("SELECT avatar_path FROM users WHERE id = ?")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten();
+    file_paths.extend(avatar_path);
+
+    sqlx::query!("DELETE FROM users WHERE id = ?", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    // 数据库提交后再删除文件，删除失败只留下孤立文件，不会丢失书籍记录
+    for file_path in file_paths {
+        if let Err(e) = remove_file_if_exists(&file_path).await {
+            tracing::warn!("删除文件 {} 失败: {}", file_path, e);
+        }
+    }
+
+    Ok(())
+}
+
+// 以随机文件名将书籍内容写入书籍目录
+// 写入失败时删除不完整的文件
+pub async fn write_book_file(book_dir: &Path, content: &str) -> Result<PendingFile, AppError> {
+    write_new_file(book_dir, "txt", content.as_bytes()).await
+}
+
+// 以随机文件名将内容写入指定目录，书籍与头像共用
+// 写入失败时删除不完整的文件
+pub async fn write_new_file(
+    dir: &Path,
+    extension: &str,
+    content: &[u8],
+) -> Result<PendingFile, AppError> {
+    let pending = PendingFile {
+        path: Some(
+            dir.join(format!("{}.{}", generate_uuid(), extension))
+                .to_string_lossy()
+                .into_owned(),
+        ),
+    };
+
+    let mut file = fs::File::create(pending.path())
+        .await
+        .map_err(AppError::Io)?;
+    file.write_all(content).await.map_err(AppError::Io)?;
+    file.sync_all().await.map_err(AppError::Io)?;
+
+    Ok(pending)
+}
+
+// 已写入但尚未保存到数据库的书籍或头像文件
+// 调用 keep 之前被丢弃时删除文件，包括请求因服务关闭而被中止的情况
+pub struct PendingFile {
+    path: Option<String>,
+}
+
+impl PendingFile {
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or_default()
+    }
+
+    // 数据库记录已保存，保留文件
+    pub fn keep(mut self) {
+        self.path = None;
+    }
+}
+
+impl Drop for PendingFile {
+    fn drop(&mut self) {
+        let Some(path) = self.path.take() else {
+            return;
+        };
+        // Drop 中无法等待异步操作，使用同步删除
+        match std::fs::remove_file(&path) {
+            Ok(()) => tracing::info!("已删除未完成上传的文件 {}", path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("删除未完成上传的文件 {} 失败: {}", path, e),
+        }
+    }
+}
+
+// 检查新增一本书后是否超出用户的书籍数量或存储空间配额，上传与转移所有权时调用
+// 需在以 BEGIN IMMEDIATE 开始的事务中调用，检查与写入之间不会有其他写入，并发上传不会超出配额
+pub async fn check_quota(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    file_size: i64,
+) -> Result<(), AppError> {
+    let (storage_quota, book_quota) = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+        "SELECT storage_quota, book_quota FROM users WHERE id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let (book_count, storage_used) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(file_size), 0) FROM books WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if let Some(book_quota) = book_quota {
+        if book_count >= book_quota {
+            return Err(AppError::Forbidden(
+                ErrorCode::QuotaExceeded,
+                format!("已达到上传数量配额上限（{} 本）", book_quota),
+            ));
+        }
+    }
+
+    if let Some(storage_quota) = storage_quota {
+        if storage_used + file_size > storage_quota {
+            return Err(AppError::Forbidden(
+                ErrorCode::QuotaExceeded,
+                format!(
+                    "超出存储空间配额（已用 {} / {} 字节）",
+                    storage_used, storage_quota
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// 待保存的书籍信息
+pub struct NewBook<'a> {
+    pub user_id: i64,
+    pub title: &'a str,
+    pub author: Option<&'a str>,
+    pub file_path: &'a str,
+    pub file_size: i64,
+    pub is_public: bool,
+    // 是否检查用户配额，命令行导入不受配额限制
+    pub enforce_quota: bool,
+}
+
+// 在同一事务中检查配额并保存书籍、章节与初始阅读进度
+pub async fn save_book(
+    pool: &Pool<Sqlite>,
+    book: &NewBook<'_>,
+    chapters: Vec<(String, usize)>,
+) -> Result<(i64, Vec<ChapterResponse>), AppError> {
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    if book.enforce_quota {
+        check_quota(&mut tx, book.user_id, book.file_size).await?;
+    }
+
+    // 将书籍信息保存到数据库
+    let book_id = sqlx::query!(
+        "INSERT INTO books (user_id, title, author, file_path, file_size, is_public)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        book.user_id,
+        book.title,
+        book.author,
+        book.file_path,
+        book.file_size,
+        book.is_public
+    )
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    // 保存章节信息
+    let mut chapter_responses = Vec::new();
+    for (chapter_title, position) in chapters {
+        let position = position as i64;
+        let chapter_id = sqlx::query!(
+            "INSERT INTO chapters (book_id, title, position) VALUES (?, ?, ?)",
+            book_id,
+            chapter_title,
+            position
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        chapter_responses.push(ChapterResponse {
+            chapter_id,
+            title: chapter_title,
+            position,
+        });
+    }
+
+    // 创建初始阅读进度
+    sqlx::query!(
+        "INSERT INTO reading_progress (user_id, book_id) VALUES (?, ?)",
+        book.user_id,
+        book_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((book_id, chapter_responses))
+}
+
+// 重新解析书籍文件并更新章节目录，返回章节数
+// 用于章节识别规则更新后刷新已上传的书籍
+// 按顺序原地更新已有章节，保留章节ID，多出的章节插入，缺少的章节删除
+pub async fn reindex_book(
+    pool: &Pool<Sqlite>,
+    book_id: i64,
+    file_path: &str,
+) -> Result<usize, AppError> {
+    let content = fs::read_to_string(file_path).await.map_err(AppError::Io)?;
+    let chapters = extract_chapters(&content);
+
+    let mut tx = pool.begin().await?;
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM chapters WHERE book_id = ? ORDER BY position, id",
+    )
+    .bind(book_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    for (index, (chapter_title, position)) in chapters.iter().enumerate() {
+        let position = *position as i64;
+        match existing.get(index) {
+            Some(chapter_id) => {
+                sqlx::query!(
+                    "UPDATE chapters SET title = ?, position = ? WHERE id = ?",
+                    chapter_title,
+                    position,
+                    chapter_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    "INSERT INTO chapters (book_id, title, position) VALUES (?, ?, ?)",
+                    book_id,
+                    chapter_title,
+                    position
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+    }
+    for chapter_id in existing.iter().skip(chapters.len()) {
+        sqlx::query!("DELETE FROM chapters WHERE id = ?", chapter_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(chapters.len())
+}
+
+// 最近修改过的文件不视为孤立文件：上传时先写入文件再保存书籍记录
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+// 检查书籍目录与数据库是否一致
+// - 书籍目录中没有对应记录的孤立文件，按文件名比较，不受路径写法（如 ./books 与 books）影响
+// - 记录指向的文件已不存在
+// - 章节位置（行号）超出文件末尾
+// repair 为 true 时删除孤立文件、文件缺失的书籍记录与无效的章节记录
+pub async fn check_consistency(
+    pool: &Pool<Sqlite>,
+    book_dir: &Path,
+    repair: bool,
+) -> Result<ConsistencyReport, AppError> {
+    let books = sqlx::query_as::<_, (i64, String)>("SELECT id, file_path FROM books ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+    let known_files: HashSet<&OsStr> = books
+        .iter()
+        .filter_map(|(_, path)| Path::new(path).file_name())
+        .collect();
+
+    // 孤立文件
+    let mut orphan_files = Vec::new();
+    let mut entries = fs::read_dir(book_dir).await.map_err(AppError::Io)?;
+    while let Some(entry) = entries.next_entry().await.map_err(AppError::Io)? {
+        let metadata = entry.metadata().await.map_err(AppError::Io)?;
+        if !metadata.is_file() || known_files.contains(entry.file_name().as_os_str()) {
+            continue;
+        }
+        // 修改时间在未来的文件同样跳过
+        let is_recent = metadata.modified().is_ok_and(|modified| {
+            modified
+                .elapsed()
+                .map_or(true, |age| age < ORPHAN_GRACE_PERIOD)
+        });
+        if !is_recent {
+            orphan_files.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+    orphan_files.sort();
+
+    // 文件缺失的书籍与超出文件末尾的章节
+    let mut missing_files = Vec::new();
+    let mut invalid_chapters = Vec::new();
+    for (book_id, file_path) in &books {
+        let content = match fs::read_to_string(file_path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                missing_files.push(MissingBookFile {
+                    book_id: *book_id,
+                    file_path: file_path.clone(),
+                });
+                continue;
+            }
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        let line_count = content.lines().count() as i64;
+        let chapters = sqlx::query_as::<_, InvalidChapter>(
+            "SELECT id AS chapter_id, book_id, position, ? AS line_count
+             FROM chapters WHERE book_id = ? AND position >= ?
+             ORDER BY position",
+        )
+        .bind(line_count)
+        .bind(book_id)
+        .bind(line_count)
+        .fetch_all(pool)
+        .await?;
+        invalid_chapters.extend(chapters);
+    }
+
+    if repair {
+        for file_path in &orphan_files {
+            remove_file_if_exists(file_path).await?;
+        }
+        for missing in &missing_files {
+            sqlx::query!("DELETE FROM books WHERE id = ?", missing.book_id)
+                .execute(pool)
+                .await?;
+        }
+        for chapter in &invalid_chapters {
+            sqlx::query!("DELETE FROM chapters WHERE id = ?", chapter.chapter_id)
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    Ok(ConsistencyReport {
+        orphan_files,
+        missing_files,
+        invalid_chapters,
+        repaired: repair,
+    })
+}
@@ -1,33 +1,36 @@
 pub mod test_admin;
+pub mod test_cli;
+pub mod test_config;
+#[cfg(feature = "embed-frontend")]
+pub mod test_frontend;
+pub mod test_oidc;
 pub mod test_reading;
+pub mod test_server;
 pub mod test_user;
 
 use anyhow::Result;
 use axum::{
     body::Body,
-    http::{Method, Request},
+    http::{Method, Request, StatusCode},
     response::Response,
     Router,
 };
+use http_body_util::BodyExt;
 use sqlx::{Pool, Sqlite};
 use tower::ServiceExt;
 
-use crate::{
-    app::create_app,
-    config::Config,
-    db::create_test_pool,
-};
-
-// 测试工具函数
-async fn setup_test_app() -> Result<(axum::Router, Pool<Sqlite>)> {
-    // 创建内存数据库
-    let pool = create_test_pool().await?;
+use crate::{app::create_app, config::Config, db::create_test_pool};
 
-    // 创建一个测试配置
-    let config = Config {
+// 测试配置
+fn test_config() -> Config {
+    Config {
         server: crate::config::ServerConfig {
             host: "127.0.0.1".to_string(),
             port: 0,
+            unix_socket: None,
+            unix_socket_mode: "660".to_string(),
+            tls: None,
+            shutdown_timeout: 5,
         },
         db: crate::config::DbConfig {
             url: "sqlite::memory:".to_string(),
@@ -35,13 +38,31 @@ async fn setup_test_app() -> Result<(axum::Router, Pool<Sqlite>)> {
         },
         storage: crate::config::StorageConfig {
             book_dir: std::env::temp_dir().join("test_books"),
+            avatar_dir: std::env::temp_dir().join("test_avatars"),
+            startup_check: crate::config::StartupCheck::Off,
+            content_cache_mb: 16,
         },
         jwt: crate::config::JwtConfig {
             secret: "test_secret_key".to_string(),
             expiration: 3600,
-            admin_expiration: 3600,
         },
-    };
+        ..Default::default()
+    }
+}
+
+// 测试工具函数
+async fn setup_test_app() -> Result<(axum::Router, Pool<Sqlite>)> {
+    setup_test_app_with_config(test_config()).await
+}
+
+// 使用自定义配置创建测试应用
+async fn setup_test_app_with_config(config: Config) -> Result<(axum::Router, Pool<Sqlite>)> {
+    // 创建内存数据库
+    let pool = create_test_pool().await?;
+
+    // 确保书籍与头像目录存在
+    tokio::fs::create_dir_all(&config.storage.book_dir).await?;
+    tokio::fs::create_dir_all(&config.storage.avatar_dir).await?;
 
     // 创建应用
     let app = create_app(pool.clone(), config).await?;
@@ -84,7 +105,7 @@ where
     // Note: This assumes JSON for POST/PUT. You might want to make this more
     // flexible or let the caller set it if needed, especially if `body` isn't
     // always JSON.
-    if matches!(method, Method::POST | Method::PUT) {
+    if matches!(method, Method::POST | Method::PUT | Method::DELETE) {
         // Only add Content-Type if the body isn't explicitly empty? Might be tricky.
         // A common pattern is to require the caller to provide it if necessary,
         // or infer based on the type B if possible (advanced).
@@ -99,3 +120,41 @@ where
     // Send the request using Tower's oneshot
     app.clone().oneshot(req).await.unwrap()
 }
+
+/// 以 multipart 表单上传一本 TXT 书籍，返回原始响应
+pub async fn upload_test_book_request(
+    app: &Router,
+    token: &str,
+    title: &str,
+    content: &str,
+) -> Response<Body> {
+    const BOUNDARY: &str = "----reader-test-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\n{title}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"book.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\n{content}\r\n--{b}--\r\n",
+        b = BOUNDARY,
+    );
+
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/api/books/upload")
+        .header("Authorization", format!("Bearer {}", token))
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    app.clone().oneshot(req).await.unwrap()
+}
+
+/// 以 multipart 表单上传一本 TXT 书籍，返回书籍ID
+pub async fn upload_test_book(app: &Router, token: &str, title: &str, content: &str) -> i64 {
+    let response = upload_test_book_request(app, token, title, content).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    json["data"]["book_id"].as_i64().unwrap()
+}
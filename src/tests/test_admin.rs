@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use axum::{
     body::Body,
@@ -8,9 +10,14 @@ use http_body_util::BodyExt;
 use serial_test::serial;
 
 use super::{make_request, setup_test_app};
-use crate::models::{
-    AdminSetupRequest, CreateInviteCodeRequest, LoginRequest, ResetUserPasswordRequest,
-    UpdateSettingsRequest,
+use crate::{
+    cache::SettingsCache,
+    models::{
+        AdminSetupRequest, AnnouncementRequest, BatchCreateInviteCodesRequest,
+        CreateInviteCodeRequest, CreateUserRequest, LoginRequest, RegistrationMode,
+        ResetUserPasswordRequest, Role, TransferBookRequest, UpdateBookRequest,
+        UpdateSettingsRequest, UpdateUserQuotaRequest, UpdateUserRoleRequest,
+    },
 };
 
 /// 设置管理员，并返回响应
@@ -18,6 +25,7 @@ use crate::models::{
 #[allow(unused)]
 pub async fn setup_admin(app: &Router) -> Result<serde_json::Value> {
     let setup_body = serde_json::to_string(&AdminSetupRequest {
+        username: None,
         password: "admin123".to_string(),
     })?;
     let response = make_request(app, Method::POST, "/api/auth/admin/setup", setup_body, None).await;
@@ -56,7 +64,8 @@ async fn test_admin_setup_and_settings() -> Result<()> {
 
     // 测试更新系统设置
     let settings_body = serde_json::to_string(&UpdateSettingsRequest {
-        invite_code_required: true,
+        registration_mode: Some(RegistrationMode::Invite),
+        ..Default::default()
     })?;
 
     let response = make_request(
@@ -86,7 +95,7 @@ async fn test_admin_setup_and_settings() -> Result<()> {
     let json: serde_json::Value = serde_json::from_slice(&body)?;
 
     assert_eq!(json["code"], 0);
-    assert_eq!(json["data"]["invite_code_required"], true);
+    assert_eq!(json["data"]["registration_mode"], "invite");
 
     Ok(())
 }
@@ -103,6 +112,7 @@ async fn test_invite_code_management() -> Result<()> {
     let invite_code_body = serde_json::to_string(&CreateInviteCodeRequest {
         limit_times: 1,
         description: Some("测试邀请码".to_string()),
+        ..Default::default()
     })?;
 
     let response = make_request(
@@ -149,9 +159,10 @@ async fn test_user_management() -> Result<()> {
     let json = setup_admin(&app).await?;
     let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
 
-    // 设置邀请码为非必填
+    // 开放注册，邀请码可选
     let settings_body = serde_json::to_string(&UpdateSettingsRequest {
-        invite_code_required: false,
+        registration_mode: Some(RegistrationMode::Open),
+        ..Default::default()
     })?;
     let response = make_request(
         &app,
@@ -182,8 +193,14 @@ async fn test_user_management() -> Result<()> {
     dbg!(&json);
     assert_eq!(json["code"], 0);
     assert!(json["data"]["users"].is_array());
-    let user_id = json["data"]["users"][0]["user_id"].as_u64().unwrap();
-    assert_eq!(json["data"]["users"][0]["username"], "testuser");
+    let user = json["data"]["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|user| user["username"] == "testuser")
+        .unwrap();
+    assert_eq!(user["role"], "uploader");
+    let user_id = user["user_id"].as_u64().unwrap();
 
     // 测试重置用户密码
     let reset_password_body = serde_json::to_string(&ResetUserPasswordRequest {
@@ -217,3 +234,1816 @@ async fn test_user_management() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_password_policy() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    // 设置密码策略
+    let settings_body = serde_json::to_string(&UpdateSettingsRequest {
+        registration_mode: Some(RegistrationMode::Open),
+        password_min_length: Some(10),
+        password_require_digit: Some(true),
+        password_check_breached: Some(true),
+        ..Default::default()
+    })?;
+    let response = make_request(
+        &app,
+        Method::PUT,
+        "/api/admin/settings",
+        settings_body,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let register = |password: &str| {
+        serde_json::to_string(&CreateUserRequest {
+            username: "policyuser".to_string(),
+            password: password.to_string(),
+            invite_code: None,
+        })
+        .unwrap()
+    };
+
+    // 长度不足、缺少数字、出现在泄露列表中的密码都会被拒绝
+    for password in ["short1", "longpasswordonly", "password123"] {
+        let response = make_request(
+            &app,
+            Method::POST,
+            "/api/auth/register",
+            register(password),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await?.to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(json["code"], 1008, "{}", password);
+    }
+
+    // 符合策略的密码可以注册
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/register",
+        register("correct horse 42"),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let user_id = json["data"]["user_id"].as_i64().unwrap();
+
+    // 管理员重置密码同样受策略约束
+    let reset_password_body = serde_json::to_string(&ResetUserPasswordRequest {
+        new_password: "admin123".to_string(),
+    })?;
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/reset_password", user_id),
+        reset_password_body,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 公开的密码策略
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/password_policy",
+        Body::empty(),
+        None,
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["min_length"], 10);
+    assert_eq!(json["data"]["require_digit"], true);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admin_book_management() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    let book_id = super::upload_test_book(&app, &token, "管理测试", "第一章 开始\n正文").await;
+    let other_book_id = super::upload_test_book(&app, &token, "另一本书", "内容").await;
+    sqlx::query("UPDATE books SET is_public = 1 WHERE id = ?")
+        .bind(book_id)
+        .execute(&pool)
+        .await?;
+
+    // 按关键字与公开状态筛选
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/books?keyword=管理&is_public=true",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["total"], 1);
+    let book = &json["data"]["books"][0];
+    assert_eq!(book["book_id"], book_id);
+    assert_eq!(book["owner_username"], "testuser");
+    assert_eq!(book["chapter_count"], 1);
+    assert!(book["file_size"].as_i64().unwrap() > 0);
+
+    // 关键字中的 % 与 _ 按字面匹配
+    sqlx::query("UPDATE books SET author = '100%_作者' WHERE id = ?")
+        .bind(other_book_id)
+        .execute(&pool)
+        .await?;
+    for (keyword, expected) in [("%25", 1), ("%25_", 1), ("_", 1), ("0_%25", 0), ("\\", 0)] {
+        let response = make_request(
+            &app,
+            Method::GET,
+            &format!("/api/admin/books?keyword={}", keyword),
+            Body::empty(),
+            Some(&admin_token),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await?.to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(json["data"]["total"], expected, "keyword {}", keyword);
+    }
+
+    // 强制取消公开
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/books/{}/unpublish", book_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 编辑元数据
+    let response = make_request(
+        &app,
+        Method::PUT,
+        &format!("/api/admin/books/{}", book_id),
+        serde_json::to_string(&UpdateBookRequest {
+            author: Some("新作者".to_string()),
+            ..Default::default()
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let (author, is_public) =
+        sqlx::query_as::<_, (String, bool)>("SELECT author, is_public FROM books WHERE id = ?")
+            .bind(book_id)
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(author, "新作者");
+    assert!(!is_public);
+
+    // 转移所有权
+    let new_owner_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, password_hash, status, book_quota) \
+         VALUES ('newowner', 'x', 'disabled', 0) RETURNING id",
+    )
+    .fetch_one(&pool)
+    .await?;
+    let transfer_uri = format!("/api/admin/books/{}/transfer", book_id);
+    let transfer = || {
+        make_request(
+            &app,
+            Method::POST,
+            &transfer_uri,
+            serde_json::to_string(&TransferBookRequest { new_owner_id }).unwrap(),
+            Some(&admin_token),
+        )
+    };
+
+    // 新所有者被禁用或超出配额时拒绝转移
+    let response = transfer().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    sqlx::query("UPDATE users SET status = 'active' WHERE id = ?")
+        .bind(new_owner_id)
+        .execute(&pool)
+        .await?;
+    let response = transfer().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 2005);
+
+    sqlx::query("UPDATE users SET book_quota = NULL WHERE id = ?")
+        .bind(new_owner_id)
+        .execute(&pool)
+        .await?;
+    let response = transfer().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let owner_id = sqlx::query_scalar::<_, i64>("SELECT user_id FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(owner_id, new_owner_id);
+
+    // 删除书籍及文件
+    let file_path = sqlx::query_scalar::<_, String>("SELECT file_path FROM books WHERE id = ?")
+        .bind(other_book_id)
+        .fetch_one(&pool)
+        .await?;
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/admin/books/{}", other_book_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!std::path::Path::new(&file_path).exists());
+
+    // 普通用户无权访问
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/books",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_user_lifecycle() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+    let user_id = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = 'testuser'")
+        .fetch_one(&pool)
+        .await?;
+
+    // 配额：最多一本书
+    let response = make_request(
+        &app,
+        Method::PUT,
+        &format!("/api/admin/users/{}/quota", user_id),
+        serde_json::to_string(&UpdateUserQuotaRequest {
+            storage_quota: None,
+            book_quota: Some(1),
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let book_id = super::upload_test_book(&app, &token, "配额测试", "内容").await;
+    let response = super::upload_test_book_request(&app, &token, "第二本", "内容").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 2005);
+
+    // 用户列表包含存储用量与状态
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/users",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let user = json["data"]["users"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|user| user["username"] == "testuser")
+        .unwrap();
+    assert_eq!(user["status"], "active");
+    assert_eq!(user["book_count"], 1);
+    assert_eq!(user["storage_used"], "内容".len());
+    assert_eq!(user["book_quota"], 1);
+    assert!(user["last_active_at"].is_string());
+
+    // 活跃时间在内存中节流，间隔内的请求不再写入数据库
+    sqlx::query("UPDATE users SET last_active_at = NULL WHERE id = ?")
+        .bind(user_id)
+        .execute(&pool)
+        .await?;
+    make_request(
+        &app,
+        Method::GET,
+        "/api/auth/user_info",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    let last_active_at =
+        sqlx::query_scalar::<_, Option<String>>("SELECT last_active_at FROM users WHERE id = ?")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await?;
+    assert!(last_active_at.is_none());
+
+    // 禁用后已有令牌与登录均被拒绝
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/disable", user_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = make_request(&app, Method::GET, "/api/books", Body::empty(), Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let login_body = serde_json::to_string(&LoginRequest {
+        username: "testuser".to_string(),
+        password: "password123".to_string(),
+        device_id: "test_device".to_string(),
+    })?;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/login",
+        login_body.clone(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // 重新启用
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/enable", user_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(&app, Method::POST, "/api/auth/login", login_body, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 已启用的用户不能重复启用，也不记录审计日志
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/enable", user_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let enable_count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM audit_log WHERE action = 'user.enable'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(enable_count, 1);
+
+    // 删除用户同时删除书籍文件
+    let file_path = sqlx::query_scalar::<_, String>("SELECT file_path FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/admin/users/{}", user_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!std::path::Path::new(&file_path).exists());
+    let book_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM books")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(book_count, 0);
+
+    Ok(())
+}
+
+// 使用邀请码注册，返回响应的 HTTP 状态与错误码
+async fn register_with_invite(
+    app: &Router,
+    username: &str,
+    invite_code: &str,
+) -> Result<(StatusCode, i64)> {
+    let register_body = serde_json::to_string(&CreateUserRequest {
+        username: username.to_string(),
+        password: "password123".to_string(),
+        invite_code: Some(invite_code.to_string()),
+    })?;
+    let response = make_request(app, Method::POST, "/api/auth/register", register_body, None).await;
+    let status = response.status();
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    Ok((status, json["code"].as_i64().unwrap()))
+}
+
+#[tokio::test]
+#[serial]
+async fn test_invite_code_lifecycle() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    // 自定义邀请码，可使用两次
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/admin/invite_code",
+        serde_json::to_string(&CreateInviteCodeRequest {
+            limit_times: 2,
+            code: Some("WELCOME-2025".to_string()),
+            expires_at: Some("2999-01-01T00:00:00+08:00".to_string()),
+            ..Default::default()
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["invite_code"], "WELCOME-2025");
+    assert_eq!(json["data"]["expires_at"], "2998-12-31T16:00:00.000Z");
+
+    // 并发注册不会超过使用次数上限
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..5 {
+        let app = app.clone();
+        tasks.spawn(async move {
+            register_with_invite(&app, &format!("user{}", i), "WELCOME-2025").await
+        });
+    }
+    let succeeded = tasks
+        .join_all()
+        .await
+        .into_iter()
+        .filter(|result| matches!(result, Ok((StatusCode::OK, _))))
+        .count();
+    assert!(succeeded <= 2);
+    let (invite_id, used_times) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT id, used_times FROM invite_codes WHERE code = 'WELCOME-2025'",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(used_times as usize, succeeded);
+
+    // 补足到上限后再注册会失败
+    for i in succeeded..2 {
+        let (status, _) =
+            register_with_invite(&app, &format!("extra{}", i), "WELCOME-2025").await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, code) = register_with_invite(&app, "late", "WELCOME-2025").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(code, 1002);
+
+    // 使用记录
+    let response = make_request(
+        &app,
+        Method::GET,
+        &format!("/api/admin/invite_codes/{}/uses", invite_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["uses"].as_array().unwrap().len(), 2);
+
+    // 批量生成
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/admin/invite_codes/batch",
+        serde_json::to_string(&BatchCreateInviteCodesRequest {
+            count: 3,
+            limit_times: 1,
+            ..Default::default()
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let codes: Vec<String> = serde_json::from_value(json["data"]["invite_codes"].clone())?;
+    assert_eq!(codes.len(), 3);
+
+    // 撤销后无法使用
+    let revoked_id = sqlx::query_scalar::<_, i64>("SELECT id FROM invite_codes WHERE code = ?")
+        .bind(&codes[0])
+        .fetch_one(&pool)
+        .await?;
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/invite_codes/{}/revoke", revoked_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let (status, _) = register_with_invite(&app, "revoked", &codes[0]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 过期后无法使用
+    sqlx::query("UPDATE invite_codes SET expires_at = '2000-01-01T00:00:00.000Z' WHERE code = ?")
+        .bind(&codes[1])
+        .execute(&pool)
+        .await?;
+    let (status, _) = register_with_invite(&app, "expired", &codes[1]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 删除
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/admin/invite_codes/{}", revoked_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let (status, _) = register_with_invite(&app, "deleted", &codes[0]).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_registration_modes() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    let set_mode = |mode| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            let settings_body = serde_json::to_string(&UpdateSettingsRequest {
+                registration_mode: Some(mode),
+                ..Default::default()
+            })
+            .unwrap();
+            let response = make_request(
+                &app,
+                Method::PUT,
+                "/api/admin/settings",
+                settings_body,
+                Some(&admin_token),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    };
+    let register = |username: &str| {
+        let app = app.clone();
+        let register_body = serde_json::to_string(&CreateUserRequest {
+            username: username.to_string(),
+            password: "password123".to_string(),
+            invite_code: None,
+        })
+        .unwrap();
+        async move {
+            let response = make_request(
+                &app,
+                Method::POST,
+                "/api/auth/register",
+                register_body,
+                None,
+            )
+            .await;
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            (status, json)
+        }
+    };
+    let login = |username: &str| {
+        let app = app.clone();
+        let login_body = serde_json::to_string(&LoginRequest {
+            username: username.to_string(),
+            password: "password123".to_string(),
+            device_id: "test_device".to_string(),
+        })
+        .unwrap();
+        async move {
+            make_request(&app, Method::POST, "/api/auth/login", login_body, None)
+                .await
+                .status()
+        }
+    };
+
+    // 审核模式：注册后处于待审核状态，无法登录
+    set_mode(RegistrationMode::Approval).await;
+    let (status, json) = register("pending1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["status"], "pending");
+    assert!(json["data"]["token"].is_null());
+    let pending1 = json["data"]["user_id"].as_i64().unwrap();
+    let (_, json) = register("pending2").await;
+    let pending2 = json["data"]["user_id"].as_i64().unwrap();
+    assert_eq!(login("pending1").await, StatusCode::FORBIDDEN);
+
+    // 待审核的用户不能通过启用接口绕过审核
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/enable", pending1),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(login("pending1").await, StatusCode::FORBIDDEN);
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/registrations",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["registrations"].as_array().unwrap().len(), 2);
+
+    // 通过后可以登录，拒绝后用户被删除
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/registrations/{}/approve", pending1),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(login("pending1").await, StatusCode::OK);
+
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/registrations/{}/reject", pending2),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+        .bind(pending2)
+        .fetch_one(&pool)
+        .await?;
+    assert!(!exists);
+
+    // 已通过的用户不能再被拒绝
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/registrations/{}/reject", pending1),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 仅限邀请码
+    set_mode(RegistrationMode::Invite).await;
+    let (status, json) = register("invite_only").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], 1002);
+
+    // 关闭注册
+    set_mode(RegistrationMode::Closed).await;
+    let (status, json) = register("closed").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(json["code"], 1011);
+
+    // 开放注册
+    set_mode(RegistrationMode::Open).await;
+    let (status, json) = register("open").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["data"]["token"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admin_stats() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    let book_id = super::upload_test_book(&app, &token, "统计测试", "内容").await;
+    super::upload_test_book(&app, &token, "私有书籍", "内容").await;
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    sqlx::query("UPDATE books SET is_public = 1 WHERE id = ?")
+        .bind(book_id)
+        .execute(&pool)
+        .await?;
+    sqlx::query("UPDATE reading_progress SET reading_time = 120 WHERE book_id = ?")
+        .bind(book_id)
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "INSERT INTO reading_daily_stats (user_id, date, reading_time)
+         SELECT id, ?, 120 FROM users WHERE role != 'admin'",
+    )
+    .bind(&today)
+    .execute(&pool)
+    .await?;
+    sqlx::query("UPDATE users SET total_reading_time = 120 WHERE role != 'admin'")
+        .execute(&pool)
+        .await?;
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/stats?days=7",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let stats = &json["data"];
+
+    // 管理员也是用户
+    assert_eq!(stats["users"]["total"], 2);
+    assert_eq!(stats["users"]["active_7d"], 2);
+    assert_eq!(stats["books"]["total"], 2);
+    assert_eq!(stats["books"]["public"], 1);
+    assert_eq!(stats["books"]["total_storage"], 2 * "内容".len());
+    assert_eq!(stats["total_reading_time"], 120);
+
+    // 按日统计补齐了没有数据的日期
+    let uploads = stats["uploads_per_day"].as_array().unwrap();
+    assert_eq!(uploads.len(), 7);
+    assert_eq!(uploads[6]["date"], today);
+    assert_eq!(uploads[6]["value"], 2);
+    assert_eq!(uploads[0]["value"], 0);
+    assert_eq!(stats["reading_time_per_day"][6]["value"], 120);
+
+    assert_eq!(stats["top_books"][0]["book_id"], book_id);
+    assert_eq!(stats["top_books"][0]["reading_time"], 120);
+    assert_eq!(stats["top_books"].as_array().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_audit_log() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    // 登录失败，记录客户端地址
+    let login_body = serde_json::to_string(&LoginRequest {
+        username: "testuser".to_string(),
+        password: "wrong_password".to_string(),
+        device_id: "test_device".to_string(),
+    })?;
+    let mut request = axum::http::Request::builder()
+        .method(Method::POST)
+        .uri("/api/auth/login")
+        .header("Content-Type", "application/json")
+        .body(Body::from(login_body))?;
+    request
+        .extensions_mut()
+        .insert(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+            [192, 0, 2, 1],
+            40000,
+        ))));
+    let response = tower::ServiceExt::oneshot(app.clone(), request).await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 公开后再取消公开书籍
+    let book_id = super::upload_test_book(&app, &token, "审计测试", "内容").await;
+    let response = make_request(
+        &app,
+        Method::PUT,
+        &format!("/api/books/{}", book_id),
+        serde_json::to_string(&UpdateBookRequest {
+            is_public: Some(true),
+            ..Default::default()
+        })?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/books/{}/unpublish", book_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let query = |params: &str| {
+        let app = app.clone();
+        let uri = format!("/api/admin/audit_log?{}", params);
+        let admin_token = admin_token.clone();
+        async move {
+            let response =
+                make_request(&app, Method::GET, &uri, Body::empty(), Some(&admin_token)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()["data"].clone()
+        }
+    };
+
+    let data = query("action=login.success").await;
+    assert_eq!(data["total"], 1);
+    assert_eq!(data["entries"][0]["actor_type"], "user");
+
+    let data = query("action=login.failure").await;
+    assert_eq!(data["total"], 1);
+    let entry = &data["entries"][0];
+    assert_eq!(entry["actor_type"], "anonymous");
+    assert_eq!(entry["ip"], "192.0.2.1");
+    assert_eq!(entry["detail"]["username"], "testuser");
+
+    let data = query(&format!("target_type=book&target_id={}", book_id)).await;
+    let actions: Vec<&str> = data["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, ["book.unpublish", "book.publish"]);
+
+    let data = query("actor_type=admin&since=2000-01-01T00:00:00Z").await;
+    assert!(data["total"].as_i64().unwrap() >= 2);
+    let data = query("since=2999-01-01T00:00:00Z").await;
+    assert_eq!(data["total"], 0);
+
+    // 边界按时间而不是字符串比较，与小数位数无关
+    let data = query("action=login.success").await;
+    let created_at = data["entries"][0]["created_at"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let time = chrono::DateTime::parse_from_rfc3339(&created_at).unwrap();
+    let micros = |offset: i64| {
+        (time + chrono::Duration::microseconds(offset))
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+    };
+    let data = query(&format!("action=login.success&since={}", micros(0))).await;
+    assert_eq!(data["total"], 1);
+    let data = query(&format!("action=login.success&until={}", micros(0))).await;
+    assert_eq!(data["total"], 0);
+    let data = query(&format!("action=login.success&until={}", micros(500))).await;
+    assert_eq!(data["total"], 1);
+    let data = query(&format!("action=login.success&since={}", micros(500))).await;
+    assert_eq!(data["total"], 0);
+
+    // 审计日志只允许追加
+    assert!(sqlx::query("UPDATE audit_log SET action = 'tampered'")
+        .execute(&pool)
+        .await
+        .is_err());
+    assert!(sqlx::query("DELETE FROM audit_log")
+        .execute(&pool)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_ip() -> Result<()> {
+    use axum::extract::{ConnectInfo, FromRequestParts};
+
+    use crate::audit::ClientIp;
+
+    let mut config = super::test_config();
+    config.proxy_auth.trusted_proxies = vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()];
+
+    let client_ip = |peer: [u8; 4], forwarded_for: &[&str]| {
+        let mut builder = axum::http::Request::builder();
+        for value in forwarded_for {
+            builder = builder.header("X-Forwarded-For", *value);
+        }
+        let (mut parts, _) = builder.body(()).unwrap().into_parts();
+        parts
+            .extensions
+            .insert(ConnectInfo(std::net::SocketAddr::from((peer, 40000))));
+        let config = config.clone();
+        async move {
+            ClientIp::from_request_parts(&mut parts, &config)
+                .await
+                .unwrap()
+                .0
+                .unwrap()
+        }
+    };
+
+    // 从右向左跳过受信任的代理，客户端伪造的左侧条目被忽略
+    assert_eq!(
+        client_ip([127, 0, 0, 1], &["203.0.113.9, 198.51.100.7, 10.0.0.2"]).await,
+        "198.51.100.7"
+    );
+    // 多个请求头按顺序拼接
+    assert_eq!(
+        client_ip([127, 0, 0, 1], &["203.0.113.9", "198.51.100.7"]).await,
+        "198.51.100.7"
+    );
+    // 全部是受信任代理时取最左侧的地址
+    assert_eq!(
+        client_ip([127, 0, 0, 1], &["10.0.0.3, 10.0.0.2"]).await,
+        "10.0.0.3"
+    );
+    // 无法解析的条目停在最后一个可信地址
+    assert_eq!(
+        client_ip([127, 0, 0, 1], &["198.51.100.7, bogus, 10.0.0.2"]).await,
+        "10.0.0.2"
+    );
+    // 不受信任的对端忽略请求头
+    assert_eq!(
+        client_ip([192, 0, 2, 1], &["198.51.100.7"]).await,
+        "192.0.2.1"
+    );
+
+    // Unix 域套接字连接没有对端地址，只在开启 trust_unix_socket 时读取请求头
+    let socket_client_ip = |trust_unix_socket: bool| {
+        let (mut parts, _) = axum::http::Request::builder()
+            .header("X-Forwarded-For", "198.51.100.7, 10.0.0.2")
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts.extensions.insert(crate::server::UnixSocketConnection);
+        let mut config = config.clone();
+        config.proxy_auth.trust_unix_socket = trust_unix_socket;
+        async move {
+            ClientIp::from_request_parts(&mut parts, &config)
+                .await
+                .unwrap()
+                .0
+        }
+    };
+    assert_eq!(socket_client_ip(false).await, None);
+    assert_eq!(
+        socket_client_ip(true).await.as_deref(),
+        Some("198.51.100.7")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_roles() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+    let admin_id = json["data"]["user_id"].as_i64().unwrap();
+    assert_eq!(json["data"]["username"], "admin");
+
+    // 管理员使用普通登录接口，旧的管理员登录接口已移除
+    let login_body = serde_json::to_string(&LoginRequest {
+        username: "admin".to_string(),
+        password: "admin123".to_string(),
+        device_id: "test_device".to_string(),
+    })?;
+    let response = make_request(&app, Method::POST, "/api/auth/login", login_body, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/admin/login",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/user_info",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["role"], "uploader");
+    let user_id = json["data"]["user_id"].as_i64().unwrap();
+
+    let set_role = |role: Role| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            make_request(
+                &app,
+                Method::PUT,
+                &format!("/api/admin/users/{}/role", user_id),
+                serde_json::to_string(&UpdateUserRoleRequest { role }).unwrap(),
+                Some(&admin_token),
+            )
+            .await
+            .status()
+        }
+    };
+
+    // 版主可以管理书籍，但不能修改系统设置
+    assert_eq!(set_role(Role::Moderator).await, StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/books",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/settings",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 1005);
+
+    // 只读用户不能上传，也不能访问管理接口
+    assert_eq!(set_role(Role::Reader).await, StatusCode::OK);
+    let response = super::upload_test_book_request(&app, &token, "只读", "内容").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 1012);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/books",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // 不能移除唯一的管理员
+    let response = make_request(
+        &app,
+        Method::PUT,
+        &format!("/api/admin/users/{}/role", admin_id),
+        serde_json::to_string(&UpdateUserRoleRequest {
+            role: Role::Uploader,
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/admin/users/{}/disable", admin_id),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 授予另一位管理员后即可取消原管理员的角色
+    assert_eq!(set_role(Role::Admin).await, StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/settings",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::PUT,
+        &format!("/api/admin/users/{}/role", admin_id),
+        serde_json::to_string(&UpdateUserRoleRequest {
+            role: Role::Uploader,
+        })?,
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 已有管理员时不能再次执行首次设置
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/admin/setup",
+        serde_json::to_string(&AdminSetupRequest {
+            username: Some("another".to_string()),
+            password: "admin123".to_string(),
+        })?,
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_concurrent_admin_setup() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    // 并发执行首次设置时只有一个请求成功
+    let setup = |username: &str| {
+        let body = serde_json::to_string(&AdminSetupRequest {
+            username: Some(username.to_string()),
+            password: "admin123".to_string(),
+        })
+        .unwrap();
+        let app = app.clone();
+        async move {
+            make_request(&app, Method::POST, "/api/auth/admin/setup", body, None)
+                .await
+                .status()
+        }
+    };
+    let statuses = tokio::join!(
+        setup("admin1"),
+        setup("admin2"),
+        setup("admin3"),
+        setup("admin4")
+    );
+    let statuses = [statuses.0, statuses.1, statuses.2, statuses.3];
+    assert_eq!(
+        statuses
+            .iter()
+            .filter(|status| **status == StatusCode::OK)
+            .count(),
+        1,
+        "{:?}",
+        statuses
+    );
+
+    let admin_count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE role = 'admin'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(admin_count, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_announcements() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    let create = |req: AnnouncementRequest| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            let response = make_request(
+                &app,
+                Method::POST,
+                "/api/admin/announcements",
+                serde_json::to_string(&req).unwrap(),
+                Some(&admin_token),
+            )
+            .await;
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            (status, json["data"]["id"].as_i64())
+        }
+    };
+    let list = || {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            let response = make_request(
+                &app,
+                Method::GET,
+                "/api/announcements",
+                Body::empty(),
+                Some(&token),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            json["data"]["announcements"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|announcement| announcement["id"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let (status, current) = create(AnnouncementRequest {
+        title: "欢迎".to_string(),
+        content: "欢迎使用".to_string(),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let current = current.unwrap();
+
+    // 尚未开始与已经结束的公告不展示
+    let (status, _) = create(AnnouncementRequest {
+        title: "预告".to_string(),
+        content: "明年见".to_string(),
+        starts_at: Some("2999-01-01T00:00:00Z".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = create(AnnouncementRequest {
+        title: "过期".to_string(),
+        content: "已结束".to_string(),
+        starts_at: Some("2000-01-01T00:00:00+08:00".to_string()),
+        ends_at: Some("2000-01-02T00:00:00+08:00".to_string()),
+    })
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // 结束时间早于开始时间
+    let (status, _) = create(AnnouncementRequest {
+        title: "无效".to_string(),
+        content: "无效".to_string(),
+        starts_at: Some("2000-01-02T00:00:00Z".to_string()),
+        ends_at: Some("2000-01-01T00:00:00Z".to_string()),
+    })
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    assert_eq!(list().await, [current]);
+
+    // 关闭后不再展示
+    let response = make_request(
+        &app,
+        Method::POST,
+        &format!("/api/announcements/{}/dismiss", current),
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(list().await.is_empty());
+
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        &format!("/api/admin/announcements/{}", current),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/announcements",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["announcements"].as_array().unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_maintenance_mode() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    // 角色以数据库为准，修改后无需重新登录
+    let user_id = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE username = 'testuser'")
+        .fetch_one(&pool)
+        .await?;
+    let set_role = |role: Role| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            make_request(
+                &app,
+                Method::PUT,
+                &format!("/api/admin/users/{}/role", user_id),
+                serde_json::to_string(&UpdateUserRoleRequest { role }).unwrap(),
+                Some(&admin_token),
+            )
+            .await
+            .status()
+        }
+    };
+    assert_eq!(set_role(Role::Moderator).await, StatusCode::OK);
+
+    let set_maintenance = |enabled: bool| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            let settings_body = serde_json::to_string(&UpdateSettingsRequest {
+                maintenance_mode: Some(enabled),
+                maintenance_message: Some("升级中".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+            make_request(
+                &app,
+                Method::PUT,
+                "/api/admin/settings",
+                settings_body,
+                Some(&admin_token),
+            )
+            .await
+            .status()
+        }
+    };
+
+    assert_eq!(set_maintenance(true).await, StatusCode::OK);
+
+    // 普通用户收到 503
+    let response = make_request(&app, Method::GET, "/api/books", Body::empty(), Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 9001);
+    assert_eq!(json["message"], "升级中");
+
+    // 版主也不能访问管理接口
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/books",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // 提升为管理员后立即不受影响，降级后已签发的令牌随之失去豁免
+    assert_eq!(set_role(Role::Admin).await, StatusCode::OK);
+    let response = make_request(&app, Method::GET, "/api/books", Body::empty(), Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(set_role(Role::Moderator).await, StatusCode::OK);
+    let response = make_request(&app, Method::GET, "/api/books", Body::empty(), Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // 管理员不受影响，并且仍然可以登录
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/user_info",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let login_body = serde_json::to_string(&LoginRequest {
+        username: "admin".to_string(),
+        password: "admin123".to_string(),
+        device_id: "test_device".to_string(),
+    })?;
+    let response = make_request(&app, Method::POST, "/api/auth/login", login_body, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(set_maintenance(false).await, StatusCode::OK);
+    let response = make_request(&app, Method::GET, "/api/books", Body::empty(), Some(&token)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_settings_cache_expiry() -> Result<()> {
+    let pool = super::create_test_pool().await?;
+    let set_maintenance = |enabled: bool| {
+        sqlx::query(
+            "INSERT INTO settings (id, maintenance_mode) VALUES (1, ?)
+             ON CONFLICT (id) DO UPDATE SET maintenance_mode = excluded.maintenance_mode",
+        )
+        .bind(enabled)
+        .execute(&pool)
+    };
+
+    // 未过期时使用缓存
+    let cache = SettingsCache::load_with_ttl(&pool, Duration::from_secs(3600)).await?;
+    assert!(!cache.get(&pool).await.maintenance_mode);
+    set_maintenance(true).await?;
+    assert!(!cache.get(&pool).await.maintenance_mode);
+    cache.refresh(&pool).await?;
+    assert!(cache.get(&pool).await.maintenance_mode);
+
+    // 过期后重新读取绕过接口写入的设置
+    let cache = SettingsCache::load_with_ttl(&pool, Duration::ZERO).await?;
+    assert!(cache.get(&pool).await.maintenance_mode);
+    set_maintenance(false).await?;
+    assert!(!cache.get(&pool).await.maintenance_mode);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_consistency_check() -> Result<()> {
+    // 使用独立的书籍目录，避免与其他测试的文件互相影响
+    let mut config = super::test_config();
+    config.storage.book_dir = std::env::temp_dir().join("test_books_consistency");
+    let _ = tokio::fs::remove_dir_all(&config.storage.book_dir).await;
+    let book_dir = config.storage.book_dir.clone();
+    let (app, pool) = super::setup_test_app_with_config(config).await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+
+    let kept = super::upload_test_book(&app, &token, "保留", "第一行\n第二行").await;
+    let missing = super::upload_test_book(&app, &token, "缺失", "内容").await;
+
+    // 制造孤立文件、缺失文件与超出文件末尾的章节
+    let orphan = book_dir.join("orphan.txt");
+    tokio::fs::write(&orphan, "孤立").await?;
+    std::fs::File::options()
+        .write(true)
+        .open(&orphan)?
+        .set_modified(std::time::SystemTime::now() - Duration::from_secs(3600))?;
+    // 刚写入的文件可能属于正在进行的上传，不视为孤立文件
+    let uploading = book_dir.join("uploading.txt");
+    tokio::fs::write(&uploading, "上传中").await?;
+    // 路径写法不同但指向同一文件的记录不视为孤立文件
+    let kept_path: String = sqlx::query_scalar("SELECT file_path FROM books WHERE id = ?")
+        .bind(kept)
+        .fetch_one(&pool)
+        .await?;
+    let kept_file_name = std::path::Path::new(&kept_path).file_name().unwrap();
+    let kept_alias = book_dir
+        .join("..")
+        .join(book_dir.file_name().unwrap())
+        .join(kept_file_name);
+    sqlx::query("UPDATE books SET file_path = ? WHERE id = ?")
+        .bind(kept_alias.to_string_lossy())
+        .bind(kept)
+        .execute(&pool)
+        .await?;
+    let missing_path: String = sqlx::query_scalar("SELECT file_path FROM books WHERE id = ?")
+        .bind(missing)
+        .fetch_one(&pool)
+        .await?;
+    tokio::fs::remove_file(&missing_path).await?;
+    sqlx::query("INSERT INTO chapters (book_id, title, position) VALUES (?, '越界', 99)")
+        .bind(kept)
+        .execute(&pool)
+        .await?;
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/consistency",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let report = &json["data"];
+    assert_eq!(report["orphan_files"], serde_json::json!([orphan]));
+    assert_eq!(report["missing_files"][0]["book_id"], missing);
+    assert_eq!(report["invalid_chapters"][0]["book_id"], kept);
+    assert_eq!(report["invalid_chapters"][0]["line_count"], 2);
+    assert_eq!(report["repaired"], false);
+    assert!(orphan.exists());
+
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/admin/consistency/repair",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!orphan.exists());
+    assert!(uploading.exists());
+
+    let report = crate::storage::check_consistency(&pool, &book_dir, false).await?;
+    assert!(report.is_consistent());
+    let book_ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM books")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(book_ids, [kept]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_backup_and_restore() -> Result<()> {
+    let root = std::env::temp_dir().join("test_backup");
+    let _ = tokio::fs::remove_dir_all(&root).await;
+    let mut config = super::test_config();
+    config.storage.book_dir = root.join("books");
+    config.backup.dir = root.join("backups");
+    config.backup.keep = 1;
+    // 内存数据库无法通过 VACUUM INTO 生成文件快照，这里使用文件数据库
+    config.db.url = root.join("reader.db").to_string_lossy().into_owned();
+    tokio::fs::create_dir_all(&config.storage.book_dir).await?;
+    let pool = crate::db::init_db_pool(&config).await?;
+    crate::db::run_migrations(&pool).await?;
+    let app = crate::app::create_app(pool, config.clone()).await?;
+
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let json = setup_admin(&app).await?;
+    let admin_token = json["data"]["admin_token"].as_str().unwrap().to_string();
+    super::upload_test_book(&app, &token, "备份测试", "第一章\n内容").await;
+
+    let create_backup = || {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            let response = make_request(
+                &app,
+                Method::POST,
+                "/api/admin/backups",
+                Body::empty(),
+                Some(&admin_token),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            json["data"]["name"].as_str().unwrap().to_string()
+        }
+    };
+    create_backup().await;
+    let name = create_backup().await;
+
+    // 只保留最新的一份
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/backups",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    let backups = json["data"]["backups"].as_array().unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0]["name"], name);
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        &format!("/api/admin/backups/{}", name),
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    assert_eq!(body, tokio::fs::read(config.backup.dir.join(&name)).await?);
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/admin/backups/..%2Fsecret.zip",
+        Body::empty(),
+        Some(&admin_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 恢复到另一套目录，书籍路径随之改写，数据库地址按 URL 解析
+    let mut target = config.clone();
+    let restored_db = root.join("restored/reader.db");
+    target.db.url = format!("sqlite://{}", restored_db.display());
+    target.storage.book_dir = root.join("restored/books");
+    target.storage.avatar_dir = root.join("restored/avatars");
+    tokio::fs::create_dir_all(root.join("restored")).await?;
+
+    // 无效的备份不会替换任何数据
+    let invalid = root.join("invalid.zip");
+    tokio::fs::write(&invalid, "not a zip").await?;
+    assert!(crate::backup::restore(&target, &invalid).await.is_err());
+    assert!(!target.storage.book_dir.exists());
+
+    // 替换中途失败时恢复原有数据
+    let mut broken = target.clone();
+    broken.storage.avatar_dir = root.join("missing/avatars");
+    tokio::fs::write(&restored_db, "原有数据库").await?;
+    tokio::fs::create_dir_all(&target.storage.book_dir).await?;
+    tokio::fs::write(target.storage.book_dir.join("原有.txt"), "原有书籍").await?;
+    assert!(
+        crate::backup::restore(&broken, &config.backup.dir.join(&name))
+            .await
+            .is_err()
+    );
+    assert_eq!(tokio::fs::read_to_string(&restored_db).await?, "原有数据库");
+    assert!(target.storage.book_dir.join("原有.txt").exists());
+    assert!(!root.join("restored/reader.db.before-restore").exists());
+    assert!(!root.join("restored/books.before-restore").exists());
+
+    crate::backup::restore(&target, &config.backup.dir.join(&name)).await?;
+    assert!(root.join("restored/books.before-restore/原有.txt").exists());
+    let restored = sqlx::SqlitePool::connect_with(
+        sqlx::sqlite::SqliteConnectOptions::new().filename(&restored_db),
+    )
+    .await?;
+    let (title, file_path): (String, String) = sqlx::query_as("SELECT title, file_path FROM books")
+        .fetch_one(&restored)
+        .await?;
+    assert_eq!(title, "备份测试");
+    assert!(file_path.starts_with(&*target.storage.book_dir.to_string_lossy()));
+    assert_eq!(tokio::fs::read_to_string(&file_path).await?, "第一章\n内容");
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&restored)
+        .await?;
+    assert_eq!(users, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_restore_checks_schema_version() -> Result<()> {
+    let root = std::env::temp_dir().join("test_restore_version");
+    let _ = tokio::fs::remove_dir_all(&root).await;
+    tokio::fs::create_dir_all(&root).await?;
+
+    // 按最初版本的结构生成备份中的数据库
+    let snapshot = root.join("snapshot.db");
+    let pool = sqlx::SqlitePool::connect_with(
+        sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(&snapshot)
+            .create_if_missing(true),
+    )
+    .await?;
+    sqlx::raw_sql(include_str!("fixtures/baseline_schema.sql"))
+        .execute(&pool)
+        .await?;
+    sqlx::raw_sql(
+        "INSERT INTO users (username, password_hash) VALUES ('reader', 'reader_hash');
+         INSERT INTO reading_settings (user_id) VALUES (1);
+         INSERT INTO books (user_id, title, file_path) VALUES (1, '旧书', '/old/books/old.txt');",
+    )
+    .execute(&pool)
+    .await?;
+    pool.close().await;
+
+    let write_archive = |path: std::path::PathBuf| {
+        let snapshot = snapshot.clone();
+        async move {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&path)?);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("reader.db", options)?;
+            std::io::Write::write_all(&mut zip, &tokio::fs::read(&snapshot).await?)?;
+            zip.start_file("books/old.txt", options)?;
+            std::io::Write::write_all(&mut zip, "第一章\n内容".as_bytes())?;
+            zip.finish()?;
+            anyhow::Ok(path)
+        }
+    };
+
+    let mut config = super::test_config();
+    let restored_db = root.join("data/reader.db");
+    config.db.url = format!("sqlite://{}", restored_db.display());
+    config.storage.book_dir = root.join("data/books");
+    config.storage.avatar_dir = root.join("data/avatars");
+    tokio::fs::create_dir_all(root.join("data")).await?;
+
+    // 较旧的备份在恢复时升级到当前结构，并补充书籍文件大小
+    let archive = write_archive(root.join("old.zip")).await?;
+    crate::backup::restore(&config, &archive).await?;
+    let restored = sqlx::SqlitePool::connect_with(
+        sqlx::sqlite::SqliteConnectOptions::new().filename(&restored_db),
+    )
+    .await?;
+    let version = crate::db::schema_version(&mut *restored.acquire().await?).await?;
+    assert_eq!(version, crate::db::SCHEMA_VERSION);
+    let (file_path, file_size): (String, i64) =
+        sqlx::query_as("SELECT file_path, file_size FROM books")
+            .fetch_one(&restored)
+            .await?;
+    assert!(file_path.starts_with(&*config.storage.book_dir.to_string_lossy()));
+    assert_eq!(file_size, "第一章\n内容".len() as i64);
+    let role: String = sqlx::query_scalar("SELECT role FROM users WHERE username = 'reader'")
+        .fetch_one(&restored)
+        .await?;
+    assert_eq!(role, "uploader");
+    restored.close().await;
+
+    // 较新程序生成的备份在替换前被拒绝
+    let pool = sqlx::SqlitePool::connect_with(
+        sqlx::sqlite::SqliteConnectOptions::new().filename(&snapshot),
+    )
+    .await?;
+    sqlx::query(&format!(
+        "PRAGMA user_version = {}",
+        crate::db::SCHEMA_VERSION + 1
+    ))
+    .execute(&pool)
+    .await?;
+    pool.close().await;
+    let archive = write_archive(root.join("new.zip")).await?;
+    let error = crate::backup::restore(&config, &archive).await.unwrap_err();
+    assert!(error.to_string().contains("数据库结构版本"));
+    assert!(!root.join("data/reader.db.before-restore").exists());
+
+    tokio::fs::remove_dir_all(&root).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+async fn test_concurrent_uploads_respect_quota() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    sqlx::query("UPDATE users SET book_quota = 2 WHERE username = 'testuser'")
+        .execute(&pool)
+        .await?;
+
+    // 同时上传多本书，配额检查与写入在同一写事务中，只有两本成功
+    let uploads: Vec<_> = (0..6)
+        .map(|i| {
+            let app = app.clone();
+            let token = token.clone();
+            tokio::spawn(async move {
+                super::upload_test_book_request(&app, &token, &format!("并发{}", i), "内容")
+                    .await
+                    .status()
+            })
+        })
+        .collect();
+    let mut succeeded = 0;
+    for upload in uploads {
+        let status = upload.await?;
+        if status == StatusCode::OK {
+            succeeded += 1;
+        } else {
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+    }
+    assert_eq!(succeeded, 2);
+
+    let book_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM books")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(book_count, 2);
+
+    Ok(())
+}
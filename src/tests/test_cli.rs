@@ -0,0 +1,415 @@
+use anyhow::Result;
+use serial_test::serial;
+
+use super::test_config;
+use crate::{
+    cli::{AdminCommand, Command, InviteCommand, UserCommand},
+    commands,
+    db::{create_test_pool, run_migrations, SCHEMA_VERSION},
+    models::Role,
+    utils::verify_password,
+};
+
+#[tokio::test]
+#[serial]
+async fn test_user_and_invite_commands() -> Result<()> {
+    let pool = create_test_pool().await?;
+    let config = test_config();
+
+    // 迁移可以在已有数据库上重复执行
+    run_migrations(&pool).await?;
+
+    commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Create {
+            username: "root".to_string(),
+            password: Some("root123".to_string()),
+            role: Role::Admin,
+        }),
+    )
+    .await?;
+    commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Create {
+            username: "reader".to_string(),
+            password: Some("reader123".to_string()),
+            role: Role::Reader,
+        }),
+    )
+    .await?;
+    let role: String = sqlx::query_scalar("SELECT role FROM users WHERE username = 'reader'")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(role, "reader");
+
+    // 用户名重复
+    let result = commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Create {
+            username: "reader".to_string(),
+            password: Some("reader123".to_string()),
+            role: Role::Reader,
+        }),
+    )
+    .await;
+    assert!(result.is_err());
+
+    // 未指定用户名时重置最早创建的管理员
+    commands::run(
+        &pool,
+        &config,
+        Command::Admin(AdminCommand::ResetPassword {
+            username: None,
+            password: Some("newpass1".to_string()),
+        }),
+    )
+    .await?;
+    let password_hash: String =
+        sqlx::query_scalar("SELECT password_hash FROM users WHERE username = 'root'")
+            .fetch_one(&pool)
+            .await?;
+    assert!(verify_password("newpass1", &password_hash)?);
+
+    // 只能重置管理员的密码
+    let result = commands::run(
+        &pool,
+        &config,
+        Command::Admin(AdminCommand::ResetPassword {
+            username: Some("reader".to_string()),
+            password: Some("newpass1".to_string()),
+        }),
+    )
+    .await;
+    assert!(result.is_err());
+
+    // 禁用普通用户，唯一的管理员不能禁用
+    commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Disable {
+            username: "reader".to_string(),
+        }),
+    )
+    .await?;
+    let status: String = sqlx::query_scalar("SELECT status FROM users WHERE username = 'reader'")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(status, "disabled");
+    let result = commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Disable {
+            username: "root".to_string(),
+        }),
+    )
+    .await;
+    assert!(result.is_err());
+
+    commands::run(
+        &pool,
+        &config,
+        Command::Invite(InviteCommand::Create {
+            count: 3,
+            limit_times: -1,
+            code: None,
+            description: Some("命令行".to_string()),
+            expires_at: None,
+        }),
+    )
+    .await?;
+    let invite_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM invite_codes WHERE description = '命令行' AND limit_times = -1",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(invite_count, 3);
+
+    // 命令行操作记录为 system
+    let system_actions: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE actor_type = 'system'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(system_actions, 5);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_import_and_reindex_commands() -> Result<()> {
+    let pool = create_test_pool().await?;
+    let mut config = test_config();
+    config.storage.book_dir = std::env::temp_dir().join("test_books_import");
+    let _ = tokio::fs::remove_dir_all(&config.storage.book_dir).await;
+    tokio::fs::create_dir_all(&config.storage.book_dir).await?;
+
+    let import_dir = std::env::temp_dir().join("test_import_source");
+    let _ = tokio::fs::remove_dir_all(&import_dir).await;
+    tokio::fs::create_dir_all(&import_dir).await?;
+    tokio::fs::write(
+        import_dir.join("甲.txt"),
+        "序言\n第一章 开始\n内容\n第二章 继续\n内容\n",
+    )
+    .await?;
+    tokio::fs::write(import_dir.join("乙.TXT"), "只有正文").await?;
+    tokio::fs::write(import_dir.join("说明.md"), "不是书籍").await?;
+
+    commands::run(
+        &pool,
+        &config,
+        Command::User(UserCommand::Create {
+            username: "owner".to_string(),
+            password: Some("owner123".to_string()),
+            role: Role::Uploader,
+        }),
+    )
+    .await?;
+    commands::run(
+        &pool,
+        &config,
+        Command::Import {
+            dir: import_dir.clone(),
+            user: "owner".to_string(),
+            public: true,
+        },
+    )
+    .await?;
+
+    let books: Vec<(i64, String, bool)> =
+        sqlx::query_as("SELECT id, title, is_public FROM books ORDER BY title")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(books.len(), 2);
+    assert!(books.iter().all(|(_, _, is_public)| *is_public));
+    let book_id = books.iter().find(|(_, title, _)| title == "甲").unwrap().0;
+    let chapter_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chapters WHERE book_id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+    assert!(chapter_count > 0);
+
+    // 重新识别时保留已有章节的ID，多余的章节被删除
+    let chapter_ids = || {
+        sqlx::query_scalar::<_, i64>("SELECT id FROM chapters WHERE book_id = ? ORDER BY id")
+            .bind(book_id)
+            .fetch_all(&pool)
+    };
+    let original_ids = chapter_ids().await?;
+    sqlx::query("INSERT INTO chapters (book_id, title, position) VALUES (?, '多余', 99)")
+        .bind(book_id)
+        .execute(&pool)
+        .await?;
+    commands::run(
+        &pool,
+        &config,
+        Command::Reindex {
+            book_id: Some(book_id),
+        },
+    )
+    .await?;
+    assert_eq!(chapter_ids().await?, original_ids);
+
+    // 章节被清空后重新识别
+    sqlx::query("DELETE FROM chapters").execute(&pool).await?;
+    commands::run(&pool, &config, Command::Reindex { book_id: None }).await?;
+    let reindexed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chapters WHERE book_id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(reindexed, chapter_count);
+
+    // 导入到不存在的用户
+    let result = commands::run(
+        &pool,
+        &config,
+        Command::Import {
+            dir: import_dir,
+            user: "nobody".to_string(),
+            public: false,
+        },
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_migrate_baseline_database() -> Result<()> {
+    // 按最初版本的结构创建数据库并写入数据
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await?;
+    sqlx::raw_sql(include_str!("fixtures/baseline_schema.sql"))
+        .execute(&pool)
+        .await?;
+    let book_path = std::env::temp_dir().join("test_migrate_book.txt");
+    tokio::fs::write(&book_path, "第一章\n内容").await?;
+    sqlx::raw_sql(&format!(
+        "INSERT INTO users (username, password_hash) VALUES ('reader', 'reader_hash');
+         INSERT INTO reading_settings (user_id) VALUES (1);
+         INSERT INTO admin (password_hash) VALUES ('admin_hash');
+         INSERT INTO settings (id, invite_code_required) VALUES (1, 1);
+         INSERT INTO books (user_id, title, file_path) VALUES (1, '旧书', '{}');",
+        book_path.display()
+    ))
+    .execute(&pool)
+    .await?;
+
+    run_migrations(&pool).await?;
+    let config = test_config();
+    commands::run(&pool, &config, Command::Migrate).await?;
+
+    // 管理员并入用户表，设置与书籍按原有数据补充
+    let (role, password_hash): (String, String) =
+        sqlx::query_as("SELECT role, password_hash FROM users WHERE username = 'admin'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(role, "admin");
+    assert_eq!(password_hash, "admin_hash");
+    let role: String = sqlx::query_scalar("SELECT role FROM users WHERE username = 'reader'")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(role, "uploader");
+    let registration_mode: String =
+        sqlx::query_scalar("SELECT registration_mode FROM settings WHERE id = 1")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(registration_mode, "invite");
+    let file_size: i64 = sqlx::query_scalar("SELECT file_size FROM books")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(file_size, "第一章\n内容".len() as i64);
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(version, SCHEMA_VERSION);
+
+    // 升级后的结构与新建的数据库一致
+    let fresh = create_test_pool().await?;
+    let describe = |pool: sqlx::SqlitePool| async move {
+        let names: Vec<(String, String)> = sqlx::query_as(
+            "SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY type, name",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let mut columns = Vec::new();
+        for (kind, table) in &names {
+            if kind == "table" {
+                let mut table_columns: Vec<(String, String, bool, Option<String>)> =
+                    sqlx::query_as(
+                        "SELECT name, type, \"notnull\", dflt_value FROM pragma_table_info(?)",
+                    )
+                    .bind(table)
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+                table_columns.sort();
+                columns.push((table.clone(), table_columns));
+            }
+        }
+        (names, columns)
+    };
+    assert_eq!(describe(pool.clone()).await, describe(fresh).await);
+
+    // 重复执行不做任何修改，版本高于程序时拒绝启动
+    run_migrations(&pool).await?;
+    sqlx::query("PRAGMA user_version = 99")
+        .execute(&pool)
+        .await?;
+    assert!(run_migrations(&pool).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_migrate_admin_with_taken_username() -> Result<()> {
+    // 用户表中已有 admin 与 admin_legacy 时，原管理员使用下一个可用的用户名
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await?;
+    sqlx::raw_sql(include_str!("fixtures/baseline_schema.sql"))
+        .execute(&pool)
+        .await?;
+    sqlx::raw_sql(
+        "INSERT INTO users (username, password_hash) VALUES ('admin', 'user_hash');
+         INSERT INTO users (username, password_hash) VALUES ('admin_legacy', 'user_hash');
+         INSERT INTO admin (password_hash) VALUES ('admin_hash');",
+    )
+    .execute(&pool)
+    .await?;
+
+    run_migrations(&pool).await?;
+
+    let (username, password_hash): (String, String) =
+        sqlx::query_as("SELECT username, password_hash FROM users WHERE role = 'admin'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(username, "admin_legacy_2");
+    assert_eq!(password_hash, "admin_hash");
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_adopt_legacy_database_file() -> Result<()> {
+    // 旧版本在工作目录下创建了名为 sqlite:reader.db 的文件
+    let root = std::env::temp_dir().join("test_legacy_db");
+    let _ = tokio::fs::remove_dir_all(&root).await;
+    tokio::fs::create_dir_all(&root).await?;
+    let legacy = sqlx::SqlitePool::connect_with(
+        sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(root.join("sqlite:reader.db"))
+            .create_if_missing(true),
+    )
+    .await?;
+    run_migrations(&legacy).await?;
+    crate::db::create_user(&mut *legacy.acquire().await?, "legacy", "hash", "active").await?;
+    legacy.close().await;
+
+    // 相对路径相对于工作目录解析，连接池关闭后再切换回原目录
+    let init_in_root = || async {
+        let mut config = test_config();
+        config.db.url = "sqlite:reader.db".to_string();
+        let current_dir = std::env::current_dir()?;
+        std::env::set_current_dir(&root)?;
+        let result = crate::db::init_db_pool(&config).await;
+        if let Ok(pool) = &result {
+            pool.close().await;
+        }
+        std::env::set_current_dir(current_dir)?;
+        result.map(|_| ())
+    };
+    let mut config = test_config();
+    config.db.url = format!("sqlite:{}", root.join("reader.db").display());
+    init_in_root().await?;
+
+    // 旧文件被移动到解析后的位置，数据保持不变
+    assert!(root.join("reader.db").is_file());
+    assert!(!root.join("sqlite:reader.db").exists());
+    let pool = crate::db::init_db_pool(&config).await?;
+    let username = sqlx::query_scalar::<_, String>("SELECT username FROM users")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(username, "legacy");
+    pool.close().await;
+
+    // 新文件已存在时不再处理旧文件
+    tokio::fs::write(root.join("sqlite:reader.db"), "旧文件").await?;
+    init_in_root().await?;
+    assert_eq!(
+        tokio::fs::read_to_string(root.join("sqlite:reader.db")).await?,
+        "旧文件"
+    );
+    let pool = crate::db::init_db_pool(&config).await?;
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 1);
+    pool.close().await;
+
+    tokio::fs::remove_dir_all(&root).await?;
+    Ok(())
+}
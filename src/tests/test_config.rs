@@ -0,0 +1,140 @@
+use anyhow::Result;
+
+use crate::config::{load_config_with_env, ConfigOverrides};
+
+fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_layered_config() -> Result<()> {
+    let dir = std::env::temp_dir().join("test_layered_config");
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::create_dir_all(&dir).await?;
+    let config_path = dir.join("config.toml");
+    tokio::fs::write(
+        &config_path,
+        r#"
+[server]
+host = "127.0.0.1"
+port = 4000
+
+[db]
+url = "reader.db"
+max_connections = 5
+
+[storage]
+book_dir = "books"
+
+[jwt]
+secret = "file_secret"
+expiration = 3600
+"#,
+    )
+    .await?;
+
+    // 配置文件 < 环境变量 < 命令行参数
+    let overrides = ConfigOverrides {
+        config_path: Some(config_path),
+        port: Some(5000),
+        ..Default::default()
+    };
+    let config = load_config_with_env(
+        &overrides,
+        env(&[
+            ("READER_JWT_SECRET", "env_secret"),
+            ("READER_SERVER_HOST", "::"),
+            ("READER_SERVER_PORT", "4500"),
+            ("READER_PROXY_AUTH_TRUSTED_PROXIES", "10.0.0.0/8, 127.0.0.1"),
+            ("READER_PROXY_AUTH_TRUST_UNIX_SOCKET", "true"),
+            ("READER_SERVER_UNIX_SOCKET_MODE", "0660"),
+            ("READER_BACKUP_INTERVAL_HOURS", "6"),
+            (
+                "READER_SERVER_TLS",
+                r#"{"cert_path": "cert.pem", "key_path": "key.pem"}"#,
+            ),
+            ("UNRELATED", "1"),
+        ]),
+    )
+    .await?;
+
+    assert_eq!(config.jwt.secret, "env_secret");
+    assert_eq!(config.jwt.expiration, 3600);
+    assert_eq!(config.server.host, "::");
+    assert_eq!(config.server.port, 5000);
+    assert_eq!(
+        config.proxy_auth.trusted_proxies,
+        ["10.0.0.0/8", "127.0.0.1"]
+    );
+    assert!(config.proxy_auth.trust_unix_socket);
+    assert_eq!(config.server.unix_socket_mode, "0660");
+    assert_eq!(config.backup.interval_hours, Some(6));
+    assert_eq!(
+        config.server.tls.unwrap().cert_path,
+        std::path::PathBuf::from("cert.pem")
+    );
+
+    // 类型不符的环境变量报错
+    assert!(
+        load_config_with_env(&overrides, env(&[("READER_SERVER_PORT", "abc")]))
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_first_run_generates_jwt_secret() -> Result<()> {
+    let dir = std::env::temp_dir().join("test_first_run_config");
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::create_dir_all(&dir).await?;
+
+    for file_name in ["config.toml", "config.json"] {
+        let overrides = ConfigOverrides {
+            config_path: Some(dir.join(file_name)),
+            ..Default::default()
+        };
+        let config = load_config_with_env(&overrides, Vec::new()).await?;
+        assert_eq!(config.jwt.secret.len(), 64);
+        assert_ne!(
+            config.jwt.secret,
+            crate::config::Config::default().jwt.secret
+        );
+
+        // 再次启动时读取已生成的配置文件
+        let reloaded = load_config_with_env(&overrides, Vec::new()).await?;
+        assert_eq!(reloaded.jwt.secret, config.jwt.secret);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_breached_list_loaded_at_startup() -> Result<()> {
+    let dir = std::env::temp_dir().join("test_breached_list_config");
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    tokio::fs::create_dir_all(&dir).await?;
+    let overrides = ConfigOverrides {
+        config_path: Some(dir.join("config.toml")),
+        ..Default::default()
+    };
+    let list_path = dir.join("breached.txt");
+    let vars = env(&[("READER_PASSWORD_BREACHED_LIST", list_path.to_str().unwrap())]);
+
+    // 列表文件不存在时无法启动
+    assert!(load_config_with_env(&overrides, vars.clone())
+        .await
+        .is_err());
+
+    // 启动时加载并统一为小写
+    tokio::fs::write(&list_path, "Hunter2\n\n  letmein  \n").await?;
+    let config = load_config_with_env(&overrides, vars).await?;
+    let passwords = &config.password.breached_passwords;
+    assert_eq!(passwords.len(), 2);
+    assert!(passwords.contains("hunter2") && passwords.contains("letmein"));
+
+    Ok(())
+}
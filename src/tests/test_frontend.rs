@@ -0,0 +1,114 @@
+use anyhow::Result;
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use http_body_util::BodyExt;
+use rust_embed::RustEmbed;
+use tower::ServiceExt;
+
+use super::make_request;
+use crate::{error::ErrorCode, frontend};
+
+// 模拟前端构建产物
+#[derive(RustEmbed)]
+#[folder = "src/tests/frontend"]
+struct TestAssets;
+
+fn frontend_app() -> Router {
+    Router::new().fallback(frontend::serve::<TestAssets>)
+}
+
+#[tokio::test]
+async fn test_frontend_spa_fallback() -> Result<()> {
+    let app = frontend_app();
+    let index = TestAssets::get("index.html").unwrap().data;
+
+    // 根路径与前端页面路径都返回入口，且不缓存
+    for uri in ["/", "/books/42", "/admin/users"] {
+        let response = make_request(&app, Method::GET, uri, Body::empty(), None).await;
+        assert_eq!(response.status(), StatusCode::OK, "{uri}");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()?
+            .starts_with("text/html"));
+        let body = response.into_body().collect().await?.to_bytes();
+        assert_eq!(body.as_ref(), index.as_ref());
+    }
+
+    // 缺失的静态文件与未匹配的接口返回 404
+    let response = make_request(&app, Method::GET, "/assets/missing.js", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = make_request(&app, Method::GET, "/api/unknown", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], ErrorCode::NotFound.code());
+
+    let response = make_request(&app, Method::POST, "/books/42", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_frontend_assets_caching() -> Result<()> {
+    let app = frontend_app();
+    let uri = "/assets/index-4f2a9c1e.js";
+
+    // 带哈希的资源长期缓存
+    let response = make_request(&app, Method::GET, uri, Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers().clone();
+    assert_eq!(
+        headers[header::CACHE_CONTROL],
+        "public, max-age=31536000, immutable"
+    );
+    assert!(headers[header::CONTENT_TYPE]
+        .to_str()?
+        .contains("javascript"));
+    assert!(headers.get(header::CONTENT_ENCODING).is_none());
+    let body = response.into_body().collect().await?.to_bytes();
+    assert_eq!(
+        body.as_ref(),
+        TestAssets::get("assets/index-4f2a9c1e.js")
+            .unwrap()
+            .data
+            .as_ref()
+    );
+
+    // ETag 未变化时返回 304
+    let etag = headers[header::ETAG].to_str()?;
+    let request = Request::builder()
+        .uri(uri)
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())?;
+    let response = app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // 支持 gzip 时返回预压缩的版本，不支持 br 时不选择 br
+    let request = Request::builder()
+        .uri(uri)
+        .header(header::ACCEPT_ENCODING, "br;q=0, gzip, deflate")
+        .body(Body::empty())?;
+    let response = app.oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(response.headers()[header::VARY], "Accept-Encoding");
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()?
+        .contains("javascript"));
+    assert_ne!(response.headers()[header::ETAG], etag);
+    let body = response.into_body().collect().await?.to_bytes();
+    assert_eq!(
+        body.as_ref(),
+        TestAssets::get("assets/index-4f2a9c1e.js.gz")
+            .unwrap()
+            .data
+            .as_ref()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, StatusCode},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use http_body_util::BodyExt;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use reqwest::Url;
+use serial_test::serial;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+
+use super::{make_request, setup_test_app_with_config, test_config};
+use crate::{
+    auth::{create_token, Claims},
+    config::OidcConfig,
+    models::OidcCallbackRequest,
+};
+
+const CLIENT_ID: &str = "reader";
+const CLIENT_SECRET: &str = "mock_client_secret";
+
+// 已签发但尚未兑换的授权码
+struct PendingCode {
+    code_challenge: String,
+    nonce: String,
+    subject: String,
+}
+
+// 模拟身份提供方的状态
+#[derive(Clone, Default)]
+struct MockIssuer {
+    issuer: String,
+    codes: Arc<Mutex<HashMap<String, PendingCode>>>,
+    // ID 令牌中的 preferred_username 声明
+    preferred_username: Arc<Mutex<String>>,
+    // 发现文档被请求的次数
+    discovery_requests: Arc<AtomicUsize>,
+}
+
+// 启动本地模拟身份提供方，返回其签发者地址
+async fn start_mock_issuer() -> Result<MockIssuer> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let issuer = MockIssuer {
+        issuer: format!("http://{}", listener.local_addr()?),
+        preferred_username: Arc::new(Mutex::new("oidcuser".to_string())),
+        ..Default::default()
+    };
+
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/token", post(token))
+        .with_state(issuer.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    Ok(issuer)
+}
+
+async fn discovery(State(issuer): State<MockIssuer>) -> Json<serde_json::Value> {
+    issuer.discovery_requests.fetch_add(1, Ordering::SeqCst);
+    Json(serde_json::json!({
+        "issuer": issuer.issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer.issuer),
+        "token_endpoint": format!("{}/token", issuer.issuer),
+        "id_token_signing_alg_values_supported": ["HS256"],
+    }))
+}
+
+async fn token(
+    State(issuer): State<MockIssuer>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let pending = issuer
+        .codes
+        .lock()
+        .unwrap()
+        .remove(&form["code"])
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    // 校验 PKCE
+    let expected = URL_SAFE_NO_PAD.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+    if expected != pending.code_challenge || form["client_secret"] != CLIENT_SECRET {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let id_token = encode(
+        &Header::default(),
+        &serde_json::json!({
+            "iss": issuer.issuer,
+            "aud": CLIENT_ID,
+            "sub": pending.subject,
+            "nonce": pending.nonce,
+            "preferred_username": *issuer.preferred_username.lock().unwrap(),
+            "iat": now,
+            "exp": now + 300,
+        }),
+        &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+    )
+    .unwrap();
+
+    Ok(Json(serde_json::json!({
+        "access_token": "mock_access_token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    })))
+}
+
+// 模拟用户在身份提供方完成授权，返回回调请求
+fn approve(issuer: &MockIssuer, authorize_url: &str, subject: &str) -> OidcCallbackRequest {
+    let url = Url::parse(authorize_url).unwrap();
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(params["code_challenge_method"], "S256");
+
+    let code = crate::utils::generate_uuid();
+    issuer.codes.lock().unwrap().insert(
+        code.clone(),
+        PendingCode {
+            code_challenge: params["code_challenge"].clone(),
+            nonce: params["nonce"].clone(),
+            subject: subject.to_string(),
+        },
+    );
+
+    OidcCallbackRequest {
+        code,
+        state: params["state"].clone(),
+    }
+}
+
+async fn response_json(response: axum::response::Response) -> Result<serde_json::Value> {
+    let body = response.into_body().collect().await?.to_bytes();
+    Ok(serde_json::from_slice(&body)?)
+}
+
+fn mock_oidc_config(issuer: &MockIssuer) -> OidcConfig {
+    OidcConfig {
+        issuer: issuer.issuer.clone(),
+        client_id: CLIENT_ID.to_string(),
+        client_secret: Some(CLIENT_SECRET.to_string()),
+        redirect_uri: "http://localhost:5173/oidc/callback".to_string(),
+        scopes: vec!["openid".to_string()],
+        username_claim: "preferred_username".to_string(),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_oidc_login_and_link() -> Result<()> {
+    let issuer = start_mock_issuer().await?;
+    let mut config = test_config();
+    config.oidc = Some(mock_oidc_config(&issuer));
+    let (app, pool) = setup_test_app_with_config(config).await?;
+
+    // 首次登录自动创建用户
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/oidc/authorize",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await?;
+    let callback = approve(
+        &issuer,
+        json["data"]["authorize_url"].as_str().unwrap(),
+        "subject-1",
+    );
+
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await?;
+    assert_eq!(json["data"]["username"], "oidcuser");
+    assert!(json["data"]["token"].is_string());
+
+    // 同一个 state 不能重复使用
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 已有用户绑定新的身份
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let start_link = || async {
+        let response = make_request(
+            &app,
+            Method::POST,
+            "/api/auth/oidc/link",
+            Body::empty(),
+            Some(&token),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = response_json(response).await.unwrap();
+        approve(
+            &issuer,
+            json["data"]["authorize_url"].as_str().unwrap(),
+            "subject-2",
+        )
+    };
+
+    // 绑定回调必须由发起绑定的用户完成
+    let callback = start_link().await;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let other_token = json["data"]["token"].as_str().unwrap().to_string();
+    let callback = start_link().await;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        Some(&other_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let callback = start_link().await;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await?;
+    assert_eq!(json["data"]["username"], "testuser");
+
+    let identity_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM user_identities")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(identity_count, 2);
+
+    // 发现文档在缓存有效期内只获取一次
+    assert_eq!(issuer.discovery_requests.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_oidc_non_ascii_username() -> Result<()> {
+    let issuer = start_mock_issuer().await?;
+    *issuer.preferred_username.lock().unwrap() = "阅读爱好者的长长的名字".to_string();
+    let mut config = test_config();
+    config.oidc = Some(mock_oidc_config(&issuer));
+    let (app, _pool) = setup_test_app_with_config(config).await?;
+
+    // 两个不同的身份使用相同的声明登录，第二个用户名追加后缀
+    let mut usernames = Vec::new();
+    for subject in ["subject-1", "subject-2"] {
+        let response = make_request(
+            &app,
+            Method::GET,
+            "/api/auth/oidc/authorize",
+            Body::empty(),
+            None,
+        )
+        .await;
+        let json = response_json(response).await?;
+        let callback = approve(
+            &issuer,
+            json["data"]["authorize_url"].as_str().unwrap(),
+            subject,
+        );
+        let response = make_request(
+            &app,
+            Method::POST,
+            "/api/auth/oidc/callback",
+            serde_json::to_string(&callback)?,
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = response_json(response).await?;
+        usernames.push(json["data"]["username"].as_str().unwrap().to_string());
+    }
+
+    // 按字节截断且不拆开字符，结果满足注册时的用户名规则
+    assert_eq!(usernames[0], "阅读爱好者");
+    assert!(usernames[1].starts_with("阅读爱好者_"));
+    for username in &usernames {
+        assert!(crate::utils::validate_username(username).is_ok());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_oidc_user_deletes_account_after_login() -> Result<()> {
+    let issuer = start_mock_issuer().await?;
+    let mut config = test_config();
+    config.oidc = Some(mock_oidc_config(&issuer));
+    let (app, pool) = setup_test_app_with_config(config.clone()).await?;
+
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/oidc/authorize",
+        Body::empty(),
+        None,
+    )
+    .await;
+    let json = response_json(response).await?;
+    let callback = approve(
+        &issuer,
+        json["data"]["authorize_url"].as_str().unwrap(),
+        "subject-1",
+    );
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/oidc/callback",
+        serde_json::to_string(&callback)?,
+        None,
+    )
+    .await;
+    let json = response_json(response).await?;
+    let token = json["data"]["token"].as_str().unwrap().to_string();
+    let user_id = json["data"]["user_id"].as_i64().unwrap();
+
+    // 令牌签发已久时必须提供密码
+    let mut claims = Claims::new_user(user_id, &config);
+    claims.iat -= 3600;
+    let stale_token = create_token(&claims, &config)?;
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        "/api/auth/account",
+        "{}",
+        Some(&stale_token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 刚通过 OIDC 登录时无需密码即可注销
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        "/api/auth/account",
+        "{}",
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await?;
+    assert!(!exists);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_oidc_rejects_mismatched_issuer() -> Result<()> {
+    let issuer = start_mock_issuer().await?;
+
+    // 末尾的斜杠不影响比较
+    let mut config = test_config();
+    let mut oidc = mock_oidc_config(&issuer);
+    oidc.issuer = format!("{}/", issuer.issuer);
+    config.oidc = Some(oidc);
+    let (app, _pool) = setup_test_app_with_config(config).await?;
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/oidc/authorize",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 发现文档声明的 issuer 与配置不一致时拒绝
+    let mut config = test_config();
+    let mut oidc = mock_oidc_config(&issuer);
+    oidc.issuer = issuer.issuer.replace("127.0.0.1", "localhost");
+    config.oidc = Some(oidc);
+    let (app, _pool) = setup_test_app_with_config(config).await?;
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/oidc/authorize",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    Ok(())
+}
@@ -81,3 +81,45 @@ async fn test_reading_settings() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_daily_stats_use_user_timezone() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+    let token = register_test_user_and_login(&app).await?;
+    let book_id = super::upload_test_book(&app, &token, "时区", "第一章 开始\n正文").await;
+
+    // UTC+14 时区，一天中大部分时间与 UTC 不在同一日期
+    sqlx::query("UPDATE users SET timezone = 'Pacific/Kiritimati'")
+        .execute(&pool)
+        .await?;
+    // 模拟 10 秒前在同一设备上发送过心跳
+    let last_read_at = (chrono::Utc::now() - chrono::Duration::seconds(10))
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    sqlx::query("UPDATE reading_progress SET last_read_at = ?, last_device_id = 'test'")
+        .bind(&last_read_at)
+        .execute(&pool)
+        .await?;
+
+    let heartbeat = serde_json::json!({ "book_id": book_id, "position": 0, "device_id": "test" });
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/reading/heartbeat",
+        serde_json::to_vec(&heartbeat)?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let date = sqlx::query_scalar::<_, String>("SELECT date FROM reading_daily_stats")
+        .fetch_one(&pool)
+        .await?;
+    let expected = chrono::Utc::now()
+        .with_timezone(&chrono_tz::Pacific::Kiritimati)
+        .format("%Y-%m-%d")
+        .to_string();
+    assert_eq!(date, expected);
+
+    Ok(())
+}
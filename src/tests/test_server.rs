@@ -0,0 +1,434 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::Result;
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use http_body_util::BodyExt;
+use serial_test::serial;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    sync::oneshot,
+};
+use tower::ServiceExt;
+
+use super::{make_request, setup_test_app, test_config};
+use crate::{error::ErrorCode, openapi, server, storage};
+
+#[tokio::test]
+async fn test_resolve_listen_addr() -> Result<()> {
+    let mut config = test_config().server;
+    config.port = 8080;
+
+    for (host, expected) in [
+        ("127.0.0.1", "127.0.0.1:8080"),
+        ("0.0.0.0", "0.0.0.0:8080"),
+        ("::", "[::]:8080"),
+        ("[::1]", "[::1]:8080"),
+    ] {
+        config.host = host.to_string();
+        assert_eq!(server::resolve_addr(&config).await?.to_string(), expected);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_unix_socket_listener() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+    let socket_path = std::env::temp_dir().join("reader-test.sock");
+    let mut config = test_config().server;
+    config.unix_socket = Some(socket_path.clone());
+
+    let server = tokio::spawn(async move { server::serve(app, &config).await });
+
+    let mut stream = connect_unix(&socket_path).await;
+
+    stream
+        .write_all(
+            b"GET /api/admin/check_setup HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains("\"is_setup\":false"));
+
+    // 套接字默认只允许属主与同组用户连接
+    let mode = std::os::unix::fs::PermissionsExt::mode(
+        &tokio::fs::metadata(&socket_path).await?.permissions(),
+    );
+    assert_eq!(mode & 0o777, 0o660);
+
+    server.abort();
+
+    // 套接字连接没有对端地址，默认不信任代理请求头，即使 127.0.0.1 是受信任代理
+    for (trust_unix_socket, expected) in [(false, "HTTP/1.1 401"), (true, "HTTP/1.1 200")] {
+        let mut config = test_config();
+        config.proxy_auth.enabled = true;
+        config.proxy_auth.trust_unix_socket = trust_unix_socket;
+        config.server.unix_socket = Some(socket_path.clone());
+        config.server.unix_socket_mode = "600".to_string();
+        let (app, _pool) = super::setup_test_app_with_config(config.clone()).await?;
+        let server = tokio::spawn(async move { server::serve(app, &config.server).await });
+
+        let mut stream = connect_unix(&socket_path).await;
+        stream
+            .write_all(
+                b"GET /api/auth/user_info HTTP/1.1\r\nHost: localhost\r\nRemote-User: proxyuser\r\nConnection: close\r\n\r\n",
+            )
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        assert!(response.starts_with(expected), "{}", response);
+        let mode = std::os::unix::fs::PermissionsExt::mode(
+            &tokio::fs::metadata(&socket_path).await?.permissions(),
+        );
+        assert_eq!(mode & 0o777, 0o600);
+
+        server.abort();
+    }
+
+    // 路径上是普通文件时拒绝启动，不删除该文件
+    let (app, _pool) = setup_test_app().await?;
+    let file_path = std::env::temp_dir().join("reader-test-not-socket");
+    tokio::fs::write(&file_path, "data").await?;
+    let mut config = test_config().server;
+    config.unix_socket = Some(file_path.clone());
+    assert!(server::serve(app, &config).await.is_err());
+    assert_eq!(tokio::fs::read_to_string(&file_path).await?, "data");
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_graceful_shutdown() -> Result<()> {
+    let socket_path = std::env::temp_dir().join("reader-test-shutdown.sock");
+    let mut config = test_config().server;
+    config.unix_socket = Some(socket_path.clone());
+    config.shutdown_timeout = 1;
+
+    let app = Router::new()
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                "done"
+            }),
+        )
+        .route(
+            "/stuck",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                "never"
+            }),
+        );
+
+    // 进行中的请求在关闭时仍能完成
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server_config = config.clone();
+    let server_app = app.clone();
+    let server = tokio::spawn(async move {
+        server::serve_with_shutdown(server_app, &server_config, async {
+            let _ = shutdown_rx.await;
+        })
+        .await
+    });
+    let mut stream = connect_unix(&socket_path).await;
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown_tx.send(()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.ends_with("done"));
+    tokio::time::timeout(Duration::from_secs(5), server).await???;
+    assert!(!socket_path.exists());
+
+    // 超过等待时间的请求被中止
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        server::serve_with_shutdown(app, &config, async {
+            let _ = shutdown_rx.await;
+        })
+        .await
+    });
+    let mut stream = connect_unix(&socket_path).await;
+    stream
+        .write_all(b"GET /stuck HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown_tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server).await???;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pending_upload_cleanup() -> Result<()> {
+    let book_dir = std::env::temp_dir().join("test_books_pending");
+    tokio::fs::create_dir_all(&book_dir).await?;
+
+    // 未保存到数据库就被丢弃的文件会被删除
+    let pending = storage::write_book_file(&book_dir, "内容").await?;
+    let path = pending.path().to_string();
+    assert!(Path::new(&path).exists());
+    drop(pending);
+    assert!(!Path::new(&path).exists());
+
+    let pending = storage::write_book_file(&book_dir, "内容").await?;
+    let path = pending.path().to_string();
+    pending.keep();
+    assert!(Path::new(&path).exists());
+
+    tokio::fs::remove_dir_all(&book_dir).await?;
+    Ok(())
+}
+
+// 等待 Unix 套接字就绪后连接
+async fn connect_unix(socket_path: &Path) -> UnixStream {
+    for _ in 0..50 {
+        if let Ok(stream) = UnixStream::connect(socket_path).await {
+            return stream;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Unix 套接字未就绪");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_health_and_metrics() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+
+    let response = make_request(&app, Method::GET, "/healthz", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(&app, Method::GET, "/readyz", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 上传一本书，读取两次正文并发送心跳
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let book_id = super::upload_test_book(&app, &token, "指标", "第一章 开始\n正文内容").await;
+    for _ in 0..2 {
+        let response = make_request(
+            &app,
+            Method::GET,
+            &format!("/api/books/{}/content?position=0", book_id),
+            Body::empty(),
+            Some(&token),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let heartbeat = serde_json::json!({ "book_id": book_id, "position": 0, "device_id": "test" });
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/reading/heartbeat",
+        serde_json::to_vec(&heartbeat)?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = make_request(&app, Method::GET, "/metrics", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let metrics = String::from_utf8(body.to_vec())?;
+    assert!(metrics.contains(
+        "reader_http_requests_total{method=\"GET\",route=\"/api/books/{book_id}/content\",status=\"200\"} 2"
+    ));
+    assert!(metrics.contains(
+        "reader_http_request_duration_seconds_count{method=\"GET\",route=\"/api/books/{book_id}/content\"} 2"
+    ));
+    assert!(metrics.contains("reader_content_cache_hits_total 1\n"));
+    assert!(metrics.contains("reader_content_cache_misses_total 1\n"));
+    assert!(metrics.contains("reader_active_readers 1\n"));
+    assert!(metrics.contains(&format!(
+        "reader_upload_bytes_total {}\n",
+        "第一章 开始\n正文内容".len()
+    )));
+    assert!(metrics.contains("reader_db_pool_max_connections"));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_readyz_unavailable() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+    let mut config = test_config();
+    config.storage.book_dir = std::env::temp_dir().join("test_books_missing");
+    let _ = tokio::fs::remove_dir_all(&config.storage.book_dir).await;
+    let app_missing_dir = crate::app::create_app(pool, config).await?;
+
+    // 书籍目录不存在时未就绪，但进程仍然存活
+    let response = make_request(
+        &app_missing_dir,
+        Method::GET,
+        "/readyz",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 9002);
+
+    let response = make_request(
+        &app_missing_dir,
+        Method::GET,
+        "/healthz",
+        Body::empty(),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = make_request(&app, Method::GET, "/readyz", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_request_id() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    // 未提供时由服务端生成
+    let response = make_request(&app, Method::GET, "/healthz", Body::empty(), None).await;
+    let request_id = response.headers()["x-request-id"].to_str()?;
+    assert_eq!(request_id.len(), 36);
+
+    // 内部错误的响应中带有客户端提供的请求ID
+    let token = super::test_user::register_test_user_and_login(&app).await?;
+    let book_id = super::upload_test_book(&app, &token, "请求ID", "正文").await;
+    let file_path: String = sqlx::query_scalar("SELECT file_path FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+    tokio::fs::remove_file(&file_path).await?;
+
+    let request = Request::builder()
+        .uri(format!("/api/books/{}/content?position=0", book_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("X-Request-Id", "client-request-1")
+        .body(Body::empty())?;
+    let response = app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()["x-request-id"], "client-request-1");
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 9999);
+    assert_eq!(json["request_id"], "client-request-1");
+
+    // 其他错误不附带请求ID
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/books/9999",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert!(json.get("request_id").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_code_table() -> Result<()> {
+    // 文档中的错误码表须与代码中的定义一致，比较时忽略对齐用的空格
+    let normalize = |table: &str| -> Vec<Vec<String>> {
+        table
+            .lines()
+            .filter(|line| line.starts_with('|') && !line.starts_with("| -"))
+            .map(|line| {
+                line.trim_matches('|')
+                    .split('|')
+                    .map(|cell| cell.trim().to_string())
+                    .collect()
+            })
+            .collect()
+    };
+
+    let doc =
+        std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("doc/接口文档.md"))?;
+    let section = doc
+        .split("## 8. 错误码")
+        .nth(1)
+        .expect("文档缺少错误码章节");
+    assert_eq!(normalize(section), normalize(&ErrorCode::markdown_table()));
+
+    // 错误码不能重复
+    let mut codes: Vec<i32> = ErrorCode::ALL.iter().map(|code| code.code()).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), ErrorCode::ALL.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_openapi_spec() -> Result<()> {
+    // 接口变更后需同步更新 doc/openapi.json，设置 UPDATE_OPENAPI=1 运行测试即可重新生成
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("doc/openapi.json");
+    let spec = serde_json::to_value(openapi::openapi())?;
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(&path, serde_json::to_string_pretty(&spec)? + "\n")?;
+    }
+    let committed: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    assert!(
+        spec == committed,
+        "接口文档与 doc/openapi.json 不一致，确认变更后使用 UPDATE_OPENAPI=1 重新生成"
+    );
+
+    // 每个接口都有成功响应与统一的错误响应
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let responses = &operation["responses"];
+            assert!(responses.get("200").is_some(), "{method} {path}");
+            assert!(responses.get("default").is_some(), "{method} {path}");
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_openapi_routes() -> Result<()> {
+    let (app, _) = setup_test_app().await?;
+
+    let response = make_request(&app, Method::GET, "/api/openapi.json", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let spec: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(spec, serde_json::to_value(openapi::openapi())?);
+    assert!(spec["paths"]["/api/books/random_public"]["get"].is_object());
+    assert!(spec["components"]["securitySchemes"]["bearer"].is_object());
+
+    // 交互式文档页面内嵌同一份规范
+    let response = make_request(&app, Method::GET, "/api/docs", Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()?
+        .starts_with("text/html"));
+    let body = response.into_body().collect().await?.to_bytes();
+    assert!(String::from_utf8_lossy(&body).contains("Reader Server API"));
+
+    Ok(())
+}
@@ -1,16 +1,23 @@
+use std::net::SocketAddr;
+
 use anyhow::Result;
 use axum::{
     body::Body,
-    http::{Method, StatusCode},
+    extract::ConnectInfo,
+    http::{Method, Request, StatusCode},
     response::Response,
     Router,
 };
 use bytes::Bytes;
 use http_body_util::BodyExt;
 use serial_test::serial;
+use tower::ServiceExt;
 
 use super::{make_request, setup_test_app};
-use crate::models::{ChangePasswordRequest, CreateUserRequest, LoginRequest};
+use crate::models::{
+    ChangePasswordRequest, ChangeUsernameRequest, CreateUserRequest, LoginRequest,
+    UpdateProfileRequest,
+};
 
 /// 创建测试用户
 /// 用户名：testuser
@@ -120,9 +127,25 @@ async fn test_user_info_and_password_change() -> Result<()> {
     assert_eq!(json["data"]["username"], "testuser");
     assert_eq!(json["data"]["user_id"], 1);
 
+    // 有本地密码的用户即使刚登录也必须提供旧密码
+    assert_eq!(json["data"]["has_password"], true);
+    let change_pwd_body = serde_json::to_string(&ChangePasswordRequest {
+        old_password: None,
+        new_password: "newpassword123".to_string(),
+    })?;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/change_password",
+        change_pwd_body,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
     // 测试修改密码
     let change_pwd_body = serde_json::to_string(&ChangePasswordRequest {
-        old_password: "password123".to_string(),
+        old_password: Some("password123".to_string()),
         new_password: "newpassword123".to_string(),
     })?;
 
@@ -161,3 +184,445 @@ async fn test_user_info_and_password_change() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_proxy_header_auth() -> Result<()> {
+    let mut config = super::test_config();
+    config.proxy_auth.enabled = true;
+    config.proxy_auth.trusted_proxies = vec!["10.0.0.0/8".to_string()];
+    let (app, pool) = super::setup_test_app_with_config(config).await?;
+
+    let user_info_request = |peer: [u8; 4]| {
+        let mut req = Request::builder()
+            .method(Method::GET)
+            .uri("/api/auth/user_info")
+            .header("Remote-User", "proxyuser")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+        req
+    };
+
+    // 受信任代理：自动创建用户并认证
+    let response = app
+        .clone()
+        .oneshot(user_info_request([10, 1, 2, 3]))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["username"], "proxyuser");
+
+    // 再次请求不会重复创建用户
+    let response = app
+        .clone()
+        .oneshot(user_info_request([10, 1, 2, 3]))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE username = 'proxyuser'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(count, 1);
+
+    // 非受信任地址的请求头被忽略
+    let response = app
+        .clone()
+        .oneshot(user_info_request([192, 168, 1, 1]))
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 自动创建同样受注册模式限制：设置管理员后为邀请码模式，代理无法提供邀请码
+    super::test_admin::setup_admin(&app).await?;
+    let mut req = user_info_request([10, 1, 2, 3]);
+    req.headers_mut()
+        .insert("Remote-User", "otheruser".parse().unwrap());
+    let response = app.clone().oneshot(req).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM users WHERE username = 'otheruser')",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert!(!exists);
+
+    // 代理创建的用户没有本地密码，由代理认证确认身份后可以注销账号
+    let has_password = sqlx::query_scalar::<_, bool>(
+        "SELECT has_password FROM users WHERE username = 'proxyuser'",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert!(!has_password);
+    let mut req = user_info_request([10, 1, 2, 3]);
+    *req.method_mut() = Method::DELETE;
+    *req.uri_mut() = "/api/auth/account".parse().unwrap();
+    req.headers_mut()
+        .insert("Content-Type", "application/json".parse().unwrap());
+    *req.body_mut() = Body::from("{}");
+    let response = app.clone().oneshot(req).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE username = 'proxyuser'")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(count, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_export_and_delete_account() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = register_test_user_and_login(&app).await?;
+    let book_id = super::upload_test_book(&app, &token, "测试书籍", "第一章 开始\n正文").await;
+    let file_path = sqlx::query_scalar::<_, String>("SELECT file_path FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_one(&pool)
+        .await?;
+
+    // 头像同样导出
+    let avatar_path = std::env::temp_dir().join("test_export_avatar.png");
+    tokio::fs::write(&avatar_path, b"\x89PNG\r\n\x1a\n").await?;
+    sqlx::query("UPDATE users SET avatar_path = ? WHERE username = 'testuser'")
+        .bind(avatar_path.to_string_lossy())
+        .execute(&pool)
+        .await?;
+
+    // 导出数据
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/export",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/zip");
+    let body = response.into_body().collect().await?.to_bytes();
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(body.to_vec()))?;
+    for name in [
+        "user.json",
+        "books.json",
+        "reading_progress.json",
+        "reading_settings.json",
+        "stats.json",
+    ] {
+        assert!(archive.by_name(name).is_ok(), "缺少 {}", name);
+    }
+    let mut book_content = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name(&format!("books/{}.txt", book_id))?,
+        &mut book_content,
+    )?;
+    assert!(book_content.contains("第一章"));
+    let mut avatar = Vec::new();
+    std::io::Read::read_to_end(&mut archive.by_name("avatar.png")?, &mut avatar)?;
+    assert_eq!(avatar, b"\x89PNG\r\n\x1a\n");
+
+    // 刚登录也必须提供密码
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        "/api/auth/account",
+        "{}",
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 密码错误时不能注销
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        "/api/auth/account",
+        serde_json::json!({ "password": "wrongpassword" }).to_string(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 注销账号
+    let response = make_request(
+        &app,
+        Method::DELETE,
+        "/api/auth/account",
+        serde_json::json!({ "password": "password123" }).to_string(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 书籍文件与数据库记录均已删除
+    assert!(!std::path::Path::new(&file_path).exists());
+    let book_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM books")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(book_count, 0);
+
+    // 旧令牌失效
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/user_info",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_profile_and_username_change() -> Result<()> {
+    let (app, pool) = setup_test_app().await?;
+
+    let token = register_test_user_and_login(&app).await?;
+
+    // 更新个人资料
+    let profile_body = serde_json::to_string(&UpdateProfileRequest {
+        display_name: Some("测试用户".to_string()),
+        timezone: Some("Asia/Shanghai".to_string()),
+        locale: Some("en".to_string()),
+    })?;
+    let response = make_request(
+        &app,
+        Method::PUT,
+        "/api/auth/profile",
+        profile_body,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 无效时区
+    let profile_body = serde_json::to_string(&UpdateProfileRequest {
+        timezone: Some("Mars/Olympus".to_string()),
+        ..Default::default()
+    })?;
+    let response = make_request(
+        &app,
+        Method::PUT,
+        "/api/auth/profile",
+        profile_body,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 400);
+    assert_eq!(json["details"]["field"], "timezone");
+
+    // 上传头像
+    let upload_avatar = |content: &[u8]| {
+        let boundary = "----reader-test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"avatar.png\"\r\n\
+             Content-Type: image/png\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(content);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/api/auth/avatar")
+            .header("Authorization", format!("Bearer {}", token))
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        app.clone().oneshot(req)
+    };
+    let response = upload_avatar(b"\x89PNG\r\n\x1a\n").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 更换头像后删除旧文件
+    let avatar_path = || {
+        sqlx::query_scalar::<_, String>("SELECT avatar_path FROM users WHERE username = 'testuser'")
+            .fetch_one(&pool)
+    };
+    let old_avatar = avatar_path().await?;
+    let response = upload_avatar(b"\x89PNG\r\n\x1a\n").await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!std::path::Path::new(&old_avatar).exists());
+    assert!(std::path::Path::new(&avatar_path().await?).exists());
+
+    // 扩展名正确但内容不是图片
+    let response = upload_avatar(b"<script>alert(1)</script>").await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // 修改用户名
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/change_username",
+        serde_json::to_string(&ChangeUsernameRequest {
+            new_username: "renamed".to_string(),
+        })?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 资料已更新
+    let response = make_request(
+        &app,
+        Method::GET,
+        "/api/auth/user_info",
+        Body::empty(),
+        Some(&token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["data"]["username"], "renamed");
+    assert_eq!(json["data"]["display_name"], "测试用户");
+    assert_eq!(json["data"]["timezone"], "Asia/Shanghai");
+    assert_eq!(json["data"]["locale"], "en");
+    assert!(json["data"]["created_at"]
+        .as_str()
+        .unwrap()
+        .ends_with("+08:00"));
+    let avatar_url = json["data"]["avatar_url"].as_str().unwrap().to_string();
+
+    // 头像不需要认证即可获取
+    let response = make_request(&app, Method::GET, &avatar_url, Body::empty(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/png");
+
+    // 用户名已被占用
+    register_test_user(&app).await?;
+    let response = make_request(
+        &app,
+        Method::POST,
+        "/api/auth/change_username",
+        serde_json::to_string(&ChangeUsernameRequest {
+            new_username: "testuser".to_string(),
+        })?,
+        Some(&token),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_localized_messages() -> Result<()> {
+    let (app, _pool) = setup_test_app().await?;
+    let token = register_test_user_and_login(&app).await?;
+
+    let login_body = serde_json::to_string(&LoginRequest {
+        username: "testuser".to_string(),
+        password: "wrong-password".to_string(),
+        device_id: "test_device".to_string(),
+    })?;
+    let login = |accept_language: Option<&str>| {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/auth/login")
+            .header("Content-Type", "application/json");
+        if let Some(accept_language) = accept_language {
+            request = request.header("Accept-Language", accept_language);
+        }
+        app.clone()
+            .oneshot(request.body(Body::from(login_body.clone())).unwrap())
+    };
+
+    // 未指定语言时使用中文
+    let response = login(None).await?;
+    assert_eq!(response.headers()["content-language"], "zh-CN");
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 1001);
+    assert!(json["message"].as_str().unwrap().contains("密码"));
+
+    // 按权重选择受支持的语言
+    let response = login(Some("fr;q=1.0, en-US;q=0.9, zh;q=0.5")).await?;
+    assert_eq!(response.headers()["content-language"], "en");
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["code"], 1001);
+    assert_eq!(json["message"], "Incorrect username or password");
+
+    // 个人资料中的语言优先于请求头
+    let profile_body = serde_json::to_string(&UpdateProfileRequest {
+        locale: Some("en".to_string()),
+        ..Default::default()
+    })?;
+    let response = make_request(
+        &app,
+        Method::PUT,
+        "/api/auth/profile",
+        profile_body,
+        Some(&token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["message"], "更新成功");
+
+    let profile_body = serde_json::to_string(&UpdateProfileRequest {
+        timezone: Some("Mars/Olympus".to_string()),
+        ..Default::default()
+    })?;
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri("/api/auth/profile")
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Accept-Language", "zh-CN")
+        .body(Body::from(profile_body))?;
+    let response = app.clone().oneshot(request).await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["content-language"], "en");
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["message"], "Invalid request parameters: timezone");
+
+    let response = make_request(
+        &app,
+        Method::PUT,
+        "/api/auth/profile",
+        serde_json::to_string(&UpdateProfileRequest::default())?,
+        Some(&token),
+    )
+    .await;
+    let body = response.into_body().collect().await?.to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["message"], "Updated");
+
+    Ok(())
+}
+
+#[test]
+fn test_message_translations() {
+    use crate::i18n::{Locale, Message};
+
+    // 接口返回的提示只能是 Message 中的文本，每一条都需要中文与英文
+    for message in Message::ALL {
+        let zh = message.text(Locale::ZhCn);
+        let en = message.text(Locale::En);
+        assert!(!zh.is_empty(), "{:?}", message);
+        assert!(!en.is_empty(), "{:?}", message);
+        assert!(en.is_ascii(), "{:?} 缺少英文翻译: {}", message, en);
+    }
+
+    // 每条文本的标识与中文文案都不重复
+    for (index, message) in Message::ALL.iter().enumerate() {
+        for other in &Message::ALL[index + 1..] {
+            assert_ne!(message, other);
+            assert_ne!(message.text(Locale::ZhCn), other.text(Locale::ZhCn));
+        }
+    }
+}
@@ -61,6 +61,71 @@ pub fn generate_invite_code() -> String {
     code
 }
 
+// 验证用户名格式
+pub fn validate_username(username: &str) -> Result<(), AppError> {
+    if username.len() < 2 || username.len() > 20 {
+        return Err(AppError::invalid_field(
+            "username",
+            "用户名长度必须在 2-20 个字符之间",
+        ));
+    }
+
+    Ok(())
+}
+
+// 验证时区名称（IANA 时区，如 Asia/Shanghai）
+pub fn validate_timezone(timezone: &str) -> Result<(), AppError> {
+    timezone
+        .parse::<chrono_tz::Tz>()
+        .map(|_| ())
+        .map_err(|_| AppError::invalid_field("timezone", format!("无效的时区: {}", timezone)))
+}
+
+// 验证语言标签（如 zh-CN、en）
+pub fn validate_locale(locale: &str) -> Result<(), AppError> {
+    if !regex!(r"^[a-zA-Z]{2,3}(-[a-zA-Z0-9]{2,8})*$").is_match(locale) {
+        return Err(AppError::invalid_field(
+            "locale",
+            format!("无效的语言: {}", locale),
+        ));
+    }
+
+    Ok(())
+}
+
+// 将 UTC 时间字符串转换为指定时区的时间，无法解析时原样返回
+pub fn localize_time(time: &str, timezone: &str) -> String {
+    match (
+        time.parse::<chrono::DateTime<chrono::Utc>>(),
+        timezone.parse::<chrono_tz::Tz>(),
+    ) {
+        (Ok(time), Ok(tz)) => time
+            .with_timezone(&tz)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        _ => time.to_string(),
+    }
+}
+
+// 指定时区中的日期（YYYY-MM-DD），时区无效时使用 UTC 日期
+pub fn local_date(time: chrono::DateTime<chrono::Utc>, timezone: &str) -> String {
+    match timezone.parse::<chrono_tz::Tz>() {
+        Ok(tz) => time.with_timezone(&tz).format("%Y-%m-%d").to_string(),
+        Err(_) => time.format("%Y-%m-%d").to_string(),
+    }
+}
+
+// 转义 LIKE 模式中的通配符，配合 ESCAPE '\' 使用
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 // 哈希密码
 pub fn hash_password(password: &str) -> Result<String, AppError> {
     use argon2::{